@@ -75,8 +75,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
     tracing_subscriber::fmt().with_env_filter(filter).init();
 
-    // Initialize the actor system
-    let engine_options = EngineOptions::builder().endpoint("ws://localhost:9123".into()).build();
+    // Initialize the actor system, overridable with BIOMA_CONFIG or BIOMA_* variables
+    let defaults = EngineOptions::builder().endpoint("ws://localhost:9123".into()).build();
+    let engine_options = ConfigSources::default().load(&defaults)?;
+    engine_options.validate()?;
     let engine = Engine::connect(engine_options).await?;
 
     // Create echo ID
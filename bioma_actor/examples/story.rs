@@ -1,8 +1,8 @@
 use std::borrow::Cow;
 
 use bioma_actor::{
-    Actor, ActorContext, ActorError, ActorId, Engine, EngineOptions, Message, SendOptions, SpawnExistsOptions,
-    SpawnOptions, SystemActorError,
+    Actor, ActorContext, ActorError, ActorId, ConfigSources, Engine, EngineOptions, Message, SendOptions,
+    SpawnExistsOptions, SpawnOptions, SystemActorError,
 };
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
@@ -232,7 +232,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
     tracing_subscriber::fmt().with_env_filter(filter).init();
 
-    let options = EngineOptions::builder().endpoint(Cow::Borrowed("ws://localhost:9123")).build();
+    // Load the engine options, overridable with BIOMA_CONFIG or BIOMA_* variables
+    let defaults = EngineOptions::builder().endpoint(Cow::Borrowed("ws://localhost:9123")).build();
+    let options = ConfigSources::default().load(&defaults)?;
+    options.validate()?;
 
     // Initialize the actor system
     let engine = Engine::connect(options).await?;
@@ -449,7 +449,7 @@ async fn main() -> Result<(), SystemActorError> {
     color_backtrace::install();
     color_backtrace::BacktracePrinter::new().message("BOOM! 💥").install(color_backtrace::default_output_stream());
 
-    let engine = Engine::test().await?;
+    let engine = Engine::test_with(EngineOptions::load(&ConfigSources::default())?).await?;
 
     // Setup the main actor
     let (mut main_actor_ctx, mut main_actor) =
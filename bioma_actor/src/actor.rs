@@ -1,21 +1,37 @@
-use crate::engine::{Engine, Record};
-use futures::{future, Stream, StreamExt};
+use crate::backend::{Recent, ReplyFrameStream};
+use crate::dead_letter::DeadLetterReason;
+use crate::directory::ActorStatus;
+use crate::engine::Engine;
+use crate::lease::{Lease, LeaseHandle};
+use crate::local::Deferred;
+use crate::message_name::{decode_message, message_name};
+use crate::schedule::Schedule;
+use crate::supervisor::ExitReason;
+use crate::trace::{SpanKind, TraceContext};
+use futures::{future, FutureExt, Stream, StreamExt};
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use surrealdb::RecordIdKey;
 use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tokio_util::sync::CancellationToken;
 // use std::any::type_name;
+use std::borrow::Cow;
+use std::collections::{BinaryHeap, HashMap};
 use std::fmt::Debug;
 use std::future::Future;
 use std::pin::Pin;
-use std::{borrow::Cow, sync::atomic::AtomicU64};
-use surrealdb::{sql::Id, value::RecordId, Action, Notification};
-use tracing::{debug, error, trace};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use surrealdb::{sql::Id, value::RecordId};
+use tracing::{debug, error, trace, Instrument};
 
 // Constants for database table names
-const DB_TABLE_ACTOR: &str = "actor";
-const DB_TABLE_MESSAGE: &str = "message";
-const DB_TABLE_REPLY: &str = "reply";
+pub(crate) const DB_TABLE_ACTOR: &str = "actor";
+pub(crate) const DB_TABLE_MESSAGE: &str = "message";
+pub(crate) const DB_TABLE_REPLY: &str = "reply";
+pub(crate) const DB_TABLE_CANCEL: &str = "cancel";
 
 /// Implement this trait to define custom actor error types
 pub trait ActorError: std::error::Error + Debug + Send + Sync + From<SystemActorError> {}
@@ -132,16 +148,76 @@ pub enum SystemActorError {
     #[error("Actor tag already registered: {0}")]
     ActorTagAlreadyRegistered(Cow<'static, str>),
 
+    /// The lease of an actor could not be acquired in time.
+    ///
+    /// Occurs when spawning an actor that runs in another engine for longer than
+    /// `SpawnOptions::lease_timeout`.
+    #[error("Lease of {0} not acquired within {1:?}")]
+    LeaseTimeout(ActorId, std::time::Duration),
+
     /// Referenced actor tag is not registered in the system.
     ///
     /// This occurs when trying to interact with an actor type
     /// that hasn't been registered with the actor system.
     #[error("Actor tag not found: {0}")]
     ActorTagNotFound(Cow<'static, str>),
+
+    /// An actor returned an error from its main loop.
+    ///
+    /// Used to carry a custom actor error across task boundaries,
+    /// typically when a supervised child fails.
+    #[error("Actor failed: {0}")]
+    ActorFailed(Cow<'static, str>),
+
+    /// A supervisor exceeded its restart limit.
+    ///
+    /// Occurs when a supervised child is restarted more often than allowed
+    /// by the supervisor options within the configured time window.
+    #[error("Restart limit exceeded for {0}: {1} restarts")]
+    RestartLimitExceeded(ActorId, usize),
+
+    /// A message schedule is invalid.
+    ///
+    /// Occurs when scheduling a message with a malformed cron expression,
+    /// a zero period, or a schedule that never fires.
+    #[error("Invalid schedule: {0}")]
+    InvalidSchedule(Cow<'static, str>),
+
+    /// The database could not be reached at startup.
+    ///
+    /// Occurs when `Engine::connect` keeps failing for longer than
+    /// `ReconnectOptions::max_startup_wait`, with the last connection error.
+    #[error("Could not connect within {0:?}: {1}")]
+    ConnectTimeout(std::time::Duration, Cow<'static, str>),
+
+    /// The configuration could not be loaded or is invalid.
+    ///
+    /// Occurs in `ConfigSources::load` and `EngineOptions::load` on a malformed file,
+    /// environment variable or override, or on an invalid endpoint or directory.
+    #[error("Invalid config: {0}")]
+    InvalidConfig(Cow<'static, str>),
+
+    /// A feature is not supported by the engine backend.
+    ///
+    /// Occurs when using watches, the journal, retention, schedules or topics on a backend
+    /// other than SurrealDB, such as `MemoryBackend`.
+    #[error("Unsupported by the engine backend: {0}")]
+    Unsupported(Cow<'static, str>),
 }
 
 impl ActorError for SystemActorError {}
 
+lazy_static! {
+    static ref MESSAGE_ULIDS: Mutex<ulid::Generator> = Mutex::new(ulid::Generator::new());
+}
+
+/// A ULID greater than any previous one of this process, so mailboxes keep the send order of
+/// messages with the same priority, even within the same millisecond
+fn message_ulid() -> ulid::Ulid {
+    let mut generator = MESSAGE_ULIDS.lock().unwrap_or_else(|e| e.into_inner());
+    generator.generate().unwrap_or_else(|_| ulid::Ulid::new())
+}
+
 /// The message frame that is sent between actors
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FrameMessage {
@@ -156,9 +232,55 @@ pub struct FrameMessage {
     /// Message content
     #[serde(default)]
     pub msg: Value,
+    /// Schema version of the message content, see `MessageName`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// Topic the message was published on, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<Cow<'static, str>>,
+    /// Mailbox priority, higher values are received first
+    #[serde(default)]
+    pub priority: i32,
+    /// Delivered in memory to an actor in the same engine, see `LocalDelivery`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub local: bool,
+    /// Trace of the request this message belongs to, with the span that sent it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<TraceContext>,
 }
 
 impl FrameMessage {
+    /// Create a new message frame with a fresh ULID
+    pub(crate) fn new<MT: MessageType>(tx: &ActorId, rx: &ActorId, message: &MT) -> Result<Self, SystemActorError> {
+        let msg = serde_json::to_value(message)?;
+        let (name, version) = message_name::<MT>();
+        let mut frame = Self::from_parts(name, tx.record_id(), rx.record_id(), msg);
+        frame.version = version;
+        Ok(frame)
+    }
+
+    /// Create a new message frame with a fresh ULID from already serialized content
+    pub(crate) fn from_parts(name: Cow<'static, str>, tx: RecordId, rx: RecordId, msg: Value) -> Self {
+        let id = RecordId::from_table_key(DB_TABLE_MESSAGE, message_ulid().to_string());
+        Self::from_parts_with_id(id, name, tx, rx, msg)
+    }
+
+    /// Create a message frame with the given id from already serialized content
+    pub(crate) fn from_parts_with_id(
+        id: RecordId,
+        name: Cow<'static, str>,
+        tx: RecordId,
+        rx: RecordId,
+        msg: Value,
+    ) -> Self {
+        Self { id, name, tx, rx, msg, version: None, topic: None, priority: 0, local: false, trace: None }
+    }
+
+    /// Get the message id
+    pub fn id(&self) -> &RecordId {
+        &self.id
+    }
+
     /// Check if this frame matches a specific message type
     /// and deserialize it into the message type.
     ///
@@ -168,17 +290,31 @@ impl FrameMessage {
     ///
     /// # Returns
     ///
-    /// * `Some(M)` if the frame's name matches the type name of `M` and deserialization succeeds.
+    /// * `Some(M)` if the frame's name matches the name of `M` and deserialization succeeds.
+    ///   For message types with a `MessageName`, this includes the stable name,
+    ///   its aliases and the type name, and content from older versions is upgraded first.
     /// * `None` if the frame's name doesn't match or deserialization fails.
     pub fn is<M>(&self) -> Option<M>
     where
-        M: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+        M: MessageType,
     {
-        if self.name == std::any::type_name::<M>() {
-            serde_json::from_value(self.msg.clone()).ok()
-        } else {
-            None
-        }
+        self.decode::<M>().and_then(Result::ok)
+    }
+
+    /// Deserialize this frame into a specific message type, if it has the name of that type.
+    ///
+    /// Unlike `is`, content that can't be decoded is told apart from another message name.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Ok(M))` if the frame's name matches the name of `M` and deserialization succeeds.
+    /// * `Some(Err(_))` if the frame's name matches but the content can't be decoded.
+    /// * `None` if the frame's name doesn't match.
+    pub fn decode<M>(&self) -> Option<Result<M, SystemActorError>>
+    where
+        M: MessageType,
+    {
+        decode_message::<M>(&self.name, self.version, &self.msg)
     }
 }
 
@@ -251,6 +387,9 @@ pub struct FrameReply {
     /// Error message
     #[serde(default)]
     pub err: Value,
+    /// Trace of the request, with the span that processed the message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<TraceContext>,
 }
 
 impl FrameReply {
@@ -263,12 +402,27 @@ impl FrameReply {
         rx: surrealdb::RecordId,
         msg: Value,
     ) -> Self {
-        Self { id: ReplyId::new_chunk(id, chunk_num), name, tx, rx, msg, err: Value::Null }
+        Self { id: ReplyId::new_chunk(id, chunk_num), name, tx, rx, msg, err: Value::Null, trace: None }
     }
 
     /// Creates a new reply frame for a final response
     pub fn new_final(id: String, name: Cow<'static, str>, tx: surrealdb::RecordId, rx: surrealdb::RecordId) -> Self {
-        Self { id: ReplyId::new_final(id), name, tx, rx, msg: Value::Null, err: Value::Null }
+        Self { id: ReplyId::new_final(id), name, tx, rx, msg: Value::Null, err: Value::Null, trace: None }
+    }
+
+    /// The database record id of this reply
+    pub(crate) fn reply_id(&self) -> surrealdb::RecordId {
+        self.id.to_record_id()
+    }
+
+    /// Whether this is the final reply of the stream
+    pub fn is_final(&self) -> bool {
+        self.id.chunk.is_none()
+    }
+
+    /// The chunk number, `None` for the final reply
+    pub(crate) fn chunk(&self) -> Option<u64> {
+        self.id.chunk
     }
 }
 
@@ -289,6 +443,91 @@ pub type ReplyStream<T> = Pin<Box<dyn Stream<Item = Result<T, SystemActorError>>
 /// - New messages as they arrive
 pub type MessageStream = Pin<Box<dyn Stream<Item = Result<FrameMessage, SystemActorError>> + Send>>;
 
+/// A message waiting in the mailbox, ordered by priority and then by arrival.
+struct QueuedMessage {
+    priority: i32,
+    seq: u64,
+    frame: FrameMessage,
+}
+
+impl PartialEq for QueuedMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for QueuedMessage {}
+
+impl PartialOrd for QueuedMessage {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedMessage {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Higher priority first, then first in first out
+        self.priority.cmp(&other.priority).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[derive(Default)]
+struct Mailbox {
+    queue: BinaryHeap<QueuedMessage>,
+    seq: u64,
+}
+
+impl Mailbox {
+    fn push(&mut self, frame: FrameMessage) {
+        self.seq += 1;
+        self.queue.push(QueuedMessage { priority: frame.priority, seq: self.seq, frame });
+    }
+
+    fn pop(&mut self) -> Option<FrameMessage> {
+        self.queue.pop().map(|queued| queued.frame)
+    }
+}
+
+/// Merges the unreplied backlog and the live stream into a stream yielding the highest priority
+/// message that has arrived so far.
+fn prioritized<S>(backlog: Vec<FrameMessage>, live: S) -> impl Stream<Item = Result<FrameMessage, SystemActorError>>
+where
+    S: Stream<Item = Result<FrameMessage, SystemActorError>> + Send + 'static,
+{
+    let mut mailbox = Mailbox::default();
+    for frame in backlog {
+        mailbox.push(frame);
+    }
+
+    futures::stream::unfold((Box::pin(live), mailbox, false), |(mut live, mut mailbox, mut done)| async move {
+        loop {
+            // Queue everything that already arrived, so it competes on priority
+            while !done {
+                match live.next().now_or_never() {
+                    Some(Some(Ok(frame))) => mailbox.push(frame),
+                    Some(Some(Err(e))) => return Some((Err(e), (live, mailbox, done))),
+                    Some(None) => done = true,
+                    None => break,
+                }
+            }
+
+            if let Some(frame) = mailbox.pop() {
+                return Some((Ok(frame), (live, mailbox, done)));
+            }
+            if done {
+                return None;
+            }
+
+            // Nothing queued, wait for the next message
+            match live.next().await {
+                Some(Ok(frame)) => mailbox.push(frame),
+                Some(Err(e)) => return Some((Err(e), (live, mailbox, done))),
+                None => done = true,
+            }
+        }
+    })
+}
+
 /// A trait for types that can be sent as messages between actors.
 ///
 /// This trait is automatically implemented for types that meet the requirements:
@@ -296,6 +535,8 @@ pub type MessageStream = Pin<Box<dyn Stream<Item = Result<FrameMessage, SystemAc
 /// - Serialize/Deserialize for transport
 /// - Send + Sync for thread safety
 ///
+/// Frames are named after the Rust type name, implement `MessageName` for a stable name.
+///
 /// # Example
 ///
 /// ```rust
@@ -307,9 +548,9 @@ pub type MessageStream = Pin<Box<dyn Stream<Item = Result<FrameMessage, SystemAc
 ///
 /// // MyMessage automatically implements MessageType
 /// ```
-pub trait MessageType: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync {}
+pub trait MessageType: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static {}
 // Blanket implementation for all types that meet the criteria
-impl<T> MessageType for T where T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync {}
+impl<T> MessageType for T where T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static {}
 
 /// A trait for actors that can handle specific message types.
 ///
@@ -415,13 +656,42 @@ where
             // Set up reply stream first
             let handle = ctx.start_message_processing(frame.clone()).await;
 
-            // Process message and store result
-            let result = self.handle(ctx, message).await;
+            // Record a span for the processing, and correlate the logs of the handler
+            let trace = ctx.trace_context().cloned().unwrap_or_default();
+            let span = ctx
+                .engine()
+                .tracer()
+                .span(frame.name.clone(), SpanKind::Consumer, &trace, frame.trace.as_ref())
+                .attribute("actor.id", ctx.id().record_id())
+                .attribute("message.id", &frame.id)
+                .attribute("message.tx", &frame.tx);
+            let log_span = tracing::info_span!("msg", trace_id = %trace.trace_id, span_id = %trace.span_id);
+
+            // Process message and store result, unless the sender cancelled it already
+            let started = std::time::Instant::now();
+            let result = if ctx.is_cancelled() {
+                debug!("[{}] msg-cancelled {} {}", ctx.id().record_id(), frame.name, frame.id);
+                Ok(())
+            } else {
+                self.handle(ctx, message).instrument(log_span).await
+            };
+            span.end(result.as_ref().err().map(|e| e.to_string()));
+            ctx.engine().metrics().handled(ctx.id(), &frame.name, started.elapsed(), result.is_err());
+
+            // Keep a copy of the failed message for inspection if configured
+            if let Err(e) = &result {
+                if ctx.engine().options().dead_letter.on_handler_error {
+                    let reason = DeadLetterReason::HandlerError(e.to_string());
+                    if let Err(e) = ctx.engine().dead_letter(frame, reason).await {
+                        error!("Error recording dead letter: {}", e);
+                    }
+                }
+            }
 
             // Ensure cleanup happens regardless of handle result
             let cleanup_result = {
-                // Clean up reply stream first
-                ctx.finish_message_processing().await;
+                // Clean up reply stream first, the sender gets the error with the final reply
+                ctx.finish_message_processing(result.as_ref().err().map(|e| e.to_string())).await;
 
                 // Then wait for final reply to be sent
                 handle.await
@@ -443,7 +713,7 @@ where
         to: &ActorId,
     ) -> impl Future<Output = Result<(), SystemActorError>> {
         async move {
-            let _ = ctx.prepare_and_send_message::<MT>(&message, to).await?;
+            let _ = ctx.prepare_and_send_message::<MT>(&message, to, &SendOptions::default(), false).await?;
             Ok(())
         }
     }
@@ -460,8 +730,8 @@ where
         Self::Response: 'static,
     {
         async move {
-            let (_, reply_id, _) = ctx.prepare_and_send_message::<MT>(&message, to).await?;
-            ctx.wait_for_replies::<Self::Response>(&reply_id, options).await
+            let (_, reply_id, replies) = ctx.prepare_and_send_message::<MT>(&message, to, &options, true).await?;
+            ctx.wait_for_replies::<Self::Response>(&reply_id, replies, options).await
         }
     }
 }
@@ -470,13 +740,15 @@ where
 ///
 /// Controls aspects of message delivery and reply handling such as:
 /// - Timeout duration
+/// - Priority in the receiver's mailbox
 ///
 /// # Example
 ///
 /// ```rust
-/// // Custom timeout for important message
+/// // Custom timeout for important message, received ahead of queued messages
 /// let options = SendOptions::builder()
 ///     .timeout(std::time::Duration::from_secs(60))
+///     .priority(10)
 ///     .build();
 ///
 /// // Send with custom options
@@ -490,11 +762,18 @@ where
 pub struct SendOptions {
     /// The maximum duration to wait for a reply before timing out.
     pub timeout: std::time::Duration,
+    /// Mailbox priority, higher values are received first. Messages with the same
+    /// priority are received in the order they were sent.
+    #[builder(default)]
+    pub priority: i32,
+    /// Trace the message belongs to, by default the trace of the message being processed, or
+    /// a new trace.
+    pub trace: Option<TraceContext>,
 }
 
 impl Default for SendOptions {
     fn default() -> Self {
-        Self { timeout: std::time::Duration::from_secs(30) }
+        Self { timeout: std::time::Duration::from_secs(30), priority: 0, trace: None }
     }
 }
 
@@ -596,11 +875,24 @@ pub struct SpawnOptions {
     /// This field determines the behavior of the spawning process when it encounters
     /// an existing actor with the same ID as the one being spawned.
     exists: SpawnExistsOptions,
+
+    /// How long to wait for the lease of an actor running in another engine.
+    ///
+    /// Spawning fails with `SystemActorError::LeaseTimeout` once exceeded. Waits until the other
+    /// engine releases the lease or fails to renew it if not set.
+    lease_timeout: Option<std::time::Duration>,
 }
 
 impl Default for SpawnOptions {
     fn default() -> Self {
-        Self { exists: SpawnExistsOptions::Error }
+        Self { exists: SpawnExistsOptions::Error, lease_timeout: None }
+    }
+}
+
+impl SpawnOptions {
+    /// How long to wait for the lease of an actor running in another engine, if bounded.
+    pub fn lease_timeout(&self) -> Option<std::time::Duration> {
+        self.lease_timeout
     }
 }
 
@@ -609,7 +901,7 @@ impl Default for SpawnOptions {
 pub enum SpawnExistsOptions {
     /// Reset the actor if it already exists.
     ///
-    /// This option will delete the existing actor's record and create a new one.
+    /// This option will replace the existing actor's state and delete its event journal.
     Reset,
 
     /// Error if the actor already exists.
@@ -675,10 +967,38 @@ pub trait Actor: Sized + Serialize + for<'de> Deserialize<'de> + Debug + Send +
     /// The error type returned by this actor's operations.
     type Error: ActorError;
 
+    /// Version of the actor state schema, stored alongside the state.
+    ///
+    /// Increase it when changing the serialized fields of the actor, and handle
+    /// the older versions in `migrate`.
+    const STATE_VERSION: u32 = 0;
+
+    /// Upgrades actor state stored with an older `STATE_VERSION`.
+    ///
+    /// Called by `spawn` with `SpawnExistsOptions::Restore` before deserializing the state.
+    /// State saved before versioning was introduced has version `0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_version` - The version the state was stored with.
+    /// * `state` - The stored state.
+    ///
+    /// # Returns
+    ///
+    /// The state in the current `STATE_VERSION` schema.
+    fn migrate(from_version: u32, state: Value) -> Result<Value, Self::Error> {
+        let _ = from_version;
+        Ok(state)
+    }
+
     /// Spawns a new actor in the system or handles an existing one based on the provided options.
     ///
     /// This function creates a new actor instance, registers it in the database, or restores an existing actor.
     ///
+    /// The engine takes the lease of the actor, see `LeaseOptions`. If the actor runs in another
+    /// engine, this waits until that engine releases the lease or fails to renew it, or until
+    /// `SpawnOptions::lease_timeout`.
+    ///
     /// # Arguments
     ///
     /// * `engine` - The `Engine` instance.
@@ -699,36 +1019,51 @@ pub trait Actor: Sized + Serialize + for<'de> Deserialize<'de> + Debug + Send +
     /// - Serialization of the actor state fails.
     /// - Creating or updating the actor record in the database fails.
     /// - The actor already exists and `SpawnOptions::Error` is specified.
-    /// - Deserialization of an existing actor's state fails when using `SpawnOptions::Restore`.
+    /// - The lease is not acquired within `SpawnOptions::lease_timeout`.
+    /// - Migration or deserialization of an existing actor's state fails when using `SpawnOptions::Restore`.
     fn spawn(
         engine: Engine,
         id: ActorId,
         actor: Self,
         options: SpawnOptions,
-    ) -> impl Future<Output = Result<(ActorContext<Self>, Self), Self::Error>> {
+    ) -> impl Future<Output = Result<(ActorContext<Self>, Self), Self::Error>> + Send {
         async move {
-            // Check if the actor already exists
-            let actor_record: Option<ActorRecord> =
-                engine.db().lock().await.select(&id.record_id()).await.map_err(SystemActorError::from)?;
+            // Check if the actor already exists, without touching the record of a running actor
+            let actor_record = engine.backend().get_actor(&id.record_id()).await?;
+            let exists = actor_record.is_some_and(|record| !record.state.is_null());
+            if exists && matches!(options.exists, SpawnExistsOptions::Error) {
+                return Err(Self::Error::from(SystemActorError::ActorAlreadyExists(id.clone())));
+            }
 
-            if let Some(actor_record) = actor_record {
+            // Take the lease, waiting while the actor runs in another engine
+            let mut ctx = ActorContext::new(engine.clone(), id.clone());
+            let actor_record = engine.acquire_lease(&id, &ctx.instance, options.lease_timeout).await?;
+            ctx.lease = Some(engine.keep_lease(&id, &ctx.instance));
+
+            // A record without state only holds a lease
+            if let Some(mut actor_record) = actor_record.filter(|record| !record.state.is_null()) {
                 // Actor exists, apply options
                 match options.exists {
                     SpawnExistsOptions::Reset => {
-                        // Reset the actor by deleting its record
-                        let _: Option<ActorRecord> =
-                            engine.db().lock().await.delete(&id.record_id()).await.map_err(SystemActorError::from)?;
-                        // We'll create a new record below
+                        // Drop any event journal, replaying it would bring back the old state
+                        engine.clear_journal(&id).await?;
+                        // The record is replaced below
                     }
                     SpawnExistsOptions::Error => {
+                        // Created by another engine in the meantime
                         return Err(Self::Error::from(SystemActorError::ActorAlreadyExists(id.clone())));
                     }
                     SpawnExistsOptions::Restore => {
                         // Restore the actor by loading its state from the database
-                        let actor_state = actor_record.state;
-                        let actor: Self = serde_json::from_value(actor_state).map_err(SystemActorError::from)?;
-                        // Create and return the actor context with restored state
-                        let ctx = ActorContext::new(engine.clone(), id.clone());
+                        let envelope =
+                            StateEnvelope::from_stored(std::mem::take(&mut actor_record.state)).upgrade::<Self>(&id)?;
+                        let actor: Self =
+                            serde_json::from_value(envelope.state.clone()).map_err(SystemActorError::from)?;
+                        // Claim the record for this instance, clear any previous exit and store the migrated state
+                        actor_record.state = envelope.into_stored()?;
+                        actor_record.instance = Some(ctx.instance.clone());
+                        actor_record.exit = None;
+                        engine.backend().put_actor(actor_record).await?;
                         return Ok((ctx, actor));
                     }
                 }
@@ -737,21 +1072,20 @@ pub trait Actor: Sized + Serialize + for<'de> Deserialize<'de> + Debug + Send +
             // At this point, we either need to create a new actor or reset an existing one
 
             // Serialize actor properties
-            let actor_state = serde_json::to_value(&actor).map_err(SystemActorError::from)?;
+            let actor_state = StateEnvelope::of(&actor)?.into_stored()?;
 
             // Create or update actor record in the database
-            let content = ActorRecord { id: id.record_id(), tag: id.tag.clone(), state: actor_state };
-            let _record: Option<Record> = engine
-                .db()
-                .lock()
-                .await
-                .create(DB_TABLE_ACTOR)
-                .content(content)
-                .await
-                .map_err(SystemActorError::from)?;
-
-            // Create and return the actor context
-            let ctx = ActorContext::new(engine.clone(), id.clone());
+            let content = ActorRecord {
+                id: id.record_id(),
+                name: id.name.clone(),
+                tag: id.tag.clone(),
+                state: actor_state,
+                instance: Some(ctx.instance.clone()),
+                exit: None,
+                lease: None,
+            };
+            engine.backend().put_actor(content).await?;
+
             Ok((ctx, actor))
         }
     }
@@ -771,7 +1105,7 @@ pub trait Actor: Sized + Serialize + for<'de> Deserialize<'de> + Debug + Send +
     /// A `Result<(), Self::Error>`:
     /// - `Ok(())` if the actor completes its work successfully.
     /// - `Err(Self::Error)` if an error occurs during the actor's execution.
-    fn start(&mut self, ctx: &mut ActorContext<Self>) -> impl Future<Output = Result<(), Self::Error>>;
+    fn start(&mut self, ctx: &mut ActorContext<Self>) -> impl Future<Output = Result<(), Self::Error>> + Send;
 
     /// Saves the current state of the actor in the system.
     ///
@@ -795,35 +1129,136 @@ pub trait Actor: Sized + Serialize + for<'de> Deserialize<'de> + Debug + Send +
     fn save(&self, ctx: &ActorContext<Self>) -> impl Future<Output = Result<(), Self::Error>> {
         async move {
             // Serialize actor properties
-            let actor_state = serde_json::to_value(self).map_err(SystemActorError::from)?;
+            let actor_state = StateEnvelope::of(self)?.into_stored()?;
 
             let record_id = ctx.id().record_id();
 
             // Update actor record in the database
-            let content = ActorRecord { id: record_id.clone(), tag: ctx.id().tag.clone(), state: actor_state };
+            let content = ActorRecord {
+                id: record_id.clone(),
+                name: ctx.id().name.clone(),
+                tag: ctx.id().tag.clone(),
+                state: actor_state,
+                instance: Some(ctx.instance.clone()),
+                exit: None,
+                lease: None,
+            };
 
-            let _record: Option<Record> = ctx
-                .engine()
-                .db()
-                .lock()
-                .await
-                .update(&record_id)
-                .content(content)
-                .await
-                .map_err(SystemActorError::from)?;
+            ctx.engine().backend().put_actor(content).await?;
 
             Ok(())
         }
     }
 }
 
+/// Key of the schema version in stored state, not a valid field name of a Rust actor.
+const STATE_VERSION_KEY: &str = "$state_version";
+
+/// Actor state stored together with its schema version
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateEnvelope {
+    /// The `Actor::STATE_VERSION` the state was saved with
+    #[serde(rename = "$state_version")]
+    pub version: u32,
+    /// The serialized actor
+    pub state: Value,
+}
+
+impl StateEnvelope {
+    /// Envelope of the current state of an actor
+    pub fn of<T: Actor>(actor: &T) -> Result<Self, SystemActorError> {
+        Ok(Self { version: T::STATE_VERSION, state: serde_json::to_value(actor)? })
+    }
+
+    /// Reads stored state, treating state saved without an envelope as version 0
+    pub fn from_stored(stored: Value) -> Self {
+        if stored.get(STATE_VERSION_KEY).is_some() {
+            if let Ok(envelope) = serde_json::from_value(stored.clone()) {
+                return envelope;
+            }
+        }
+        Self { version: 0, state: stored }
+    }
+
+    /// Migrates the state to the current `STATE_VERSION` of the actor
+    pub(crate) fn upgrade<T: Actor>(self, id: &ActorId) -> Result<Self, T::Error> {
+        if self.version >= T::STATE_VERSION {
+            return Ok(self);
+        }
+        debug!("[{}] actor-migrate {} -> {}", id.record_id(), self.version, T::STATE_VERSION);
+        Ok(Self { version: T::STATE_VERSION, state: T::migrate(self.version, self.state)? })
+    }
+
+    pub(crate) fn into_stored(self) -> Result<Value, SystemActorError> {
+        Ok(serde_json::to_value(self)?)
+    }
+}
+
 /// Database record for an actor
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ActorRecord {
-    id: RecordId,
-    tag: Cow<'static, str>,
+    pub(crate) id: RecordId,
+    /// The actor name, see `ActorId::name`
+    #[serde(default)]
+    pub(crate) name: Cow<'static, str>,
+    pub(crate) tag: Cow<'static, str>,
+    /// The actor state, as a `StateEnvelope`
+    #[serde(default)]
+    pub(crate) state: Value,
+    /// The context instance currently running this actor
+    #[serde(default)]
+    pub(crate) instance: Option<String>,
+    /// How the actor instance terminated, if it did
     #[serde(default)]
-    state: Value,
+    pub(crate) exit: Option<ExitReason>,
+    /// The engine running this actor, see `LeaseOptions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) lease: Option<Lease>,
+}
+
+impl ActorRecord {
+    /// The record id of the actor
+    pub fn id(&self) -> &RecordId {
+        &self.id
+    }
+
+    /// The id of the actor
+    pub fn actor_id(&self) -> ActorId {
+        ActorId::with_tag(self.name.clone(), self.tag.clone())
+    }
+
+    /// The actor name, see `ActorId::name`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The actor type tag, see `ActorId::tag`
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Whether an engine currently runs the actor
+    pub fn status(&self) -> ActorStatus {
+        match &self.lease {
+            Some(lease) if lease.expires.0 > chrono::Utc::now() => ActorStatus::Running,
+            _ => ActorStatus::Stopped,
+        }
+    }
+
+    /// The context instance currently running the actor, if any
+    pub fn instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+
+    /// The lease of the engine running the actor, if any
+    pub fn lease(&self) -> Option<&Lease> {
+        self.lease.as_ref()
+    }
+
+    /// How the actor terminated, if it did
+    pub fn exit(&self) -> Option<&ExitReason> {
+        self.exit.as_ref()
+    }
 }
 
 /// The context for an actor, providing access to the actor system.
@@ -842,23 +1277,103 @@ pub struct ActorContext<T: Actor> {
     engine: Engine,
     /// The actor's unique identifier
     id: ActorId,
-    /// Channel for sending reply chunks during message processing
-    tx: Option<mpsc::UnboundedSender<Value>>,
+    /// Channel for sending reply chunks during message processing, or the error of the handler
+    tx: Option<mpsc::UnboundedSender<Result<Value, String>>>,
+    /// Unique id of this running instance of the actor
+    instance: String,
+    /// Whether the actor main loop is running through `run`
+    running: bool,
+    /// Whether the actor ran through `run` or opened its mailbox with `recv`
+    started: AtomicBool,
+    /// How the actor main loop ended
+    exit: Option<ExitReason>,
+    /// Tasks watching other actors for termination
+    watches: HashMap<ActorId, AbortHandle>,
+    /// Sequence number of the last journaled event, loaded on first use
+    pub(crate) journal_seq: Option<u64>,
+    /// Ownership of the actor by this engine, renewed while the context lives
+    lease: Option<LeaseHandle>,
+    /// Cancellation of the message being processed
+    processing: Option<Processing>,
     /// Type marker for the actor
     _marker: std::marker::PhantomData<T>,
 }
 
+/// The message an actor is processing, with its cancellation.
+#[derive(Debug)]
+struct Processing {
+    msg_id: RecordId,
+    /// Cancelled when the sender cancels the message
+    token: CancellationToken,
+    /// Cancelled when the processing ends, to stop cascading cancellations
+    done: CancellationToken,
+    /// Trace of the message, with the span processing it
+    trace: TraceContext,
+}
+
+/// The replies to a sent message, subscribed before the message is sent.
+enum SentReplies {
+    /// The receiver runs in this engine and replies through a channel
+    Local(mpsc::UnboundedReceiver<FrameReply>),
+    /// Replies delivered by the backend
+    Backend(ReplyFrameStream),
+}
+
+/// Cancels a sent message if its replies are dropped before the final one.
+struct PendingReplies {
+    engine: Engine,
+    msg_id: RecordId,
+    done: bool,
+}
+
+impl PendingReplies {
+    /// Marks the replies as complete, so dropping them cancels nothing.
+    fn complete(&mut self) {
+        self.done = true;
+    }
+
+    fn cancel(&mut self) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let engine = self.engine.clone();
+        let msg_id = self.msg_id.clone();
+        runtime.spawn(async move { engine.cancel_message(&msg_id).await });
+    }
+}
+
+impl Drop for PendingReplies {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
 impl<T: Actor> ActorContext<T> {
     /// Create a new actor context
     fn new(engine: Engine, id: ActorId) -> Self {
         debug!("[{}] ctx-new", id.record_id());
-        Self { engine, id, tx: None, _marker: std::marker::PhantomData }
+        Self {
+            engine,
+            id,
+            tx: None,
+            instance: Id::ulid().to_string(),
+            running: false,
+            started: AtomicBool::new(false),
+            exit: None,
+            watches: HashMap::new(),
+            journal_seq: None,
+            lease: None,
+            processing: None,
+            _marker: std::marker::PhantomData,
+        }
     }
 
     async fn unreplied_messages(&self) -> Result<Vec<FrameMessage>, SystemActorError> {
-        let query = include_str!("../sql/unreplied_messages.surql");
-        let mut res = self.engine().db().lock().await.query(query).bind(("rx", self.id.record_id())).await?;
-        let existing_messages: Vec<FrameMessage> = res.take(0)?;
+        let existing_messages = self.engine().backend().unreplied(&self.id.record_id()).await?;
         trace!("unreplied_messages: {:?}", existing_messages);
         Ok(existing_messages)
     }
@@ -876,22 +1391,160 @@ impl<T: Actor> ActorContext<T> {
     /// Check the health of the actor
     pub async fn health(&self) -> bool {
         // Check if the actor is still in the database
-        let record: Result<Option<ActorRecord>, SystemActorError> =
-            self.engine().db().lock().await.select(&self.id.record_id()).await.map_err(SystemActorError::from);
-        if let Ok(Some(_)) = record {
-            true
-        } else {
-            false
-        }
+        matches!(self.engine().backend().get_actor(&self.id.record_id()).await, Ok(Some(_)))
     }
 
     /// Kill the actor
     pub async fn kill(&self) -> Result<(), SystemActorError> {
-        let _: Option<ActorRecord> =
-            self.engine().db().lock().await.delete(&self.id.record_id()).await.map_err(SystemActorError::from)?;
+        self.engine().backend().delete_actor(&self.id.record_id()).await?;
+        if let Some(db) = self.engine().backend().surreal() {
+            db.query("DELETE subscription WHERE subscriber = $subscriber")
+                .bind(("subscriber", self.id.record_id()))
+                .await?;
+        }
         Ok(())
     }
 
+    /// Runs the actor's main loop and records how it ended.
+    ///
+    /// Prefer this over calling `Actor::start` directly, so that watchers of this actor
+    /// receive the error returned by `start` instead of a normal exit.
+    ///
+    /// # Arguments
+    ///
+    /// * `actor` - The actor instance to run.
+    ///
+    /// # Returns
+    ///
+    /// The result of `Actor::start`.
+    pub async fn run(&mut self, actor: &mut T) -> Result<(), T::Error> {
+        self.running = true;
+        self.started.store(true, Ordering::Relaxed);
+        let result = actor.start(self).await;
+        self.running = false;
+        self.exit = Some(match &result {
+            Ok(()) => ExitReason::Normal,
+            Err(e) => ExitReason::Error(e.to_string()),
+        });
+        result
+    }
+
+    /// Watch another actor for termination.
+    ///
+    /// When the watched actor stops, is killed through `kill`, or its record disappears from the
+    /// database, this actor receives a `Terminated` message in its mailbox. If the watched actor
+    /// does not exist, `Terminated` is delivered right away with `ExitReason::NotFound`.
+    ///
+    /// Each watch fires at most once. Watching an actor that is already watched has no effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the actor to watch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the live query on the watched actor record can't be set up.
+    pub async fn watch(&mut self, id: &ActorId) -> Result<(), SystemActorError> {
+        self.watches.retain(|_, watch| !watch.is_finished());
+        if self.watches.contains_key(id) {
+            return Ok(());
+        }
+        let watch = crate::watch::watch(self.engine.clone(), self.id.clone(), id.clone()).await?;
+        self.watches.insert(id.clone(), watch);
+        Ok(())
+    }
+
+    /// Stop watching an actor.
+    pub fn unwatch(&mut self, id: &ActorId) {
+        if let Some(watch) = self.watches.remove(id) {
+            watch.abort();
+        }
+    }
+
+    /// Subscribe this actor to a topic.
+    ///
+    /// Messages published on the topic are delivered to this actor's mailbox like any other
+    /// message, with `FrameMessage::topic` set. Subscriptions are persisted and survive restarts
+    /// until `unsubscribe` or `kill` is called. Subscribing twice has no effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic name.
+    pub async fn subscribe(&self, topic: &str) -> Result<(), SystemActorError> {
+        debug!("[{}] subscribe {}", self.id.record_id(), topic);
+        let query = include_str!("../sql/subscribe.surql");
+        self.engine()
+            .surreal_for("topics")?
+            .query(query)
+            .bind(("topic", topic.to_string()))
+            .bind(("subscriber", self.id.record_id()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    /// Unsubscribe this actor from a topic.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic name.
+    pub async fn unsubscribe(&self, topic: &str) -> Result<(), SystemActorError> {
+        debug!("[{}] unsubscribe {}", self.id.record_id(), topic);
+        self.engine()
+            .surreal_for("topics")?
+            .query("DELETE type::thing('subscription', [$topic, $subscriber])")
+            .bind(("topic", topic.to_string()))
+            .bind(("subscriber", self.id.record_id()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    /// Publish a message on a topic, without waiting for replies.
+    ///
+    /// A copy of the message is sent to every actor currently subscribed to the topic.
+    /// The publisher does not need to know the subscribers.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic name.
+    /// * `message` - The message to publish.
+    ///
+    /// # Returns
+    ///
+    /// The number of subscribers the message was sent to.
+    pub async fn publish<MT>(&self, topic: &str, message: MT) -> Result<usize, SystemActorError>
+    where
+        MT: MessageType,
+    {
+        let (name, version) = message_name::<MT>();
+        debug!("[{}] publish {} {}", self.id.record_id(), topic, name);
+        let parent = self.trace_context();
+        let trace = parent.map(TraceContext::child).unwrap_or_default();
+        let span = self
+            .engine()
+            .tracer()
+            .span(format!("publish {}", name), SpanKind::Producer, &trace, parent)
+            .attribute("actor.id", self.id.record_id())
+            .attribute("topic", topic);
+        let query = include_str!("../sql/publish.surql");
+        let subscribers: Vec<RecordId> =
+            self.engine().surreal_for("topics")?.query(query).bind(("topic", topic.to_string())).await?.take(0)?;
+        // Each copy is sent like any other message, in memory or through the backend
+        let msg = serde_json::to_value(&message)?;
+        let topic: Cow<'static, str> = Cow::Owned(topic.to_string());
+        for subscriber in &subscribers {
+            let mut frame =
+                FrameMessage::from_parts(name.clone(), self.id.record_id(), subscriber.clone(), msg.clone());
+            frame.version = version;
+            frame.topic = Some(topic.clone());
+            frame.trace = Some(trace.clone());
+            self.deliver(frame, false).await?;
+        }
+        span.end(None);
+        Ok(subscribers.len())
+    }
+
     /// Receive messages for this actor
     ///
     /// This method sets up a stream of messages for the actor, combining any unreplied messages
@@ -910,43 +1563,61 @@ impl<T: Actor> ActorContext<T> {
     /// - The live query setup fails.
     /// - There's an error in the database query.
     pub async fn recv(&self) -> Result<MessageStream, SystemActorError> {
-        let query = format!("LIVE SELECT * FROM {} WHERE rx = {}", DB_TABLE_MESSAGE, self.id().record_id());
-        debug!("[{}] msg-live {}", &self.id().record_id(), &query);
-        let mut res = self.engine().db().lock().await.query(&query).await?;
-        let live_query = res.stream::<Notification<FrameMessage>>(0)?;
+        self.started.store(true, Ordering::Relaxed);
+        let live_query = self.engine().backend().subscribe_messages(&self.id().record_id()).await?;
         let self_id = self.id().clone();
-        let live_query = live_query
-            .filter(|item| {
-                // Filter out non-create actions
-                let should_filter =
-                    item.as_ref().ok().map(|notification| notification.action == Action::Create).unwrap_or(false);
-                async move { should_filter }
-            })
-            // Map the notification to a frame
-            .map(|item| {
-                let item = item?;
-                Ok(item.data)
-            })
-            .inspect(move |item| match item {
-                Ok(frame) => {
-                    debug!(
-                        "[{}] msg-recv {} {} {} -> {} {}",
-                        &self_id.record_id(),
-                        &frame.name,
-                        &frame.id,
-                        &frame.tx,
-                        &frame.rx,
-                        &frame.msg
-                    );
-                }
-                Err(error) => debug!("msg-recv {} {:?}", self_id.record_id(), error),
-            });
+        self.engine().metrics().register(self.id());
 
         let unreplied_messages = self.unreplied_messages().await?;
-        let unreplied_stream = futures::stream::iter(unreplied_messages).map(Ok);
-        let chained_stream = unreplied_stream.chain(live_query);
 
-        Ok(Box::pin(chained_stream))
+        // Open the in-memory mailbox after loading the backlog, so a message is never in both
+        let live_query: MessageStream = if self.engine().options().local_delivery.is_enabled() {
+            let (mailbox, guard) = self.engine().local().open_mailbox(self.id());
+            let local = futures::stream::unfold((mailbox, guard), |(mut mailbox, guard)| async move {
+                mailbox.recv().await.map(|frame| (Ok::<_, SystemActorError>(frame), (mailbox, guard)))
+            });
+            Box::pin(futures::stream::select(live_query, local))
+        } else {
+            live_query
+        };
+
+        let live_query = live_query.inspect(move |item| match item {
+            Ok(frame) => {
+                debug!(
+                    "[{}] msg-recv {} {} {} -> {} {}",
+                    &self_id.record_id(),
+                    &frame.name,
+                    &frame.id,
+                    &frame.tx,
+                    &frame.rx,
+                    &frame.msg
+                );
+            }
+            Err(error) => debug!("msg-recv {} {:?}", self_id.record_id(), error),
+        });
+
+        let prioritized_stream = prioritized(unreplied_messages, live_query);
+
+        // After a reconnection, messages still waiting to be processed are loaded again
+        let mut recent = Recent::new();
+        let metrics = self.engine().metrics().clone();
+        let actor_id = self.id().clone();
+        let deduplicated = prioritized_stream.filter(move |item| {
+            let fresh = match item {
+                Ok(frame) => recent.insert(frame.id.clone()),
+                Err(_) => true,
+            };
+            if let (true, Ok(frame)) = (fresh, item) {
+                metrics.received(&actor_id, &frame.name);
+            }
+            future::ready(fresh)
+        });
+
+        // Stop receiving once another engine took over the actor
+        match &self.lease {
+            Some(lease) => Ok(Box::pin(deduplicated.take_until(lease.lost()))),
+            None => Ok(Box::pin(deduplicated)),
+        }
     }
 
     /// Begins processing an incoming message and sets up reply streaming.
@@ -963,34 +1634,50 @@ impl<T: Actor> ActorContext<T> {
     /// # Returns
     ///
     /// A JoinHandle for the reply processing task
-    async fn start_message_processing(&mut self, frame: FrameMessage) -> tokio::task::JoinHandle<()> {
+    pub(crate) async fn start_message_processing(&mut self, frame: FrameMessage) -> tokio::task::JoinHandle<()> {
         // Set up message processing and logging
         debug!("[{}] msg-process-start {} {}", self.id().record_id(), frame.name, frame.id);
 
+        // Cancelled when the sender stops waiting for the replies
+        let token = self.engine.cancellations().register(&frame.id);
+        let trace = frame.trace.as_ref().map(TraceContext::child).unwrap_or_default();
+        self.processing =
+            Some(Processing { msg_id: frame.id.clone(), token, done: CancellationToken::new(), trace: trace.clone() });
+
         // Create an unbounded channel for streaming replies
         // tx: sender that will be stored in actor context
         // rx: receiver that will be used in spawned task
         let (tx, mut rx) = mpsc::unbounded_channel();
 
-        // Clone database and frame for use in spawned task
-        let db = self.engine.db().clone();
+        // Clone engine and frame for use in spawned task
+        let engine = self.engine.clone();
         let frame_clone = frame.clone();
+        let actor_id = self.id.clone();
 
-        // Load SQL query template for reply insertion
-        let reply_query = include_str!("../sql/reply.surql");
+        // Replies to a locally delivered message go straight to the waiting sender
+        let local_replies = if frame.local { engine.local().take_replies(&frame.id) } else { None };
 
         // Spawn async task to handle reply processing
         let handle = tokio::spawn(async move {
             // Counter for tracking reply chunks in stream
             let chunk_counter = AtomicU64::new(1);
+            let mut error = None;
 
             // Process each reply value sent through channel
             while let Some(value) = rx.recv().await {
+                let value = match value {
+                    Ok(value) => value,
+                    // The handler failed, the error is sent with the final reply
+                    Err(e) => {
+                        error = Some(e);
+                        continue;
+                    }
+                };
                 let chunk = chunk_counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
                 debug!("[{}] msg-chunk {} {}-{}", frame_clone.rx, frame_clone.name, frame_clone.id.key(), chunk);
 
                 // Create reply frame for this chunk
-                let reply = FrameReply::new_chunk(
+                let mut reply = FrameReply::new_chunk(
                     frame_clone.id.key().to_string(),
                     chunk,
                     frame_clone.name.clone(),
@@ -998,23 +1685,11 @@ impl<T: Actor> ActorContext<T> {
                     frame_clone.tx.clone(),
                     value,
                 );
+                reply.trace = Some(trace.clone());
+                engine.metrics().reply_chunk(&actor_id, &frame_clone.name);
 
-                // Get database ID for reply
-                let reply_id = reply.id.to_record_id();
-
-                // Insert reply chunk into database
-                // This query likely creates a new reply record and links it to original message
-                let result = db
-                    .lock()
-                    .await
-                    .query(reply_query)
-                    .bind(("reply_id", reply_id))
-                    .bind(("reply", reply))
-                    .bind(("msg_id", frame_clone.id.clone()))
-                    .await;
-
-                // Log any errors during reply insertion
-                if let Err(e) = result {
+                // Store the reply chunk, or pass it to a local sender
+                if let Err(e) = send_reply(&engine, &frame_clone, local_replies.as_ref(), reply).await {
                     error!(
                         "[{}] msg-chunk-error {} {}-{} {}",
                         frame_clone.rx,
@@ -1035,20 +1710,17 @@ impl<T: Actor> ActorContext<T> {
             debug!("[{}] msg-final {} {}", rx, name, id_key);
 
             // Create final reply frame (chunk = None indicates end of stream)
-            let reply = FrameReply::new_final(id_key.clone(), frame_clone.name, frame_clone.rx, frame_clone.tx);
-            let reply_id = reply.id.to_record_id();
-
-            // Insert final reply into database
-            // Uses same query template but marks this as final reply
-            if let Err(e) = db
-                .lock()
-                .await
-                .query(reply_query)
-                .bind(("reply_id", reply_id))
-                .bind(("reply", reply))
-                .bind(("msg_id", frame_clone.id))
-                .await
-            {
+            let mut reply = FrameReply::new_final(
+                id_key.clone(),
+                frame_clone.name.clone(),
+                frame_clone.rx.clone(),
+                frame_clone.tx.clone(),
+            );
+            reply.trace = Some(trace);
+            reply.err = error.map(Value::String).unwrap_or_default();
+
+            // Send the final reply the same way as the chunks
+            if let Err(e) = send_reply(&engine, &frame_clone, local_replies.as_ref(), reply).await {
                 error!("[{}] msg-final-error {} {} {}", rx, name, id_key, e);
             }
         });
@@ -1059,56 +1731,176 @@ impl<T: Actor> ActorContext<T> {
     }
 
     // Called when handle() returns to clean up
-    async fn finish_message_processing(&mut self) {
+    pub(crate) async fn finish_message_processing(&mut self, error: Option<String>) {
+        if let (Some(tx), Some(error)) = (&self.tx, error) {
+            let _ = tx.send(Err(error));
+        }
         // Drop channel to trigger final reply
         self.tx = None;
+        if let Some(processing) = self.processing.take() {
+            self.engine.cancellations().unregister(&processing.msg_id);
+            processing.done.cancel();
+        }
+    }
+
+    /// The cancellation token of the message being processed.
+    ///
+    /// The token is cancelled when the sender drops the reply stream before the final reply, when
+    /// waiting for a reply times out, or when the message that led to this one is cancelled.
+    /// Handlers doing long or streaming work should check it and stop early. Messages cancelled
+    /// before their processing starts are not handled.
+    ///
+    /// Outside of message processing, the token is never cancelled.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let cancel = ctx.cancellation_token();
+    /// while let Some(Some(chunk)) = cancel.run_until_cancelled(stream.next()).await {
+    ///     ctx.reply(chunk?).await?;
+    /// }
+    /// ```
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.processing.as_ref().map(|processing| processing.token.clone()).unwrap_or_default()
+    }
+
+    /// Whether the sender cancelled the message being processed, see `cancellation_token`.
+    pub fn is_cancelled(&self) -> bool {
+        self.processing.as_ref().is_some_and(|processing| processing.token.is_cancelled())
+    }
+
+    /// The trace of the message being processed, with the span processing it.
+    ///
+    /// Messages sent and published while processing a message continue its trace.
+    pub fn trace_context(&self) -> Option<&TraceContext> {
+        self.processing.as_ref().map(|processing| &processing.trace)
+    }
+
+    /// Cancels a message sent while processing another one, if that one is cancelled.
+    fn cascade_cancel(&self, msg_id: &RecordId) {
+        let Some(processing) = &self.processing else {
+            return;
+        };
+        let token = processing.token.clone();
+        let done = processing.done.clone();
+        let engine = self.engine.clone();
+        let msg_id = msg_id.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = token.cancelled() => engine.cancel_message(&msg_id).await,
+                _ = done.cancelled() => {}
+            }
+        });
     }
 
     /// Internal method to prepare and send a message
+    ///
+    /// Set `replies` to wait for replies. The replies are then subscribed before the message is
+    /// sent, so none of them are missed.
     async fn prepare_and_send_message<MT>(
         &self,
         message: &MT,
         to: &ActorId,
-    ) -> Result<(RecordId, RecordId, FrameMessage), SystemActorError>
+        options: &SendOptions,
+        replies: bool,
+    ) -> Result<(RecordId, RecordId, Option<SentReplies>), SystemActorError>
     where
         MT: MessageType,
     {
-        let msg_value = serde_json::to_value(&message)?;
-        let name = std::any::type_name::<MT>();
-        let msg_id = Id::ulid();
-        let request_id = RecordId::from_table_key(DB_TABLE_MESSAGE, msg_id.to_string());
-        let reply_id = RecordId::from_table_key(DB_TABLE_REPLY, msg_id.to_string());
-
-        let request = FrameMessage {
-            id: request_id.clone(),
-            name: name.into(),
-            tx: self.id().record_id(),
-            rx: to.record_id(),
-            msg: msg_value.clone(),
-        };
+        let mut request = FrameMessage::new(self.id(), to, message)?;
+        request.priority = options.priority;
+        let request_id = request.id.clone();
+
+        // The message continues the trace of the caller, or of the message being processed
+        let parent = options.trace.as_ref().or(self.trace_context());
+        let trace = parent.map(TraceContext::child).unwrap_or_default();
+        let span = self
+            .engine()
+            .tracer()
+            .span(format!("send {}", request.name), SpanKind::Producer, &trace, parent)
+            .attribute("actor.id", self.id().record_id())
+            .attribute("message.id", &request_id)
+            .attribute("message.rx", to.record_id());
+        request.trace = Some(trace);
+        let reply_id = RecordId::from_table_key(DB_TABLE_REPLY, request_id.key().clone());
+
+        debug!(
+            "[{}] msg-send {} {} {} {}",
+            &self.id().record_id(),
+            &request.name,
+            &request.id,
+            &to.record_id(),
+            &request.msg
+        );
 
-        debug!("[{}] msg-send {} {} {} {}", &self.id().record_id(), name, &request.id, &to.record_id(), &msg_value);
+        let replies = self.deliver(request, replies).await?;
+        self.cascade_cancel(&request_id);
+        span.end(None);
+        Ok((request_id, reply_id, replies))
+    }
 
-        let db = self.engine().db().clone();
+    /// Delivers a message frame, in memory if the receiver runs in this engine.
+    ///
+    /// Returns the replies if `replies` is set. Replies from the backend are subscribed before
+    /// the message is enqueued, as backends such as `MemoryBackend` only pass on replies sent
+    /// after subscribing.
+    async fn deliver(&self, request: FrameMessage, replies: bool) -> Result<Option<SentReplies>, SystemActorError> {
+        // Skip the database if the receiver runs in this engine
+        let request = match self.engine().deliver_local(request, replies) {
+            Ok(local_replies) => return Ok(local_replies.map(SentReplies::Local)),
+            Err(request) => request,
+        };
 
-        let task_request_id = request_id.clone();
-        let task_request = request.clone();
+        let backend = self.engine().backend().clone();
+        let replies =
+            if replies { Some(SentReplies::Backend(backend.subscribe_replies(request.id()).await?)) } else { None };
 
         tokio::spawn(async move {
             tokio::time::sleep(std::time::Duration::from_secs(0)).await;
-            let msg_id: Result<Option<Record>, surrealdb::Error> =
-                db.lock().await.create(DB_TABLE_MESSAGE).content(task_request).await;
-            if let Ok(Some(msg_id)) = msg_id {
-                let id = msg_id.id.clone();
-                if task_request_id != id {
-                    error!("msg-send {}", &task_request_id);
-                }
-            } else {
-                error!("msg-send {:?}", msg_id);
+            if let Err(e) = backend.enqueue(request).await {
+                error!("msg-send {}", e);
             }
         });
 
-        Ok((request_id, reply_id, request))
+        Ok(replies)
+    }
+
+    /// Forwards a received message to another actor, returning the replies of that actor.
+    ///
+    /// The forwarded message is sent by this actor, with the content, version and priority of
+    /// `frame`. The reply stream ends after the final reply.
+    pub(crate) async fn forward(
+        &self,
+        frame: &FrameMessage,
+        to: &ActorId,
+    ) -> Result<(RecordId, ReplyFrameStream), SystemActorError> {
+        let mut request =
+            FrameMessage::from_parts(frame.name.clone(), self.id().record_id(), to.record_id(), frame.msg.clone());
+        request.version = frame.version;
+        request.priority = frame.priority;
+        request.trace = frame.trace.clone();
+        let msg_id = request.id.clone();
+
+        debug!(
+            "[{}] msg-forward {} {} {} {}",
+            &self.id().record_id(),
+            &frame.name,
+            &frame.id,
+            &msg_id,
+            &to.record_id()
+        );
+
+        let replies = self.deliver(request, true).await?;
+        let replies = self.reply_frames(&msg_id, replies).await?;
+        // Include the final reply, then stop
+        let replies = replies.scan(false, |done, reply| {
+            if *done {
+                return future::ready(None);
+            }
+            *done = !matches!(&reply, Ok(reply) if !reply.is_final());
+            future::ready(Some(reply))
+        });
+        Ok((msg_id, Box::pin(replies)))
     }
 
     /// Send a message to an actor without waiting for a reply.
@@ -1140,7 +1932,7 @@ impl<T: Actor> ActorContext<T> {
         M: Message<MT>,
         MT: MessageType,
     {
-        let _ = self.prepare_and_send_message::<MT>(&message, to).await?;
+        let _ = self.prepare_and_send_message::<MT>(&message, to, &SendOptions::default(), false).await?;
         Ok(())
     }
 
@@ -1172,10 +1964,53 @@ impl<T: Actor> ActorContext<T> {
     where
         MT: MessageType,
     {
-        let (_, _, _) = self.prepare_and_send_message(&message, to).await?;
+        let (_, _, _) = self.prepare_and_send_message(&message, to, &SendOptions::default(), false).await?;
         Ok(())
     }
 
+    /// Send a message to an actor after a delay, without waiting for a reply.
+    ///
+    /// The message is persisted and delivered by the engine scheduler, so it is not lost
+    /// if the process restarts before the delay elapses.
+    ///
+    /// # Returns
+    ///
+    /// The schedule id, which can be passed to `Engine::cancel_schedule`.
+    pub async fn send_after<M, MT>(
+        &self,
+        message: MT,
+        to: &ActorId,
+        delay: std::time::Duration,
+    ) -> Result<RecordId, SystemActorError>
+    where
+        M: Message<MT>,
+        MT: MessageType,
+    {
+        self.schedule::<M, MT>(message, to, Schedule::after(delay)?).await
+    }
+
+    /// Send a message to an actor according to a schedule, without waiting for replies.
+    ///
+    /// Schedules are persisted in the database and each delivery happens exactly once,
+    /// even when several processes share the same engine database.
+    ///
+    /// # Returns
+    ///
+    /// The schedule id, which can be passed to `Engine::cancel_schedule`.
+    pub async fn schedule<M, MT>(
+        &self,
+        message: MT,
+        to: &ActorId,
+        schedule: Schedule,
+    ) -> Result<RecordId, SystemActorError>
+    where
+        M: Message<MT>,
+        MT: MessageType,
+    {
+        let frame = FrameMessage::new(self.id(), to, &message)?;
+        self.engine().schedule_message(frame, schedule).await
+    }
+
     /// Send a message and receive a stream of replies.
     ///
     /// # Type Parameters
@@ -1224,8 +2059,8 @@ impl<T: Actor> ActorContext<T> {
         M: Message<MT>,
         MT: MessageType,
     {
-        let (_, reply_id, _) = self.prepare_and_send_message::<MT>(&message, to).await?;
-        self.wait_for_replies::<M::Response>(&reply_id, options).await
+        let (_, reply_id, replies) = self.prepare_and_send_message::<MT>(&message, to, &options, true).await?;
+        self.wait_for_replies::<M::Response>(&reply_id, replies, options).await
     }
 
     /// Send a message to an actor and wait for a reply.
@@ -1259,8 +2094,8 @@ impl<T: Actor> ActorContext<T> {
         MT: MessageType,
         RT: MessageType + 'static,
     {
-        let (_, reply_id, _) = self.prepare_and_send_message::<MT>(&message, &to).await?;
-        self.wait_for_replies::<RT>(&reply_id, options).await
+        let (_, reply_id, replies) = self.prepare_and_send_message::<MT>(&message, &to, &options, true).await?;
+        self.wait_for_replies::<RT>(&reply_id, replies, options).await
     }
 
     /// Sends a message and collects all replies into a Vec.
@@ -1340,9 +2175,13 @@ impl<T: Actor> ActorContext<T> {
         if let Some(first) = stream.next().await {
             let result = first?;
 
-            // Ensure there are no additional replies
-            if stream.next().await.is_some() {
-                return Err(SystemActorError::MessageReply("Expected single reply but received multiple".into()));
+            // Ensure there are no additional replies, nor a handler error after the reply
+            match stream.next().await {
+                Some(Err(e)) => return Err(e),
+                Some(Ok(_)) => {
+                    return Err(SystemActorError::MessageReply("Expected single reply but received multiple".into()))
+                }
+                None => {}
             }
 
             Ok(result)
@@ -1387,9 +2226,13 @@ impl<T: Actor> ActorContext<T> {
         if let Some(first) = stream.next().await {
             let result = first?;
 
-            // Ensure there are no additional replies
-            if stream.next().await.is_some() {
-                return Err(SystemActorError::MessageReply("Expected single reply but received multiple".into()));
+            // Ensure there are no additional replies, nor a handler error after the reply
+            match stream.next().await {
+                Some(Err(e)) => return Err(e),
+                Some(Ok(_)) => {
+                    return Err(SystemActorError::MessageReply("Expected single reply but received multiple".into()))
+                }
+                None => {}
             }
 
             Ok(result)
@@ -1427,13 +2270,25 @@ impl<T: Actor> ActorContext<T> {
     pub async fn reply<R: MessageType>(&self, response: R) -> Result<(), SystemActorError> {
         if let Some(tx) = &self.tx {
             let value = serde_json::to_value(&response)?;
-            tx.send(value).map_err(|_| SystemActorError::MessageReply("Reply channel closed".into()))?;
+            tx.send(Ok(value)).map_err(|_| SystemActorError::MessageReply("Reply channel closed".into()))?;
             Ok(())
         } else {
             Err(SystemActorError::MessageReply("No active message processing".into()))
         }
     }
 
+    /// Ends a received message without handling it, the sender gets `error` with the final reply.
+    ///
+    /// Messages that are never replied are delivered again when the actor restarts, this is for
+    /// messages the actor can't handle at all, e.g. unknown or undecodable ones.
+    pub async fn reject(&mut self, frame: &FrameMessage, error: impl std::fmt::Display) {
+        let handle = self.start_message_processing(frame.clone()).await;
+        self.finish_message_processing(Some(error.to_string())).await;
+        if let Err(e) = handle.await {
+            error!("Error during reply cleanup: {}", e);
+        }
+    }
+
     /// Waits for and streams all replies to a sent message.
     ///
     /// This method establishes a live query to receive replies as they arrive. The reply
@@ -1447,6 +2302,7 @@ impl<T: Actor> ActorContext<T> {
     /// # Arguments
     ///
     /// * `reply_id` - The ID of the reply to wait for
+    /// * `replies` - The replies subscribed when the message was sent
     /// * `options` - Options controlling timeout and other behaviors
     ///
     /// # Returns
@@ -1494,37 +2350,33 @@ impl<T: Actor> ActorContext<T> {
     async fn wait_for_replies<RT: MessageType + 'static>(
         &self,
         reply_id: &RecordId,
+        replies: Option<SentReplies>,
         options: SendOptions,
     ) -> Result<ReplyStream<RT>, SystemActorError> {
         // Debug print for starting to wait for replies
         debug!("[{}] reply-wait {} {}", self.id().record_id(), std::any::type_name::<RT>(), reply_id.key());
 
-        // Set up the live query for replies
-        let query =
-            format!("LIVE SELECT id.{{id, chunk}}, * FROM {} WHERE id.id = '{}'", DB_TABLE_REPLY, reply_id.key());
-        debug!("[{}] reply-live {}", self.id().record_id(), query);
-
-        let mut res = self.engine().db().lock().await.query(&query).await?;
-        let notification_stream = res.stream::<Notification<FrameReply>>(0)?;
+        let msg_id = RecordId::from_table_key(DB_TABLE_MESSAGE, reply_id.key().clone());
+        let replies = self.reply_frames(&msg_id, replies).await?;
         let self_id = self.id().clone();
 
-        // Transform the notification stream into a reply stream
-        let stream = notification_stream
-            // Only process Create actions
-            .filter(|n| future::ready(matches!(n, Ok(n) if n.action == Action::Create)))
-            .map(|n| -> Result<FrameReply, SystemActorError> {
-                let n = n?;
-                Ok(n.data)
-            })
-            // Take messages until we get final message (chunk = None)
-            .take_while(|reply| {
-                future::ready(match reply {
-                    Ok(reply) => reply.id.chunk.is_some(), // Continue while we have chunks
-                    Err(_) => false,                       // Stop on error
-                })
-            })
+        // Take replies until the final one (chunk = None), keeping the error of a failed handler
+        let replies = futures::stream::unfold((replies, false), |(mut replies, ended)| async move {
+            if ended {
+                return None;
+            }
+            match replies.next().await? {
+                Ok(reply) if reply.id.chunk.is_some() => Some((Ok(reply), (replies, false))),
+                Ok(reply) if !reply.err.is_null() => Some((Ok(reply), (replies, true))),
+                // Stop on the final reply, or on error
+                _ => None,
+            }
+        });
+
+        // Transform the replies into a stream of responses
+        let stream = replies
             // Process each reply
-            .map(move |reply| -> Result<RT, SystemActorError> {
+            .map(move |reply: Result<FrameReply, SystemActorError>| -> Result<RT, SystemActorError> {
                 let reply = reply?;
                 debug!(
                     "[{}] reply-recv {} {} chunk={:?}",
@@ -1535,36 +2387,165 @@ impl<T: Actor> ActorContext<T> {
                 );
 
                 if !reply.err.is_null() {
-                    return Err(SystemActorError::MessageReply(reply.err.to_string().into()));
+                    let err = reply.err.as_str().map(String::from).unwrap_or_else(|| reply.err.to_string());
+                    return Err(SystemActorError::MessageReply(err.into()));
                 }
 
                 let response: RT = serde_json::from_value(reply.msg)?;
                 Ok(response)
             });
 
-        // Timeout stream that maps all items through a timeout
+        // Timeout stream that maps all items through a timeout, and cancels the message if the
+        // timeout fires or the stream is dropped before the final reply
         let timeout_duration = options.timeout;
-        let stream =
-            futures::stream::unfold((Box::pin(stream), timeout_duration), |(mut stream, timeout)| async move {
+        let pending = PendingReplies { engine: self.engine.clone(), msg_id, done: false };
+        let stream = futures::stream::unfold(
+            (Box::pin(stream), timeout_duration, pending),
+            |(mut stream, timeout, mut pending)| async move {
                 match tokio::time::timeout(timeout, stream.next()).await {
-                    Ok(Some(item)) => Some((item, (stream, timeout))),
-                    Ok(None) => None,
+                    Ok(Some(item)) => Some((item, (stream, timeout, pending))),
+                    Ok(None) => {
+                        pending.complete();
+                        None
+                    }
                     Err(_) => {
                         error!(
                             "Stream item timeout after {:?} for message type {}",
                             timeout,
                             std::any::type_name::<RT>()
                         );
+                        pending.cancel();
                         Some((
                             Err(SystemActorError::MessageTimeout(std::any::type_name::<RT>().into(), timeout)),
-                            (stream, timeout),
+                            (stream, timeout, pending),
                         ))
                     }
                 }
-            });
+            },
+        );
 
         Ok(Box::pin(stream))
     }
+    /// Streams the replies to a sent message, as delivered by the backend or in memory.
+    ///
+    /// The message is removed once its final reply is received, if configured.
+    async fn reply_frames(
+        &self,
+        msg_id: &RecordId,
+        replies: Option<SentReplies>,
+    ) -> Result<ReplyFrameStream, SystemActorError> {
+        let is_local = matches!(replies, Some(SentReplies::Local(_)));
+        let replies: ReplyFrameStream = match replies {
+            Some(SentReplies::Local(local_replies)) => {
+                Box::pin(futures::stream::unfold(local_replies, |mut local_replies| async move {
+                    local_replies.recv().await.map(|reply| (Ok(reply), local_replies))
+                }))
+            }
+            Some(SentReplies::Backend(replies)) => replies,
+            // Sent without subscribing, replies sent before this point may be missed
+            None => self.engine().backend().subscribe_replies(msg_id).await?,
+        };
+        let engine = self.engine().clone();
+        let msg_id = msg_id.clone();
+
+        // Remove the message once the final reply is consumed, if configured
+        let replies = replies.inspect(move |reply| {
+            // Other backends drop messages with their final reply already
+            if matches!(reply, Ok(reply) if reply.id.chunk.is_none())
+                && engine.options().retention.delete_on_final_reply
+                && engine.backend().surreal().is_some()
+            {
+                if is_local {
+                    // Stored behind local delivery, delete it after it is written
+                    if engine.options().local_delivery.stores_messages() {
+                        engine.local().defer(Deferred::Delete(msg_id.clone()));
+                    }
+                    return;
+                }
+                let engine = engine.clone();
+                let msg_id = msg_id.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = engine.delete_messages(vec![msg_id]).await {
+                        error!("retention-delete {}", e);
+                    }
+                });
+            }
+        });
+        Ok(Box::pin(replies))
+    }
+}
+
+/// Sends a reply to the sender of `frame`.
+///
+/// Replies to messages delivered through the database are stored, and received by the sender's
+/// live query. Replies to locally delivered messages are passed to the sender's reply stream, and
+/// stored behind it depending on `LocalDelivery`.
+pub(crate) async fn send_reply(
+    engine: &Engine,
+    frame: &FrameMessage,
+    local_replies: Option<&mpsc::UnboundedSender<FrameReply>>,
+    reply: FrameReply,
+) -> Result<(), SystemActorError> {
+    if frame.local {
+        let delivery = engine.options().local_delivery;
+        if delivery.stores_reply(reply.is_final()) {
+            let deferred = Deferred::Reply { msg_id: frame.id.clone(), reply: reply.clone() };
+            engine.local().defer(deferred);
+        }
+        // The sender may have stopped waiting
+        if let Some(local_replies) = local_replies {
+            let _ = local_replies.send(reply);
+        }
+        return Ok(());
+    }
+
+    engine.backend().append_reply(frame.id.clone(), reply).await
+}
+
+impl<T: Actor> Drop for ActorContext<T> {
+    fn drop(&mut self) {
+        for (_, watch) in self.watches.drain() {
+            watch.abort();
+        }
+
+        let owner = self.lease.take().map(|_| self.engine.node_id().to_string());
+        // Contexts that only send, such as `Relay`, leave no exit but still release their lease
+        let reason = if !*self.started.get_mut() {
+            None
+        } else if std::thread::panicking() {
+            Some(ExitReason::Panic("panicked".to_string()))
+        } else if let Some(exit) = self.exit.take() {
+            Some(exit)
+        } else if self.running {
+            Some(ExitReason::Aborted)
+        } else {
+            Some(ExitReason::Normal)
+        };
+        if reason.is_none() && owner.is_none() {
+            return;
+        }
+
+        // Record the exit so watchers are notified, unless another instance took over the actor
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let backend = self.engine.backend().clone();
+        let record_id = self.id.record_id();
+        let instance = self.instance.clone();
+        runtime.spawn(async move {
+            if let Some(reason) = reason {
+                debug!("[{}] ctx-drop {}", record_id, reason);
+                if let Err(e) = backend.record_exit(&record_id, &instance, reason).await {
+                    error!("[{}] ctx-exit-error {}", record_id, e);
+                }
+            }
+            if let Some(owner) = owner {
+                if let Err(e) = backend.release_lease(&record_id, &owner, &instance).await {
+                    error!("[{}] ctx-lease-error {}", record_id, e);
+                }
+            }
+        });
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,374 @@
+use super::{BackendFuture, CancelStream, EngineBackend, ExitStream, ReplyFrameStream};
+use crate::actor::{ActorRecord, FrameReply, DB_TABLE_MESSAGE};
+use crate::directory::ActorQuery;
+use crate::lease::LeaseClaim;
+use crate::prelude::*;
+use object_store::{path::Path, ObjectStore, PutPayload};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use surrealdb::{engine::any::Any, RecordId, Surreal};
+use tracing::{debug, error};
+
+/// Key of the reference left in place of an offloaded payload.
+const CLAIM_CHECK_KEY: &str = "$claim_check";
+
+/// Rules for storing large message and reply payloads in the object store.
+///
+/// A payload whose JSON is larger than `threshold` is stored in the engine's object store, and
+/// the database only keeps a reference to it. Receivers and senders load the payload back
+/// before decoding, so actors are not aware of it. Payloads are removed once their message is
+/// removed from the database and is not a dead letter, see `Engine::collect_claim_checks`.
+///
+/// # Example
+///
+/// ```rust
+/// // Keep payloads over 256 KiB out of the database
+/// let claim_check = ClaimCheckOptions::builder().threshold(256 * 1024).build();
+/// let options = EngineOptions::builder().claim_check(claim_check).build();
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct ClaimCheckOptions {
+    /// Payloads larger than this many bytes are stored in the object store. Never if not set.
+    #[serde(default)]
+    pub threshold: Option<usize>,
+    /// Path prefix of the payloads in the object store.
+    #[builder(default = default_prefix())]
+    #[serde(default = "default_prefix")]
+    pub prefix: Cow<'static, str>,
+    /// How often payloads of removed messages are deleted.
+    #[builder(default = default_gc_interval())]
+    #[serde(default = "default_gc_interval", with = "humantime_serde")]
+    pub gc_interval: Duration,
+    /// Payloads are kept at least this long, while their message is still being delivered.
+    #[builder(default = default_gc_grace())]
+    #[serde(default = "default_gc_grace", with = "humantime_serde")]
+    pub gc_grace: Duration,
+}
+
+fn default_prefix() -> Cow<'static, str> {
+    "claim-check".into()
+}
+
+fn default_gc_interval() -> Duration {
+    Duration::from_secs(600)
+}
+
+fn default_gc_grace() -> Duration {
+    Duration::from_secs(3600)
+}
+
+impl Default for ClaimCheckOptions {
+    fn default() -> Self {
+        ClaimCheckOptions::builder().build()
+    }
+}
+
+/// Offloads large payloads to an object store, and loads them back.
+#[derive(Clone, Debug)]
+struct ClaimCheck {
+    store: Arc<dyn ObjectStore>,
+    threshold: usize,
+    prefix: Cow<'static, str>,
+}
+
+impl ClaimCheck {
+    /// Stores `value` if larger than the threshold, replacing it with a reference.
+    async fn offload(&self, msg_id: &RecordId, value: &mut Value) -> Result<(), SystemActorError> {
+        if value.is_null() {
+            return Ok(());
+        }
+        let bytes = serde_json::to_vec(value)?;
+        if bytes.len() <= self.threshold {
+            return Ok(());
+        }
+        let size = bytes.len();
+        let path = message_dir(&self.prefix, msg_id).child(format!("{}.json", ulid::Ulid::new()));
+        self.store.put(&path, PutPayload::from(bytes)).await?;
+        debug!("claim-check-store {} {} {}", msg_id, path, size);
+        *value = json!({ CLAIM_CHECK_KEY: { "path": path.as_ref(), "size": size } });
+        Ok(())
+    }
+
+    /// Loads the payload back if `value` is a reference.
+    async fn load(&self, value: &mut Value) -> Result<(), SystemActorError> {
+        let Some(path) = reference(value) else {
+            return Ok(());
+        };
+        let path = Path::parse(path)?;
+        let bytes = self.store.get(&path).await?.bytes().await?;
+        *value = serde_json::from_slice(&bytes)?;
+        Ok(())
+    }
+
+    /// Loads the content of a message, keeping the reference if the payload can't be loaded.
+    async fn load_message(&self, mut frame: FrameMessage) -> FrameMessage {
+        if let Err(e) = self.load(&mut frame.msg).await {
+            error!("claim-check-load {} {}", frame.id(), e);
+        }
+        frame
+    }
+}
+
+/// The directory of the payloads of a message, named after its key.
+fn message_dir(prefix: &str, msg_id: &RecordId) -> Path {
+    let key: String = msg_id.key().to_string().chars().filter(char::is_ascii_alphanumeric).collect();
+    Path::from(format!("{}/{}", prefix, key))
+}
+
+/// The path of the payload, if `value` is a reference.
+fn reference(value: &Value) -> Option<&str> {
+    let object = value.as_object().filter(|object| object.len() == 1)?;
+    object.get(CLAIM_CHECK_KEY)?.get("path")?.as_str()
+}
+
+/// Backend storing large payloads of messages and replies in an object store.
+///
+/// Wraps the configured backend when `ClaimCheckOptions::threshold` is set.
+#[derive(Debug)]
+pub(crate) struct ClaimCheckBackend {
+    inner: Arc<dyn EngineBackend>,
+    claim_check: ClaimCheck,
+}
+
+impl ClaimCheckBackend {
+    pub(crate) fn new(
+        inner: Arc<dyn EngineBackend>,
+        store: Arc<dyn ObjectStore>,
+        threshold: usize,
+        prefix: &str,
+    ) -> Self {
+        let claim_check = ClaimCheck { store, threshold, prefix: prefix.to_string().into() };
+        Self { inner, claim_check }
+    }
+}
+
+impl EngineBackend for ClaimCheckBackend {
+    fn get_actor<'a>(&'a self, id: &'a RecordId) -> BackendFuture<'a, Option<ActorRecord>> {
+        self.inner.get_actor(id)
+    }
+
+    fn put_actor(&self, record: ActorRecord) -> BackendFuture<'_, ()> {
+        self.inner.put_actor(record)
+    }
+
+    fn delete_actor<'a>(&'a self, id: &'a RecordId) -> BackendFuture<'a, Option<ActorRecord>> {
+        self.inner.delete_actor(id)
+    }
+
+    fn record_exit<'a>(&'a self, id: &'a RecordId, instance: &'a str, exit: ExitReason) -> BackendFuture<'a, ()> {
+        self.inner.record_exit(id, instance, exit)
+    }
+
+    fn acquire_lease<'a>(
+        &'a self,
+        actor: &'a ActorId,
+        owner: &'a str,
+        instance: &'a str,
+        ttl: Duration,
+    ) -> BackendFuture<'a, LeaseClaim> {
+        self.inner.acquire_lease(actor, owner, instance, ttl)
+    }
+
+    fn renew_lease<'a>(
+        &'a self,
+        id: &'a RecordId,
+        owner: &'a str,
+        instance: &'a str,
+        ttl: Duration,
+    ) -> BackendFuture<'a, bool> {
+        self.inner.renew_lease(id, owner, instance, ttl)
+    }
+
+    fn release_lease<'a>(&'a self, id: &'a RecordId, owner: &'a str, instance: &'a str) -> BackendFuture<'a, ()> {
+        self.inner.release_lease(id, owner, instance)
+    }
+
+    fn find_actors<'a>(&'a self, query: &'a ActorQuery) -> BackendFuture<'a, Vec<ActorRecord>> {
+        self.inner.find_actors(query)
+    }
+
+    fn enqueue(&self, mut frame: FrameMessage) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let id = frame.id().clone();
+            self.claim_check.offload(&id, &mut frame.msg).await?;
+            self.inner.enqueue(frame).await
+        })
+    }
+
+    fn append_reply(&self, msg_id: RecordId, mut reply: FrameReply) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            self.claim_check.offload(&msg_id, &mut reply.msg).await?;
+            self.inner.append_reply(msg_id, reply).await
+        })
+    }
+
+    fn unreplied<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, Vec<FrameMessage>> {
+        Box::pin(async move {
+            let mut messages = Vec::new();
+            for frame in self.inner.unreplied(rx).await? {
+                messages.push(self.claim_check.load_message(frame).await);
+            }
+            Ok(messages)
+        })
+    }
+
+    fn unreplied_count<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, usize> {
+        self.inner.unreplied_count(rx)
+    }
+
+    fn subscribe_messages<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, MessageStream> {
+        Box::pin(async move {
+            let claim_check = self.claim_check.clone();
+            let stream = self.inner.subscribe_messages(rx).await?.then(move |item| {
+                let claim_check = claim_check.clone();
+                async move {
+                    match item {
+                        Ok(frame) => Ok(claim_check.load_message(frame).await),
+                        Err(e) => Err(e),
+                    }
+                }
+            });
+            let stream: MessageStream = Box::pin(stream);
+            Ok(stream)
+        })
+    }
+
+    fn subscribe_replies<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, ReplyFrameStream> {
+        Box::pin(async move {
+            let claim_check = self.claim_check.clone();
+            let stream = self.inner.subscribe_replies(msg_id).await?.then(move |item| {
+                let claim_check = claim_check.clone();
+                async move {
+                    let mut reply = item?;
+                    claim_check.load(&mut reply.msg).await?;
+                    Ok(reply)
+                }
+            });
+            let stream: ReplyFrameStream = Box::pin(stream);
+            Ok(stream)
+        })
+    }
+
+    fn message_exists<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, bool> {
+        self.inner.message_exists(msg_id)
+    }
+
+    fn cancel<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, ()> {
+        self.inner.cancel(msg_id)
+    }
+
+    fn subscribe_cancels<'a>(&'a self, owner: &'a str) -> BackendFuture<'a, CancelStream> {
+        self.inner.subscribe_cancels(owner)
+    }
+
+    fn subscribe_exit<'a>(&'a self, id: &'a RecordId) -> BackendFuture<'a, ExitStream> {
+        self.inner.subscribe_exit(id)
+    }
+
+    fn requeue_dead_letter<'a>(
+        &'a self,
+        dead_letter_id: &'a RecordId,
+        mut frame: FrameMessage,
+    ) -> BackendFuture<'a, bool> {
+        Box::pin(async move {
+            // Stored again under the new message, the payload of the dead letter is collected with it
+            let id = frame.id().clone();
+            self.claim_check.load(&mut frame.msg).await?;
+            self.claim_check.offload(&id, &mut frame.msg).await?;
+            self.inner.requeue_dead_letter(dead_letter_id, frame).await
+        })
+    }
+
+    fn health(&self) -> BackendFuture<'_, bool> {
+        self.inner.health()
+    }
+
+    fn live_restarts(&self) -> HashMap<String, u64> {
+        self.inner.live_restarts()
+    }
+
+    fn reset(&self) -> BackendFuture<'_, ()> {
+        self.inner.reset()
+    }
+
+    fn surreal(&self) -> Option<Surreal<Any>> {
+        self.inner.surreal()
+    }
+}
+
+impl Engine {
+    /// Deletes the stored payloads of messages removed from the database.
+    ///
+    /// Payloads of dead letters, and those younger than `ClaimCheckOptions::gc_grace`, are kept.
+    /// Returns the number of payloads deleted.
+    pub async fn collect_claim_checks(&self) -> Result<usize, SystemActorError> {
+        let options = &self.options().claim_check;
+        let store = self.store();
+        let prefix = Path::from(options.prefix.as_ref());
+        let now = chrono::Utc::now();
+
+        // Payloads by message key, unless one of them is recent
+        let mut payloads: HashMap<String, Vec<Path>> = HashMap::new();
+        let mut recent = HashSet::new();
+        let mut listing = store.list(Some(&prefix));
+        while let Some(meta) = listing.next().await.transpose()? {
+            let Some(key) = meta.location.prefix_match(&prefix).and_then(|mut parts| parts.next()) else {
+                continue;
+            };
+            let key = key.as_ref().to_string();
+            let age = now.signed_duration_since(meta.last_modified).to_std().unwrap_or_default();
+            if age < options.gc_grace {
+                recent.insert(key.clone());
+            }
+            payloads.entry(key).or_default().push(meta.location);
+        }
+
+        let mut deleted = 0;
+        for (key, paths) in payloads {
+            if recent.contains(&key) {
+                continue;
+            }
+            let msg_id = RecordId::from_table_key(DB_TABLE_MESSAGE, key.clone());
+            if self.backend().message_exists(&msg_id).await? {
+                continue;
+            }
+            // Dead letters keep the payload of their message until requeued or purged
+            if self.dead_letter_exists(&key).await? {
+                continue;
+            }
+            for path in paths {
+                store.delete(&path).await?;
+                deleted += 1;
+            }
+        }
+        if deleted > 0 {
+            debug!("claim-check-collect {}", deleted);
+        }
+        Ok(deleted)
+    }
+}
+
+/// Spawns the background task deleting unreferenced payloads, if claim checks are enabled.
+pub(crate) fn spawn_claim_check_gc(engine: &Engine) {
+    let options = engine.options().claim_check.clone();
+    if options.threshold.is_none() {
+        return;
+    }
+    let engine = engine.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(options.gc_interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = engine.shutdown_token().cancelled() => break,
+            }
+            if let Err(e) = engine.collect_claim_checks().await {
+                error!("claim-check-collect {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,259 @@
+use super::{BackendFuture, CancelStream, EngineBackend, ReplyFrameStream};
+use crate::actor::{ActorRecord, FrameReply};
+use crate::directory::ActorQuery;
+use crate::lease::{Lease, LeaseClaim};
+use crate::prelude::*;
+use futures::Stream;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use surrealdb::RecordId;
+use tokio::sync::mpsc;
+
+/// Backend keeping actors, messages and replies in the memory of the process.
+///
+/// Nothing is persisted and only actors spawned on engines sharing this backend can talk to
+/// each other. Meant for unit tests and embedded use where no database is available.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemoryBackend {
+    state: Arc<Mutex<MemoryState>>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    actors: HashMap<String, ActorRecord>,
+    /// Messages without a final reply, and whether they got any reply
+    pending: HashMap<String, (FrameMessage, bool)>,
+    messages: HashMap<String, Vec<mpsc::UnboundedSender<Result<FrameMessage, SystemActorError>>>>,
+    replies: HashMap<String, Vec<mpsc::UnboundedSender<Result<FrameReply, SystemActorError>>>>,
+    /// Cancelled messages without a final reply, with the lease owner of their receiver
+    cancelled: Vec<(RecordId, Option<String>)>,
+    /// Cancel subscribers, with their node id
+    cancels: Vec<(String, mpsc::UnboundedSender<Result<RecordId, SystemActorError>>)>,
+}
+
+impl MemoryBackend {
+    fn state(&self) -> std::sync::MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap()
+    }
+}
+
+fn holds_lease(record: &ActorRecord, owner: &str, instance: &str) -> bool {
+    record.instance.as_deref() == Some(instance) && record.lease.as_ref().is_some_and(|lease| lease.owner == owner)
+}
+
+impl MemoryState {
+    /// The lease owner of the receiver of a pending message.
+    fn receiver_owner(&self, msg_id: &RecordId) -> Option<String> {
+        let (frame, _) = self.pending.get(&msg_id.to_string())?;
+        let record = self.actors.get(&frame.rx.to_string())?;
+        record.lease.as_ref().map(|lease| lease.owner.clone())
+    }
+}
+
+/// Whether a cancel is for the node `node`, given the lease owner of the receiver.
+fn hosts(owner: Option<&str>, node: &str) -> bool {
+    owner.is_none_or(|owner| owner == node)
+}
+
+fn receiver_stream<T: Send + 'static>(rx: mpsc::UnboundedReceiver<T>) -> impl Stream<Item = T> + Send {
+    futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|item| (item, rx)) })
+}
+
+impl EngineBackend for MemoryBackend {
+    fn get_actor<'a>(&'a self, id: &'a RecordId) -> BackendFuture<'a, Option<ActorRecord>> {
+        let record = self.state().actors.get(&id.to_string()).cloned();
+        Box::pin(async move { Ok(record) })
+    }
+
+    fn put_actor(&self, mut record: ActorRecord) -> BackendFuture<'_, ()> {
+        let mut state = self.state();
+        let key = record.id.to_string();
+        record.lease = state.actors.get(&key).and_then(|previous| previous.lease.clone());
+        state.actors.insert(key, record);
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn delete_actor<'a>(&'a self, id: &'a RecordId) -> BackendFuture<'a, Option<ActorRecord>> {
+        let record = self.state().actors.remove(&id.to_string());
+        Box::pin(async move { Ok(record) })
+    }
+
+    fn record_exit<'a>(&'a self, id: &'a RecordId, instance: &'a str, exit: ExitReason) -> BackendFuture<'a, ()> {
+        if let Some(record) = self.state().actors.get_mut(&id.to_string()) {
+            if record.instance.as_deref() == Some(instance) {
+                record.exit = Some(exit);
+            }
+        }
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn acquire_lease<'a>(
+        &'a self,
+        actor: &'a ActorId,
+        owner: &'a str,
+        instance: &'a str,
+        ttl: Duration,
+    ) -> BackendFuture<'a, LeaseClaim> {
+        let mut state = self.state();
+        let id = actor.record_id();
+        let before = state.actors.get(&id.to_string()).cloned();
+        let claim = match before.as_ref().and_then(|record| record.lease.as_ref()) {
+            Some(lease) if !lease.is_free_for(owner) => LeaseClaim::Held(lease.clone()),
+            _ => {
+                let record = state.actors.entry(id.to_string()).or_insert_with(|| ActorRecord {
+                    id: id.clone(),
+                    name: actor.name().to_string().into(),
+                    tag: actor.tag().to_string().into(),
+                    state: Default::default(),
+                    instance: None,
+                    exit: None,
+                    lease: None,
+                });
+                record.instance = Some(instance.to_string());
+                record.lease = Some(Lease::new(owner, ttl));
+                LeaseClaim::Acquired(before)
+            }
+        };
+        Box::pin(async move { Ok(claim) })
+    }
+
+    fn renew_lease<'a>(
+        &'a self,
+        id: &'a RecordId,
+        owner: &'a str,
+        instance: &'a str,
+        ttl: Duration,
+    ) -> BackendFuture<'a, bool> {
+        let mut state = self.state();
+        let renewed = match state.actors.get_mut(&id.to_string()) {
+            Some(record) if holds_lease(record, owner, instance) => {
+                record.lease = Some(Lease::new(owner, ttl));
+                true
+            }
+            _ => false,
+        };
+        Box::pin(async move { Ok(renewed) })
+    }
+
+    fn release_lease<'a>(&'a self, id: &'a RecordId, owner: &'a str, instance: &'a str) -> BackendFuture<'a, ()> {
+        if let Some(record) = self.state().actors.get_mut(&id.to_string()) {
+            if holds_lease(record, owner, instance) {
+                record.lease = None;
+            }
+        }
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn find_actors<'a>(&'a self, query: &'a ActorQuery) -> BackendFuture<'a, Vec<ActorRecord>> {
+        let mut records: Vec<ActorRecord> =
+            self.state().actors.values().filter(|record| query.matches(record)).cloned().collect();
+        records.sort_by(|a, b| a.name.cmp(&b.name));
+        Box::pin(async move { Ok(records) })
+    }
+
+    fn enqueue(&self, frame: FrameMessage) -> BackendFuture<'_, ()> {
+        let mut state = self.state();
+        // Locally delivered messages are stored after delivery, they are not received again
+        if !frame.local {
+            if let Some(subscribers) = state.messages.get_mut(&frame.rx.to_string()) {
+                subscribers.retain(|tx| tx.send(Ok(frame.clone())).is_ok());
+            }
+        }
+        state.pending.insert(frame.id().to_string(), (frame, false));
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn append_reply(&self, msg_id: RecordId, reply: FrameReply) -> BackendFuture<'_, ()> {
+        let key = msg_id.to_string();
+        let mut state = self.state();
+        if reply.is_final() {
+            state.pending.remove(&key);
+            state.cancelled.retain(|(id, _)| *id != msg_id);
+        } else if let Some((_, replied)) = state.pending.get_mut(&key) {
+            *replied = true;
+        }
+        if let Some(subscribers) = state.replies.get_mut(&key) {
+            subscribers.retain(|tx| tx.send(Ok(reply.clone())).is_ok());
+            if subscribers.is_empty() || reply.is_final() {
+                state.replies.remove(&key);
+            }
+        }
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn unreplied<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, Vec<FrameMessage>> {
+        let mut messages: Vec<FrameMessage> = self
+            .state()
+            .pending
+            .values()
+            .filter(|(frame, replied)| !replied && frame.rx == *rx)
+            .map(|(frame, _)| frame.clone())
+            .collect();
+        messages.sort_by(|a, b| b.priority.cmp(&a.priority).then_with(|| a.id().to_string().cmp(&b.id().to_string())));
+        Box::pin(async move { Ok(messages) })
+    }
+
+    fn unreplied_count<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, usize> {
+        let count = self.state().pending.values().filter(|(frame, replied)| !replied && frame.rx == *rx).count();
+        Box::pin(async move { Ok(count) })
+    }
+
+    fn subscribe_messages<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, MessageStream> {
+        let (tx, stream) = mpsc::unbounded_channel();
+        self.state().messages.entry(rx.to_string()).or_default().push(tx);
+        let stream: MessageStream = Box::pin(receiver_stream(stream));
+        Box::pin(async move { Ok(stream) })
+    }
+
+    fn subscribe_replies<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, ReplyFrameStream> {
+        let (tx, stream) = mpsc::unbounded_channel();
+        self.state().replies.entry(msg_id.to_string()).or_default().push(tx);
+        let stream: ReplyFrameStream = Box::pin(receiver_stream(stream));
+        Box::pin(async move { Ok(stream) })
+    }
+
+    fn message_exists<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, bool> {
+        let exists = self.state().pending.contains_key(&msg_id.to_string());
+        Box::pin(async move { Ok(exists) })
+    }
+
+    fn cancel<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, ()> {
+        let mut state = self.state();
+        let owner = state.receiver_owner(msg_id);
+        if state.pending.contains_key(&msg_id.to_string()) && !state.cancelled.iter().any(|(id, _)| id == msg_id) {
+            state.cancelled.push((msg_id.clone(), owner.clone()));
+        }
+        state.cancels.retain(|(node, tx)| !hosts(owner.as_deref(), node) || tx.send(Ok(msg_id.clone())).is_ok());
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn subscribe_cancels<'a>(&'a self, owner: &'a str) -> BackendFuture<'a, CancelStream> {
+        let (tx, stream) = mpsc::unbounded_channel();
+        let mut state = self.state();
+        for (msg_id, _) in state.cancelled.iter().filter(|(_, receiver)| hosts(receiver.as_deref(), owner)) {
+            let _ = tx.send(Ok(msg_id.clone()));
+        }
+        state.cancels.push((owner.to_string(), tx));
+        let stream: CancelStream = Box::pin(receiver_stream(stream));
+        Box::pin(async move { Ok(stream) })
+    }
+
+    fn health(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async move { Ok(true) })
+    }
+
+    fn reset(&self) -> BackendFuture<'_, ()> {
+        let mut state = self.state();
+        state.actors.clear();
+        state.pending.clear();
+        state.cancelled.clear();
+        Box::pin(async move { Ok(()) })
+    }
+}
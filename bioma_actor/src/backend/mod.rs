@@ -0,0 +1,148 @@
+mod claim_check;
+mod memory;
+mod reconnect;
+mod surreal;
+
+pub use claim_check::ClaimCheckOptions;
+pub(crate) use claim_check::{spawn_claim_check_gc, ClaimCheckBackend};
+pub use memory::MemoryBackend;
+pub(crate) use reconnect::Recent;
+pub use reconnect::ReconnectOptions;
+pub use surreal::SurrealBackend;
+
+use crate::actor::{ActorRecord, FrameReply};
+use crate::directory::ActorQuery;
+use crate::lease::LeaseClaim;
+use crate::prelude::*;
+use futures::Stream;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::pin::Pin;
+use std::time::Duration;
+use surrealdb::{engine::any::Any, RecordId, Surreal};
+
+/// Future returned by `EngineBackend` operations.
+pub type BackendFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, SystemActorError>> + Send + 'a>>;
+
+/// Stream of replies to a message, as delivered by `EngineBackend::subscribe_replies`.
+pub type ReplyFrameStream = Pin<Box<dyn Stream<Item = Result<FrameReply, SystemActorError>> + Send>>;
+
+/// Stream of cancelled message ids, as delivered by `EngineBackend::subscribe_cancels`.
+pub type CancelStream = Pin<Box<dyn Stream<Item = Result<RecordId, SystemActorError>> + Send>>;
+
+/// Stream of how an actor terminated, as delivered by `EngineBackend::subscribe_exit`.
+pub type ExitStream = Pin<Box<dyn Stream<Item = Result<ExitReason, SystemActorError>> + Send>>;
+
+/// Storage used by the `Engine` for actor records, messages and replies.
+///
+/// `SurrealBackend` is the default and keeps everything in SurrealDB, shared by every process
+/// connected to the same database. `MemoryBackend` keeps everything in the process, for unit
+/// tests and embedded use where actors are only local.
+///
+/// Features built on SurrealDB queries (schedules, topics, dead letters, retention, the event
+/// journal, watches) are only available when `surreal` returns the database client.
+pub trait EngineBackend: Debug + Send + Sync {
+    /// Loads an actor record.
+    fn get_actor<'a>(&'a self, id: &'a RecordId) -> BackendFuture<'a, Option<ActorRecord>>;
+
+    /// Creates or replaces an actor record, keeping its lease.
+    fn put_actor(&self, record: ActorRecord) -> BackendFuture<'_, ()>;
+
+    /// Deletes an actor record, returning it if it existed.
+    fn delete_actor<'a>(&'a self, id: &'a RecordId) -> BackendFuture<'a, Option<ActorRecord>>;
+
+    /// Records how an actor instance terminated, unless another instance took over the record.
+    fn record_exit<'a>(&'a self, id: &'a RecordId, instance: &'a str, exit: ExitReason) -> BackendFuture<'a, ()>;
+
+    /// Takes the lease of an actor for `owner` and `instance`, creating the record if needed.
+    ///
+    /// Succeeds if the record has no lease, the lease already belongs to `owner`, or it expired.
+    fn acquire_lease<'a>(
+        &'a self,
+        actor: &'a ActorId,
+        owner: &'a str,
+        instance: &'a str,
+        ttl: Duration,
+    ) -> BackendFuture<'a, LeaseClaim>;
+
+    /// Extends the lease of an actor, returning `false` if `owner` and `instance` lost it.
+    fn renew_lease<'a>(
+        &'a self,
+        id: &'a RecordId,
+        owner: &'a str,
+        instance: &'a str,
+        ttl: Duration,
+    ) -> BackendFuture<'a, bool>;
+
+    /// Releases the lease of an actor, if still held by `owner` and `instance`.
+    fn release_lease<'a>(&'a self, id: &'a RecordId, owner: &'a str, instance: &'a str) -> BackendFuture<'a, ()>;
+
+    /// Actor records matching a query, ordered by name.
+    fn find_actors<'a>(&'a self, query: &'a ActorQuery) -> BackendFuture<'a, Vec<ActorRecord>>;
+
+    /// Enqueues a message for its receiver.
+    fn enqueue(&self, frame: FrameMessage) -> BackendFuture<'_, ()>;
+
+    /// Appends a reply chunk, or the final reply, to a message.
+    fn append_reply(&self, msg_id: RecordId, reply: FrameReply) -> BackendFuture<'_, ()>;
+
+    /// Messages to `rx` without any reply, highest priority first, then oldest first.
+    fn unreplied<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, Vec<FrameMessage>>;
+
+    /// Number of messages to `rx` without any reply, without loading them.
+    fn unreplied_count<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, usize>;
+
+    /// Messages enqueued for `rx` from now on.
+    ///
+    /// Messages delivered in memory (`FrameMessage::local`) are stored after delivery and must
+    /// not be delivered again.
+    fn subscribe_messages<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, MessageStream>;
+
+    /// Replies appended to `msg_id` from now on.
+    ///
+    /// Senders subscribe before the message is enqueued, so no reply is sent before subscribing.
+    fn subscribe_replies<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, ReplyFrameStream>;
+
+    /// Whether a message is still stored, replied or not.
+    fn message_exists<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, bool>;
+
+    /// Marks a message as cancelled by its sender.
+    fn cancel<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, ()>;
+
+    /// Ids of the messages cancelled and not replied yet, then of those cancelled from now on.
+    ///
+    /// Only messages to receivers leased by the engine `owner`, or not leased, are included.
+    fn subscribe_cancels<'a>(&'a self, owner: &'a str) -> BackendFuture<'a, CancelStream>;
+
+    /// How an actor terminated, delivered once it has, `ExitReason::NotFound` if it does not exist.
+    fn subscribe_exit<'a>(&'a self, _id: &'a RecordId) -> BackendFuture<'a, ExitStream> {
+        Box::pin(async { Err(SystemActorError::Unsupported("watches".into())) })
+    }
+
+    /// Enqueues `frame` in place of a dead letter, removing the dead letter in the same transaction.
+    ///
+    /// Returns `false`, without enqueuing, if the dead letter does not exist anymore.
+    fn requeue_dead_letter<'a>(
+        &'a self,
+        _dead_letter_id: &'a RecordId,
+        _frame: FrameMessage,
+    ) -> BackendFuture<'a, bool> {
+        Box::pin(async { Err(SystemActorError::Unsupported("dead letters".into())) })
+    }
+
+    /// Whether the storage is reachable.
+    fn health(&self) -> BackendFuture<'_, bool>;
+
+    /// Times the message stream of each receiver was opened again, by receiver record id.
+    fn live_restarts(&self) -> HashMap<String, u64> {
+        HashMap::new()
+    }
+
+    /// Removes all actors, messages and replies.
+    fn reset(&self) -> BackendFuture<'_, ()>;
+
+    /// The SurrealDB client, if this backend is backed by SurrealDB.
+    fn surreal(&self) -> Option<Surreal<Any>> {
+        None
+    }
+}
@@ -0,0 +1,228 @@
+use super::{BackendFuture, SurrealBackend};
+use crate::prelude::*;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::time::Duration;
+use surrealdb::{engine::any::Any, Surreal};
+use tokio::sync::watch;
+use tracing::warn;
+
+/// Number of delivered items remembered to skip duplicates when a live query is opened again.
+const RECENT_ITEMS: usize = 1024;
+
+/// How the engine connects and reconnects to the database.
+///
+/// At startup, `Engine::connect` retries with an exponential backoff until the database is
+/// reachable or `max_startup_wait` is exceeded. Once connected, the connection is checked every
+/// `health_check_interval`. When it is lost, the engine reconnects with the same backoff, opens
+/// the live queries of receiving actors and waiting senders again, and delivers the messages and
+/// replies stored while disconnected.
+///
+/// # Example
+///
+/// ```rust
+/// // Give up after 30 seconds at startup, retry at most every 5 seconds
+/// let reconnect = ReconnectOptions::builder()
+///     .max_startup_wait(std::time::Duration::from_secs(30))
+///     .max_delay(std::time::Duration::from_secs(5))
+///     .build();
+/// let options = EngineOptions::builder().reconnect(reconnect).build();
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct ReconnectOptions {
+    /// Delay before the first retry.
+    #[builder(default = default_initial_delay())]
+    #[serde(default = "default_initial_delay", with = "humantime_serde")]
+    pub initial_delay: Duration,
+    /// Upper bound of the delay between retries.
+    #[builder(default = default_max_delay())]
+    #[serde(default = "default_max_delay", with = "humantime_serde")]
+    pub max_delay: Duration,
+    /// Factor applied to the delay after each failed attempt.
+    #[builder(default = default_multiplier())]
+    #[serde(default = "default_multiplier")]
+    pub multiplier: u32,
+    /// Fail `Engine::connect` if the database is not reachable within this duration.
+    /// Retry forever if not set.
+    #[serde(default, with = "humantime_serde")]
+    pub max_startup_wait: Option<Duration>,
+    /// How often to check the connection.
+    #[builder(default = default_health_check_interval())]
+    #[serde(default = "default_health_check_interval", with = "humantime_serde")]
+    pub health_check_interval: Duration,
+}
+
+fn default_initial_delay() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_max_delay() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_multiplier() -> u32 {
+    2
+}
+
+fn default_health_check_interval() -> Duration {
+    Duration::from_secs(5)
+}
+
+impl Default for ReconnectOptions {
+    fn default() -> Self {
+        ReconnectOptions::builder().build()
+    }
+}
+
+impl ReconnectOptions {
+    /// The delay to wait after `delay` before the next attempt.
+    pub(crate) fn next_delay(&self, delay: Duration) -> Duration {
+        std::cmp::min(delay.saturating_mul(self.multiplier.max(1)), self.max_delay)
+    }
+}
+
+/// The keys of the last delivered items, to skip items delivered again after a reconnection.
+#[derive(Debug)]
+pub(crate) struct Recent<K> {
+    keys: VecDeque<K>,
+}
+
+impl<K: PartialEq> Recent<K> {
+    pub(crate) fn new() -> Self {
+        Self { keys: VecDeque::new() }
+    }
+
+    pub(crate) fn contains(&self, key: &K) -> bool {
+        self.keys.contains(key)
+    }
+
+    /// Remembers a key, returning `false` if it was delivered recently.
+    pub(crate) fn insert(&mut self, key: K) -> bool {
+        if self.contains(&key) {
+            return false;
+        }
+        if self.keys.len() == RECENT_ITEMS {
+            self.keys.pop_front();
+        }
+        self.keys.push_back(key);
+        true
+    }
+}
+
+pub(crate) type LiveStream<T> = Pin<Box<dyn Stream<Item = Result<T, SystemActorError>> + Send>>;
+
+/// Opens a live query on the given client, then loads the records it may have missed.
+pub(crate) type OpenLive<T> =
+    Box<dyn Fn(Surreal<Any>) -> BackendFuture<'static, (LiveStream<T>, Vec<T>)> + Send + Sync>;
+
+struct Resubscribe<T, K> {
+    backend: SurrealBackend,
+    open: OpenLive<T>,
+    key: fn(&T) -> K,
+    live: Option<LiveStream<T>>,
+    missed: VecDeque<T>,
+    recent: Recent<K>,
+    generation: watch::Receiver<u64>,
+    /// Counted in the backend restarts when set
+    label: Option<String>,
+    /// Delay before opening the live query again, set while it keeps ending without delivering
+    retry: Option<Duration>,
+}
+
+impl<T, K: PartialEq> Resubscribe<T, K> {
+    async fn reopen(&mut self) {
+        let options = self.backend.reconnect_options();
+        // Back off if the previous live query ended without delivering anything
+        if let Some(delay) = self.retry {
+            let _ = tokio::time::timeout(delay, self.generation.changed()).await;
+        }
+        self.retry = Some(self.retry.map_or(options.initial_delay, |delay| options.next_delay(delay)));
+        let mut delay = options.initial_delay;
+        loop {
+            match (self.open)(self.backend.client()).await {
+                Ok((live, missed)) => {
+                    self.live = Some(live);
+                    if let Some(label) = &self.label {
+                        self.backend.count_restart(label);
+                    }
+                    // Skip what was already delivered through the previous live query
+                    self.missed = missed.into_iter().filter(|item| !self.recent.contains(&(self.key)(item))).collect();
+                    return;
+                }
+                Err(e) => {
+                    warn!("live-reopen {}. Retrying in {:?}...", e, delay);
+                    // Retry early once the connection is back
+                    let _ = tokio::time::timeout(delay, self.generation.changed()).await;
+                    delay = options.next_delay(delay);
+                }
+            }
+        }
+    }
+}
+
+/// Wraps a live query so it survives connection losses.
+///
+/// The live query is opened again when it ends or the client reconnects, and the records stored
+/// in between are delivered first, except those delivered recently. Reopening backs off while the
+/// live query keeps ending without delivering anything. Restarts are counted under `label`, if
+/// set, see `EngineBackend::live_restarts`.
+///
+/// Errors of the live query are records that could not be decoded: they are passed to the caller
+/// and the live query is kept, opening it again would load the same records.
+pub(crate) fn resubscribing<T, K>(
+    backend: &SurrealBackend,
+    live: LiveStream<T>,
+    open: OpenLive<T>,
+    key: fn(&T) -> K,
+    label: Option<String>,
+) -> LiveStream<T>
+where
+    T: Send + 'static,
+    K: PartialEq + Send + Sync + 'static,
+{
+    let state = Resubscribe {
+        backend: backend.clone(),
+        open,
+        key,
+        live: Some(live),
+        missed: VecDeque::new(),
+        recent: Recent::new(),
+        generation: backend.generation(),
+        label,
+        retry: None,
+    };
+    Box::pin(futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.missed.pop_front() {
+                state.recent.insert((state.key)(&item));
+                return Some((Ok(item), state));
+            }
+            let Some(live) = state.live.as_mut() else {
+                state.reopen().await;
+                continue;
+            };
+            let next = tokio::select! {
+                item = live.next() => item,
+                // Live queries of the previous client don't receive anything anymore
+                _ = state.generation.changed() => {
+                    state.retry = None;
+                    None
+                }
+            };
+            match next {
+                Some(Ok(item)) => {
+                    state.retry = None;
+                    state.recent.insert((state.key)(&item));
+                    return Some((Ok(item), state));
+                }
+                Some(Err(e)) => {
+                    warn!("live-error {}", e);
+                    return Some((Err(e), state));
+                }
+                None => state.live = None,
+            }
+        }
+    }))
+}
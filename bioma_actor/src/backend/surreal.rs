@@ -0,0 +1,637 @@
+use super::reconnect::{resubscribing, OpenLive, ReconnectOptions};
+use super::{BackendFuture, CancelStream, EngineBackend, ExitStream, ReplyFrameStream};
+use crate::actor::{ActorRecord, FrameReply, DB_TABLE_ACTOR, DB_TABLE_CANCEL, DB_TABLE_MESSAGE, DB_TABLE_REPLY};
+use crate::directory::{ActorQuery, ActorStatus};
+use crate::lease::LeaseClaim;
+use crate::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::{Duration, Instant};
+use surrealdb::{engine::any::Any, opt::auth::Root, Action, Notification, RecordId, Surreal};
+use tokio::sync::watch;
+use tokio::time::{sleep, timeout};
+use tracing::{debug, info, warn};
+
+/// Backend storing actors, messages and replies in SurrealDB.
+///
+/// Messages are received with live queries, so actors in different processes connected to the
+/// same database can talk to each other.
+///
+/// A backend created with `connect` checks its connection and reconnects when it is lost, see
+/// `ReconnectOptions`. Message and reply streams survive reconnections.
+#[derive(Clone, Debug)]
+pub struct SurrealBackend {
+    db: Arc<RwLock<Surreal<Any>>>,
+    namespace: Cow<'static, str>,
+    database: Cow<'static, str>,
+    reconnect: ReconnectOptions,
+    /// Incremented on every reconnection
+    generation: Arc<watch::Sender<u64>>,
+    /// Times the live query of each mailbox was opened again, by receiver
+    restarts: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+/// What is needed to connect again
+#[derive(Clone)]
+struct Login {
+    endpoint: Cow<'static, str>,
+    username: Cow<'static, str>,
+    password: Cow<'static, str>,
+}
+
+impl std::fmt::Debug for Login {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Login").field("endpoint", &self.endpoint).field("username", &self.username).finish()
+    }
+}
+
+/// Reconnects a backend whose connection was lost, without keeping the backend alive
+struct HealthCheck {
+    db: Weak<RwLock<Surreal<Any>>>,
+    namespace: Cow<'static, str>,
+    database: Cow<'static, str>,
+    login: Login,
+    reconnect: ReconnectOptions,
+    generation: Arc<watch::Sender<u64>>,
+}
+
+impl HealthCheck {
+    async fn run(self) {
+        let interval = self.reconnect.health_check_interval;
+        loop {
+            sleep(interval).await;
+            let Some(db) = self.db.upgrade() else {
+                return;
+            };
+            let client = db.read().unwrap().clone();
+            drop(db);
+            if let Ok(Ok(())) = timeout(interval, client.health()).await {
+                continue;
+            }
+            warn!("Database connection lost, reconnecting");
+            if !self.reconnect().await {
+                return;
+            }
+        }
+    }
+
+    /// Returns `false` if the backend was dropped before reconnecting.
+    async fn reconnect(&self) -> bool {
+        let mut delay = self.reconnect.initial_delay;
+        loop {
+            let open = SurrealBackend::open(&self.login, &self.namespace, &self.database);
+            match timeout(self.reconnect.max_delay, open).await {
+                Ok(Ok(client)) => {
+                    let Some(db) = self.db.upgrade() else {
+                        return false;
+                    };
+                    *db.write().unwrap() = client;
+                    self.generation.send_modify(|generation| *generation += 1);
+                    info!("Reconnected to {}", self.login.endpoint);
+                    return true;
+                }
+                Ok(Err(e)) => warn!("Failed to reconnect: {}. Retrying in {:?}...", e, delay),
+                Err(_) => warn!("Failed to reconnect: timed out. Retrying in {:?}...", delay),
+            }
+            sleep(delay).await;
+            if self.db.strong_count() == 0 {
+                return false;
+            }
+            delay = self.reconnect.next_delay(delay);
+        }
+    }
+}
+
+impl SurrealBackend {
+    /// Uses a connected client, selecting the namespace and database and defining the tables.
+    ///
+    /// The backend can't reconnect a client it did not connect itself.
+    pub async fn new(
+        db: Surreal<Any>,
+        namespace: Cow<'static, str>,
+        database: Cow<'static, str>,
+    ) -> Result<Self, SystemActorError> {
+        db.use_ns(namespace.clone()).use_db(database.clone()).await?;
+        let backend = Self {
+            db: Arc::new(RwLock::new(db)),
+            namespace,
+            database,
+            reconnect: ReconnectOptions::default(),
+            generation: Arc::new(watch::channel(0).0),
+            restarts: Arc::default(),
+        };
+        backend.define().await?;
+        Ok(backend)
+    }
+
+    /// Connects to the database of the engine options, retrying as configured in
+    /// `EngineOptions::reconnect`, and keeps the connection alive.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SystemActorError::ConnectTimeout` if the database is not reachable within
+    /// `ReconnectOptions::max_startup_wait`.
+    pub async fn connect(options: &EngineOptions) -> Result<Self, SystemActorError> {
+        let login = Login {
+            endpoint: options.endpoint.clone(),
+            username: options.username.clone(),
+            password: options.password.clone(),
+        };
+        let reconnect = options.reconnect.clone();
+
+        let started = Instant::now();
+        let mut delay = reconnect.initial_delay;
+        let db = loop {
+            let attempt = Self::open(&login, &options.namespace, &options.database);
+            let result = match reconnect.max_startup_wait {
+                Some(max_wait) => match timeout(max_wait.saturating_sub(started.elapsed()), attempt).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        return Err(SystemActorError::ConnectTimeout(max_wait, "connection attempt timed out".into()))
+                    }
+                },
+                None => attempt.await,
+            };
+            match result {
+                Ok(db) => break db,
+                Err(e) => {
+                    if let Some(max_wait) = reconnect.max_startup_wait {
+                        if started.elapsed() + delay > max_wait {
+                            return Err(SystemActorError::ConnectTimeout(max_wait, e.to_string().into()));
+                        }
+                    }
+                    warn!("Failed to connect: {}. Retrying in {:?}...", e, delay);
+                    sleep(delay).await;
+                    delay = reconnect.next_delay(delay);
+                }
+            }
+        };
+
+        let backend = Self {
+            db: Arc::new(RwLock::new(db)),
+            namespace: options.namespace.clone(),
+            database: options.database.clone(),
+            reconnect,
+            generation: Arc::new(watch::channel(0).0),
+            restarts: Arc::default(),
+        };
+        backend.define().await?;
+        backend.spawn_health_check(login);
+        Ok(backend)
+    }
+
+    async fn open(login: &Login, namespace: &str, database: &str) -> Result<Surreal<Any>, SystemActorError> {
+        let db: Surreal<Any> = Surreal::init();
+        db.connect(login.endpoint.to_string()).await?;
+        db.signin(Root { username: &login.username, password: &login.password }).await?;
+        db.use_ns(namespace).use_db(database).await?;
+        Ok(db)
+    }
+
+    /// The current client, replaced on reconnection.
+    pub(crate) fn client(&self) -> Surreal<Any> {
+        self.db.read().unwrap().clone()
+    }
+
+    pub(crate) fn reconnect_options(&self) -> &ReconnectOptions {
+        &self.reconnect
+    }
+
+    /// Notified on every reconnection.
+    pub(crate) fn generation(&self) -> watch::Receiver<u64> {
+        self.generation.subscribe()
+    }
+
+    /// Counts a live query opened again for the mailbox of `rx`.
+    pub(crate) fn count_restart(&self, rx: &str) {
+        *self.restarts.lock().unwrap().entry(rx.to_string()).or_default() += 1;
+    }
+
+    /// Checks the connection and reconnects when it is lost.
+    ///
+    /// The check holds the connection weakly and stops once every clone of the backend is dropped.
+    fn spawn_health_check(&self, login: Login) {
+        let health_check = HealthCheck {
+            db: Arc::downgrade(&self.db),
+            namespace: self.namespace.clone(),
+            database: self.database.clone(),
+            login,
+            reconnect: self.reconnect.clone(),
+            generation: self.generation.clone(),
+        };
+        tokio::spawn(health_check.run());
+    }
+
+    async fn define(&self) -> Result<(), SystemActorError> {
+        // load the surreal definition file
+        let def = include_str!("../../sql/def.surql").parse::<String>().unwrap();
+        let mut res = self.client().query(&def).await?;
+        let err = res.take_errors();
+        for (k, v) in &err {
+            debug!("{}: {}", k, v);
+        }
+        Ok(())
+    }
+}
+
+impl EngineBackend for SurrealBackend {
+    fn get_actor<'a>(&'a self, id: &'a RecordId) -> BackendFuture<'a, Option<ActorRecord>> {
+        Box::pin(async move {
+            let record: Option<ActorRecord> = self.client().select(id).await?;
+            Ok(record)
+        })
+    }
+
+    fn put_actor(&self, record: ActorRecord) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            // Set the fields one by one, the lease is managed separately
+            self.client()
+                .query("UPSERT $id SET name = $name, tag = $tag, state = $state, instance = $instance, exit = $exit")
+                .bind(("id", record.id))
+                .bind(("name", record.name))
+                .bind(("tag", record.tag))
+                .bind(("state", record.state))
+                .bind(("instance", record.instance))
+                .bind(("exit", record.exit))
+                .await?
+                .check()?;
+            Ok(())
+        })
+    }
+
+    fn delete_actor<'a>(&'a self, id: &'a RecordId) -> BackendFuture<'a, Option<ActorRecord>> {
+        Box::pin(async move {
+            let record: Option<ActorRecord> = self.client().delete(id).await?;
+            Ok(record)
+        })
+    }
+
+    fn record_exit<'a>(&'a self, id: &'a RecordId, instance: &'a str, exit: ExitReason) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.client()
+                .query("UPDATE $id SET exit = $exit WHERE instance = $instance")
+                .bind(("id", id.clone()))
+                .bind(("exit", exit))
+                .bind(("instance", instance.to_string()))
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn acquire_lease<'a>(
+        &'a self,
+        actor: &'a ActorId,
+        owner: &'a str,
+        instance: &'a str,
+        ttl: Duration,
+    ) -> BackendFuture<'a, LeaseClaim> {
+        Box::pin(async move {
+            let db = self.client();
+            let id = &actor.record_id();
+            loop {
+                let before: Option<ActorRecord> = db.select(id).await?;
+                let query = match &before {
+                    // Fails if another engine created the record in the meantime
+                    None => include_str!("../../sql/lease_create.surql"),
+                    Some(_) => include_str!("../../sql/lease_acquire.surql"),
+                };
+                let mut res = db
+                    .query(query)
+                    .bind(("id", id.clone()))
+                    .bind(("name", actor.name().to_string()))
+                    .bind(("tag", actor.tag().to_string()))
+                    .bind(("owner", owner.to_string()))
+                    .bind(("instance", instance.to_string()))
+                    .bind(("ttl", ttl.as_millis() as u64))
+                    .await?;
+                let acquired: Result<Vec<RecordId>, surrealdb::Error> = res.take(0);
+                if matches!(&acquired, Ok(ids) if !ids.is_empty()) {
+                    return Ok(LeaseClaim::Acquired(before));
+                }
+                let current: Option<ActorRecord> = db.select(id).await?;
+                match (current, acquired) {
+                    (Some(ActorRecord { lease: Some(lease), .. }), _) => return Ok(LeaseClaim::Held(lease)),
+                    // Another engine created the record in between
+                    (Some(_), Err(_)) if before.is_none() => continue,
+                    (_, Err(e)) => return Err(e.into()),
+                    // The lease was released or the record deleted in between
+                    (_, Ok(_)) => continue,
+                }
+            }
+        })
+    }
+
+    fn renew_lease<'a>(
+        &'a self,
+        id: &'a RecordId,
+        owner: &'a str,
+        instance: &'a str,
+        ttl: Duration,
+    ) -> BackendFuture<'a, bool> {
+        Box::pin(async move {
+            let mut res = self
+                .client()
+                .query(include_str!("../../sql/lease_renew.surql"))
+                .bind(("id", id.clone()))
+                .bind(("owner", owner.to_string()))
+                .bind(("instance", instance.to_string()))
+                .bind(("ttl", ttl.as_millis() as u64))
+                .await?;
+            let renewed: Vec<RecordId> = res.take(0)?;
+            Ok(!renewed.is_empty())
+        })
+    }
+
+    fn release_lease<'a>(&'a self, id: &'a RecordId, owner: &'a str, instance: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.client()
+                .query("UPDATE $id SET lease = NONE WHERE lease.owner = $owner AND instance = $instance")
+                .bind(("id", id.clone()))
+                .bind(("owner", owner.to_string()))
+                .bind(("instance", instance.to_string()))
+                .await?
+                .check()?;
+            Ok(())
+        })
+    }
+
+    fn find_actors<'a>(&'a self, query: &'a ActorQuery) -> BackendFuture<'a, Vec<ActorRecord>> {
+        Box::pin(async move {
+            let mut res = self
+                .client()
+                .query(include_str!("../../sql/actor_query.surql"))
+                .bind(("tag", query.tag.clone()))
+                .bind(("prefix", query.prefix.clone()))
+                .bind(("running", query.status.map(|status| status == ActorStatus::Running)))
+                .await?;
+            let records: Vec<ActorRecord> = res.take(0)?;
+            Ok(records)
+        })
+    }
+
+    fn enqueue(&self, frame: FrameMessage) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let id = frame.id().clone();
+            let record: Option<Record> = self.client().create(DB_TABLE_MESSAGE).content(frame).await?;
+            match record {
+                Some(record) if record.id != id => Err(SystemActorError::IdMismatch(id, record.id)),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn append_reply(&self, msg_id: RecordId, reply: FrameReply) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let reply_query = include_str!("../../sql/reply.surql");
+            self.client()
+                .query(reply_query)
+                .bind(("last", reply.is_final()))
+                .bind(("reply_id", reply.reply_id()))
+                .bind(("reply", reply))
+                .bind(("msg_id", msg_id))
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn unreplied<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, Vec<FrameMessage>> {
+        Box::pin(unreplied_messages(self.client(), rx.clone()))
+    }
+
+    fn unreplied_count<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, usize> {
+        Box::pin(async move {
+            let query = include_str!("../../sql/unreplied_count.surql");
+            let mut res = self.client().query(query).bind(("rx", rx.clone())).await?;
+            let count: Option<usize> = res.take((0, "count"))?;
+            Ok(count.unwrap_or_default())
+        })
+    }
+
+    fn subscribe_messages<'a>(&'a self, rx: &'a RecordId) -> BackendFuture<'a, MessageStream> {
+        Box::pin(async move {
+            let live = live_messages(self.client(), rx.clone()).await?;
+            let label = Some(rx.to_string());
+            let rx = rx.clone();
+            let open: OpenLive<FrameMessage> = Box::new(move |db| {
+                let rx = rx.clone();
+                Box::pin(async move {
+                    let live = live_messages(db.clone(), rx.clone()).await?;
+                    let missed = unreplied_messages(db, rx).await?;
+                    Ok((live, missed))
+                })
+            });
+            Ok(resubscribing(self, live, open, |frame: &FrameMessage| frame.id().clone(), label))
+        })
+    }
+
+    fn subscribe_replies<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, ReplyFrameStream> {
+        Box::pin(async move {
+            let live = live_replies(self.client(), msg_id.clone()).await?;
+            let msg_id = msg_id.clone();
+            let open: OpenLive<FrameReply> = Box::new(move |db| {
+                let msg_id = msg_id.clone();
+                Box::pin(async move {
+                    let live = live_replies(db.clone(), msg_id.clone()).await?;
+                    let missed = stored_replies(db, msg_id).await?;
+                    Ok((live, missed))
+                })
+            });
+            Ok(resubscribing(self, live, open, FrameReply::chunk, None))
+        })
+    }
+
+    fn message_exists<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, bool> {
+        Box::pin(async move {
+            let record: Option<Record> = self.client().select(msg_id).await?;
+            Ok(record.is_some())
+        })
+    }
+
+    fn cancel<'a>(&'a self, msg_id: &'a RecordId) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            let cancel_id = RecordId::from_table_key(DB_TABLE_CANCEL, msg_id.key().clone());
+            // Only the engine running the receiver applies the cancel, see `subscribe_cancels`
+            self.client()
+                .query("UPSERT $id SET owner = $msg_id.rx.lease.owner")
+                .bind(("id", cancel_id))
+                .bind(("msg_id", msg_id.clone()))
+                .await?
+                .check()?;
+            Ok(())
+        })
+    }
+
+    fn subscribe_cancels<'a>(&'a self, owner: &'a str) -> BackendFuture<'a, CancelStream> {
+        Box::pin(async move {
+            let live = live_cancels(self.client(), owner.to_string()).await?;
+            let stored = stored_cancels(self.client(), owner.to_string()).await?;
+            let owner = owner.to_string();
+            let open: OpenLive<RecordId> = Box::new(move |db| {
+                let owner = owner.clone();
+                Box::pin(async move {
+                    let live = live_cancels(db.clone(), owner.clone()).await?;
+                    let missed = stored_cancels(db, owner).await?;
+                    Ok((live, missed))
+                })
+            });
+            let stored = futures::stream::iter(stored.into_iter().map(Ok));
+            Ok(Box::pin(stored.chain(resubscribing(self, live, open, RecordId::clone, None))) as CancelStream)
+        })
+    }
+
+    fn subscribe_exit<'a>(&'a self, id: &'a RecordId) -> BackendFuture<'a, ExitStream> {
+        Box::pin(async move {
+            // Subscribe before checking the current record, so no termination is missed in between
+            let live = live_exits(self.client(), id.clone()).await?;
+            let stored = stored_exit(self.client(), id.clone(), ExitReason::NotFound).await?;
+            let id = id.clone();
+            let open: OpenLive<ExitReason> = Box::new(move |db| {
+                let id = id.clone();
+                Box::pin(async move {
+                    let live = live_exits(db.clone(), id.clone()).await?;
+                    // A record missing now was deleted while the connection was lost
+                    let missed = stored_exit(db, id, ExitReason::Killed).await?;
+                    Ok((live, missed))
+                })
+            });
+            let stored = futures::stream::iter(stored.into_iter().map(Ok));
+            Ok(Box::pin(stored.chain(resubscribing(self, live, open, |_: &ExitReason| (), None))) as ExitStream)
+        })
+    }
+
+    fn requeue_dead_letter<'a>(&'a self, dead_letter_id: &'a RecordId, frame: FrameMessage) -> BackendFuture<'a, bool> {
+        Box::pin(async move {
+            let query = include_str!("../../sql/dead_letter_requeue.surql");
+            let mut res = self
+                .client()
+                .query(query)
+                .bind(("dead_letter_id", dead_letter_id.clone()))
+                .bind(("frame", frame))
+                .await?;
+            let last = res.num_statements() - 1;
+            let requeued: Option<bool> = res.take(last)?;
+            Ok(requeued.unwrap_or_default())
+        })
+    }
+
+    fn health(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async move { Ok(self.client().health().await.is_ok()) })
+    }
+
+    fn live_restarts(&self) -> HashMap<String, u64> {
+        self.restarts.lock().unwrap().clone()
+    }
+
+    fn reset(&self) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let db = self.client();
+            db.query(format!("REMOVE DATABASE `{}`;", self.database)).await?;
+            db.use_ns(self.namespace.clone()).use_db(self.database.clone()).await?;
+            self.define().await
+        })
+    }
+
+    fn surreal(&self) -> Option<Surreal<Any>> {
+        Some(self.client())
+    }
+}
+
+async fn unreplied_messages(db: Surreal<Any>, rx: RecordId) -> Result<Vec<FrameMessage>, SystemActorError> {
+    let query = include_str!("../../sql/unreplied_messages.surql");
+    let mut res = db.query(query).bind(("rx", rx)).await?;
+    let messages: Vec<FrameMessage> = res.take(0)?;
+    Ok(messages)
+}
+
+async fn live_messages(db: Surreal<Any>, rx: RecordId) -> Result<MessageStream, SystemActorError> {
+    // Locally delivered messages are stored after delivery, they are not received again
+    let query = format!("LIVE SELECT * FROM {} WHERE rx = {} AND !local", DB_TABLE_MESSAGE, rx);
+    debug!("[{}] msg-live {}", rx, &query);
+    let mut res = db.query(&query).await?;
+    let live_query = res.stream::<Notification<FrameMessage>>(0)?;
+    let stream = live_query
+        // Filter out non-create actions, keeping records that failed to decode
+        .filter(|item| futures::future::ready(!matches!(item, Ok(n) if n.action != Action::Create)))
+        // Map the notification to a frame
+        .map(|item| -> Result<FrameMessage, SystemActorError> {
+            let item = item?;
+            Ok(item.data)
+        });
+    Ok(Box::pin(stream))
+}
+
+async fn live_replies(db: Surreal<Any>, msg_id: RecordId) -> Result<ReplyFrameStream, SystemActorError> {
+    let query = format!("LIVE SELECT id.{{id, chunk}}, * FROM {} WHERE id.id = '{}'", DB_TABLE_REPLY, msg_id.key());
+    debug!("[{}] reply-live {}", msg_id, query);
+    let mut res = db.query(&query).await?;
+    let notification_stream = res.stream::<Notification<FrameReply>>(0)?;
+    let stream = notification_stream
+        // Only process Create actions, keeping records that failed to decode
+        .filter(|n| futures::future::ready(!matches!(n, Ok(n) if n.action != Action::Create)))
+        .map(|n| -> Result<FrameReply, SystemActorError> {
+            let n = n?;
+            Ok(n.data)
+        });
+    Ok(Box::pin(stream))
+}
+
+/// The replies stored for a message, chunks in order and the final reply last.
+async fn stored_replies(db: Surreal<Any>, msg_id: RecordId) -> Result<Vec<FrameReply>, SystemActorError> {
+    let query = format!("SELECT id.{{id, chunk}}, * FROM {} WHERE id.id = '{}'", DB_TABLE_REPLY, msg_id.key());
+    let mut res = db.query(&query).await?;
+    let mut replies: Vec<FrameReply> = res.take(0)?;
+    replies.sort_by_key(|reply| reply.chunk().unwrap_or(u64::MAX));
+    Ok(replies)
+}
+
+/// Cancels of messages to receivers leased by `owner`, or not leased, from now on.
+async fn live_cancels(db: Surreal<Any>, owner: String) -> Result<CancelStream, SystemActorError> {
+    // Live queries don't match bound parameters, the owner is inlined as a quoted string
+    let owner = serde_json::to_string(&owner)?;
+    let query = format!("LIVE SELECT id FROM {} WHERE owner = {} OR owner = NONE", DB_TABLE_CANCEL, owner);
+    let mut res = db.query(&query).await?;
+    let notification_stream = res.stream::<Notification<Record>>(0)?;
+    let stream = notification_stream
+        .filter(|n| futures::future::ready(!matches!(n, Ok(n) if n.action != Action::Create)))
+        .map(|n| -> Result<RecordId, SystemActorError> {
+            let n = n?;
+            Ok(RecordId::from_table_key(DB_TABLE_MESSAGE, n.data.id.key().clone()))
+        });
+    Ok(Box::pin(stream))
+}
+
+async fn live_exits(db: Surreal<Any>, id: RecordId) -> Result<ExitStream, SystemActorError> {
+    // Live queries don't match bound parameters, the record id is inlined
+    let query = format!("LIVE SELECT * FROM {} WHERE id = {}", DB_TABLE_ACTOR, id);
+    let mut res = db.query(&query).await?;
+    let notification_stream = res.stream::<Notification<ActorRecord>>(0)?;
+    let stream = notification_stream.filter_map(|n| {
+        futures::future::ready(match n {
+            Ok(n) => match n.action {
+                Action::Delete => Some(Ok(ExitReason::Killed)),
+                Action::Update => n.data.exit().cloned().map(Ok),
+                _ => None,
+            },
+            Err(e) => Some(Err(e.into())),
+        })
+    });
+    Ok(Box::pin(stream))
+}
+
+/// How an actor terminated according to its record, `missing` if there is no record.
+async fn stored_exit(db: Surreal<Any>, id: RecordId, missing: ExitReason) -> Result<Vec<ExitReason>, SystemActorError> {
+    let record: Option<ActorRecord> = db.select(id).await?;
+    Ok(match record {
+        Some(record) => record.exit().cloned().into_iter().collect(),
+        None => vec![missing],
+    })
+}
+
+/// The ids of the cancelled messages that were not replied yet, to receivers leased by `owner` or not leased.
+async fn stored_cancels(db: Surreal<Any>, owner: String) -> Result<Vec<RecordId>, SystemActorError> {
+    let query = format!("SELECT id FROM {} WHERE owner = $owner OR owner = NONE", DB_TABLE_CANCEL);
+    let mut res = db.query(&query).bind(("owner", owner)).await?;
+    let cancels: Vec<Record> = res.take(0)?;
+    Ok(cancels.into_iter().map(|cancel| RecordId::from_table_key(DB_TABLE_MESSAGE, cancel.id.key().clone())).collect())
+}
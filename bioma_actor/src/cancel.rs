@@ -0,0 +1,109 @@
+use crate::backend::Recent;
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use surrealdb::RecordId;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error};
+
+/// Cancellation tokens of the messages processed in an engine.
+///
+/// A sender cancels a message when it drops the reply stream before the final reply, or when
+/// waiting for a reply times out. The cancellation reaches the token of the receiver directly if
+/// it runs in the same engine, otherwise through a marker stored by the backend.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Cancellations {
+    inner: Arc<Mutex<CancelState>>,
+}
+
+#[derive(Debug)]
+struct CancelState {
+    /// Tokens of the messages being processed, by message id
+    running: HashMap<String, CancellationToken>,
+    /// Messages cancelled before their processing started
+    cancelled: Recent<String>,
+}
+
+impl Default for CancelState {
+    fn default() -> Self {
+        Self { running: HashMap::new(), cancelled: Recent::new() }
+    }
+}
+
+impl Cancellations {
+    /// The token of a message whose processing starts, cancelled already if the sender cancelled it.
+    pub(crate) fn register(&self, msg_id: &RecordId) -> CancellationToken {
+        let key = msg_id.to_string();
+        let token = CancellationToken::new();
+        let mut state = self.inner.lock().unwrap();
+        if state.cancelled.contains(&key) {
+            token.cancel();
+        }
+        state.running.insert(key, token.clone());
+        token
+    }
+
+    /// Forgets the token of a message once processed.
+    pub(crate) fn unregister(&self, msg_id: &RecordId) {
+        self.inner.lock().unwrap().running.remove(&msg_id.to_string());
+    }
+
+    /// Cancels a message, returning `true` if it is being processed in this engine.
+    pub(crate) fn cancel(&self, msg_id: &RecordId) -> bool {
+        let key = msg_id.to_string();
+        let mut state = self.inner.lock().unwrap();
+        match state.running.get(&key) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => {
+                state.cancelled.insert(key);
+                false
+            }
+        }
+    }
+}
+
+impl Engine {
+    /// Cancels a message sent from this engine.
+    pub(crate) async fn cancel_message(&self, msg_id: &RecordId) {
+        debug!("msg-cancel {}", msg_id);
+        if self.cancellations().cancel(msg_id) {
+            return;
+        }
+        // The receiver runs in another engine, or did not start processing the message yet
+        if let Err(e) = self.backend().cancel(msg_id).await {
+            error!("msg-cancel-error {} {}", msg_id, e);
+        }
+    }
+}
+
+/// Spawns the background task applying the cancellations stored by the backend.
+pub(crate) fn spawn_cancel_listener(engine: &Engine) {
+    let engine = engine.clone();
+    tokio::spawn(async move {
+        let mut cancels = match engine.backend().subscribe_cancels(engine.node_id()).await {
+            Ok(cancels) => cancels,
+            Err(e) => {
+                error!("msg-cancel-subscribe {}", e);
+                return;
+            }
+        };
+        loop {
+            let next = tokio::select! {
+                msg_id = cancels.next() => msg_id,
+                _ = engine.shutdown_token().cancelled() => None,
+            };
+            let Some(msg_id) = next else {
+                break;
+            };
+            match msg_id {
+                Ok(msg_id) => {
+                    engine.cancellations().cancel(&msg_id);
+                }
+                Err(e) => error!("msg-cancel-recv {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,208 @@
+use crate::actor::SystemActorError;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Where the configuration of a bioma binary is read from.
+///
+/// Sources are layered on top of the defaults, each one overriding the previous ones:
+///
+/// 1. A TOML or JSON file, chosen by its extension.
+/// 2. Environment variables starting with `{env_prefix}_`, e.g. `BIOMA_ENDPOINT`. Nested keys
+///    are separated by a double underscore, e.g. `BIOMA_RETENTION__MESSAGE_TTL=7d`.
+/// 3. `key=value` overrides, usually from the command line. Nested keys are separated by a
+///    dot, e.g. `retention.message_ttl=7d`.
+///
+/// Values are taken as strings when the default is a string, and as JSON otherwise, so
+/// `lease.ttl=30s`, `claim_check.threshold=65536` and `object_store={"type":"memory"}` all work.
+///
+/// Top-level keys of the file and overrides must exist in the defaults, so a misspelled or
+/// renamed key is an error rather than silently ignored. Renamed keys can be kept working with
+/// `aliases`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let sources = ConfigSources::builder().file("bioma.toml").overrides(vec!["namespace=test".into()]).build();
+/// let options = EngineOptions::load(&sources)?;
+/// ```
+#[derive(Clone, Debug, bon::Builder)]
+pub struct ConfigSources {
+    /// The config file, or the one in `{env_prefix}_CONFIG` if not given.
+    #[builder(into)]
+    pub file: Option<PathBuf>,
+    /// Prefix of the environment variables.
+    #[builder(into, default = default_env_prefix())]
+    pub env_prefix: Cow<'static, str>,
+    /// `key=value` overrides, applied last.
+    #[builder(default)]
+    pub overrides: Vec<String>,
+    /// Former names of top-level keys, as `(old, new)`, accepted in the file with a warning.
+    #[builder(default)]
+    pub aliases: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+}
+
+fn default_env_prefix() -> Cow<'static, str> {
+    "BIOMA".into()
+}
+
+impl Default for ConfigSources {
+    fn default() -> Self {
+        ConfigSources::builder().build()
+    }
+}
+
+impl ConfigSources {
+    /// Loads a configuration from the sources, on top of `defaults`.
+    ///
+    /// Environment variables for keys missing from `defaults` are ignored, while file keys and
+    /// overrides of unknown keys are an error.
+    pub fn load<T: Serialize + DeserializeOwned>(&self, defaults: &T) -> Result<T, SystemActorError> {
+        let mut config = serde_json::to_value(defaults)?;
+
+        let file =
+            self.file.clone().or_else(|| std::env::var_os(format!("{}_CONFIG", self.env_prefix)).map(Into::into));
+        if let Some(file) = file {
+            let mut layer = read_file(&file)?;
+            if let Value::Object(object) = &mut layer {
+                for (old, new) in &self.aliases {
+                    if let Some(value) = object.remove(old.as_ref()) {
+                        warn!("Deprecated config key `{}` in {}, use `{}`", old, file.display(), new);
+                        object.entry(new.to_string()).or_insert(value);
+                    }
+                }
+                if let Some(key) = object.keys().find(|key| !has_key(&config, key)) {
+                    return Err(invalid(format!("{}: unknown key `{}`", file.display(), key)));
+                }
+            }
+            merge(&mut config, layer);
+            info!("Loaded config: {}", file.display());
+        }
+
+        let prefix = format!("{}_", self.env_prefix);
+        let mut vars: Vec<(String, String)> = std::env::vars()
+            .filter_map(|(name, value)| Some((name.strip_prefix(&prefix)?.to_string(), value)))
+            .collect();
+        vars.sort();
+        for (name, value) in vars {
+            let keys: Vec<String> = name.split("__").map(|key| key.to_lowercase()).collect();
+            if !has_key(&config, &keys[0]) {
+                debug!("Ignored config variable: {}{}", prefix, name);
+                continue;
+            }
+            set(&mut config, &keys, &value)?;
+        }
+
+        for assignment in &self.overrides {
+            let Some((key, value)) = assignment.split_once('=') else {
+                return Err(invalid(format!("expected `key=value`, got `{}`", assignment)));
+            };
+            let keys: Vec<String> = key.trim().split('.').map(String::from).collect();
+            if !has_key(&config, &keys[0]) {
+                return Err(invalid(format!("unknown key `{}`", key.trim())));
+            }
+            set(&mut config, &keys, value)?;
+        }
+
+        serde_json::from_value(config).map_err(|e| invalid(e.to_string()))
+    }
+}
+
+fn invalid(message: String) -> SystemActorError {
+    SystemActorError::InvalidConfig(message.into())
+}
+
+/// Reads a TOML or JSON file into a JSON value.
+fn read_file(path: &Path) -> Result<Value, SystemActorError> {
+    let content = std::fs::read_to_string(path)?;
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    match extension {
+        "toml" => toml::from_str(&content).map_err(|e| invalid(format!("{}: {}", path.display(), e))),
+        "json" => serde_json::from_str(&content).map_err(|e| invalid(format!("{}: {}", path.display(), e))),
+        _ => Err(invalid(format!("{}: expected a .toml or .json file", path.display()))),
+    }
+}
+
+fn has_key(config: &Value, key: &str) -> bool {
+    config.as_object().is_some_and(|object| object.contains_key(key))
+}
+
+/// Merges the tables of `layer` into those of `base`, replacing any other value.
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Sets the value at a path of keys, creating the missing tables.
+fn set(config: &mut Value, keys: &[String], raw: &str) -> Result<(), SystemActorError> {
+    let mut current = config;
+    for (i, key) in keys.iter().enumerate() {
+        if current.is_null() {
+            *current = Value::Object(Map::new());
+        }
+        let Value::Object(object) = current else {
+            return Err(invalid(format!("`{}` is not a table", keys[..i].join("."))));
+        };
+        current = object.entry(key.clone()).or_insert(Value::Null);
+    }
+    *current = match &*current {
+        Value::String(_) => Value::String(raw.to_string()),
+        _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+    };
+    Ok(())
+}
+
+/// Checks a database endpoint, such as `memory`, `ws://localhost:8000` or `surrealkv://bioma.db`.
+pub(crate) fn validate_endpoint(endpoint: &str) -> Result<(), SystemActorError> {
+    if endpoint == "memory" {
+        return Ok(());
+    }
+    let Some((scheme, address)) = endpoint.split_once("://") else {
+        return Err(invalid(format!("endpoint `{}` has no scheme, e.g. ws://localhost:8000", endpoint)));
+    };
+    match scheme {
+        "ws" | "wss" | "http" | "https" => match url::Url::parse(endpoint) {
+            Ok(url) if url.host().is_some() => Ok(()),
+            _ => Err(invalid(format!("endpoint `{}` has no valid host", endpoint))),
+        },
+        "mem" | "rocksdb" | "surrealkv" | "file" | "tikv" | "fdb" | "indxdb" => {
+            if scheme != "mem" && address.is_empty() {
+                return Err(invalid(format!("endpoint `{}` has no path", endpoint)));
+            }
+            Ok(())
+        }
+        _ => Err(invalid(format!("endpoint `{}` has an unknown scheme `{}`", endpoint, scheme))),
+    }
+}
+
+/// Checks an URL given in the options, such as the endpoint of an exporter.
+pub(crate) fn validate_url(name: &str, url: &str) -> Result<(), SystemActorError> {
+    url::Url::parse(url).map_err(|e| invalid(format!("`{}` is not a valid URL `{}`: {}", name, url, e)))?;
+    Ok(())
+}
+
+/// Checks that a directory exists, or can be created under its nearest existing ancestor.
+pub(crate) fn validate_dir(name: &str, dir: &Path) -> Result<(), SystemActorError> {
+    if dir.as_os_str().is_empty() {
+        return Err(invalid(format!("`{}` is empty", name)));
+    }
+    match dir.ancestors().find(|ancestor| ancestor.exists()) {
+        Some(existing) if !existing.is_dir() => {
+            Err(invalid(format!("`{}` is not a directory: {}", name, existing.display())))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,223 @@
+use crate::local::Deferred;
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::time::Duration;
+use surrealdb::RecordId;
+use tracing::{debug, error, warn};
+
+const DB_TABLE_DEAD_LETTER: &str = "dead_letter";
+
+/// Rules deciding which messages are moved to the dead-letter queue.
+///
+/// # Example
+///
+/// ```rust
+/// // Dead-letter messages unreplied after 5 minutes and messages whose handler failed
+/// let dead_letter = DeadLetterOptions::builder()
+///     .unreplied_after(std::time::Duration::from_secs(300))
+///     .on_handler_error(true)
+///     .build();
+/// let options = EngineOptions::builder().dead_letter(dead_letter).build();
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct DeadLetterOptions {
+    /// Dead-letter messages that have not received any reply after this duration.
+    #[serde(default, with = "humantime_serde")]
+    pub unreplied_after: Option<Duration>,
+    /// Dead-letter messages whose handler returned an error.
+    #[builder(default)]
+    #[serde(default)]
+    pub on_handler_error: bool,
+    /// Dead-letter messages the receiving actor does not handle, see `ActorContext::serve`.
+    #[builder(default)]
+    #[serde(default)]
+    pub on_unhandled: bool,
+    /// How often to scan for unreplied messages.
+    #[builder(default = default_scan_interval())]
+    #[serde(default = "default_scan_interval", with = "humantime_serde")]
+    pub scan_interval: Duration,
+}
+
+fn default_scan_interval() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl Default for DeadLetterOptions {
+    fn default() -> Self {
+        DeadLetterOptions::builder().build()
+    }
+}
+
+/// Why a message was moved to the dead-letter queue.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeadLetterReason {
+    /// No reply was received within `DeadLetterOptions::unreplied_after`.
+    Unreplied,
+    /// The message handler returned an error.
+    HandlerError(String),
+    /// The receiving actor has no handler for this message name.
+    Unhandled,
+}
+
+/// A message that could not be delivered or processed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Dead letter id, sharing the key of the original message
+    pub id: RecordId,
+    /// The original message
+    pub message: RecordId,
+    /// Message name (usually message type name)
+    pub name: Cow<'static, str>,
+    /// Sender
+    pub tx: RecordId,
+    /// Receiver
+    pub rx: RecordId,
+    /// Message content
+    #[serde(default)]
+    pub msg: Value,
+    /// Schema version of the message content
+    #[serde(default)]
+    pub version: Option<u32>,
+    /// Why the message was dead-lettered
+    pub reason: DeadLetterReason,
+}
+
+impl Engine {
+    /// Moves a message to the dead-letter queue.
+    ///
+    /// The original message stays in the `message` table but is no longer delivered
+    /// to its receiver as an unreplied message. Dead-lettering a message twice has no effect.
+    ///
+    /// Dead letters are kept in SurrealDB, this and the other dead-letter queries return
+    /// `SystemActorError::Unsupported` on other backends.
+    pub async fn dead_letter(&self, frame: &FrameMessage, reason: DeadLetterReason) -> Result<(), SystemActorError> {
+        // Locally delivered messages may not be stored yet, mark them after they are
+        if frame.local && self.options().local_delivery.stores_messages() {
+            self.local().defer(Deferred::DeadLetter { frame: frame.clone(), reason });
+            return Ok(());
+        }
+
+        let db = self.surreal_for("dead letters")?;
+
+        let dead_letter_id = RecordId::from_table_key(DB_TABLE_DEAD_LETTER, frame.id().key().clone());
+        let dead_letter = DeadLetter {
+            id: dead_letter_id.clone(),
+            message: frame.id().clone(),
+            name: frame.name.clone(),
+            tx: frame.tx.clone(),
+            rx: frame.rx.clone(),
+            msg: frame.msg.clone(),
+            version: frame.version,
+            reason,
+        };
+        warn!("[{}] dead-letter {} {} {:?}", frame.rx, frame.name, frame.id(), dead_letter.reason);
+
+        let query = include_str!("../sql/dead_letter.surql");
+        db.query(query)
+            .bind(("dead_letter_id", dead_letter_id))
+            .bind(("dead_letter", dead_letter))
+            .bind(("msg_id", frame.id().clone()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    /// Lists dead letters, oldest first, optionally only those addressed to `rx`.
+    pub async fn dead_letters(&self, rx: Option<&ActorId>) -> Result<Vec<DeadLetter>, SystemActorError> {
+        let db = self.surreal_for("dead letters")?;
+        let mut res = match rx {
+            Some(rx) => {
+                db.query("SELECT * FROM dead_letter WHERE rx = $rx ORDER BY created")
+                    .bind(("rx", rx.record_id()))
+                    .await?
+            }
+            None => db.query("SELECT * FROM dead_letter ORDER BY created").await?,
+        };
+        let dead_letters: Vec<DeadLetter> = res.take(0)?;
+        Ok(dead_letters)
+    }
+
+    /// Gets a single dead letter by id.
+    pub async fn get_dead_letter(&self, id: &RecordId) -> Result<Option<DeadLetter>, SystemActorError> {
+        let dead_letter: Option<DeadLetter> = self.surreal_for("dead letters")?.select(id).await?;
+        Ok(dead_letter)
+    }
+
+    /// Whether a message with this key was dead-lettered, `false` on other backends.
+    pub(crate) async fn dead_letter_exists(&self, key: &str) -> Result<bool, SystemActorError> {
+        let Some(db) = self.backend().surreal() else {
+            return Ok(false);
+        };
+        let dead_letter: Option<DeadLetter> = db.select((DB_TABLE_DEAD_LETTER, key)).await?;
+        Ok(dead_letter.is_some())
+    }
+
+    /// Sends a dead-lettered message again as a new message and removes the dead letter.
+    ///
+    /// Returns the id of the new message, or `None` if the dead letter does not exist. Requeuing
+    /// the same dead letter concurrently sends it only once.
+    pub async fn requeue_dead_letter(&self, id: &RecordId) -> Result<Option<RecordId>, SystemActorError> {
+        let Some(dead_letter) = self.get_dead_letter(id).await? else {
+            return Ok(None);
+        };
+
+        let mut frame = FrameMessage::from_parts(dead_letter.name, dead_letter.tx, dead_letter.rx, dead_letter.msg);
+        frame.version = dead_letter.version;
+        let msg_id = frame.id().clone();
+        debug!("[{}] dead-letter-requeue {} {} -> {}", frame.rx, frame.name, id, msg_id);
+
+        if !self.backend().requeue_dead_letter(id, frame).await? {
+            return Ok(None);
+        }
+        Ok(Some(msg_id))
+    }
+
+    /// Deletes dead letters, optionally only those addressed to `rx`.
+    ///
+    /// Returns the number of dead letters removed.
+    pub async fn purge_dead_letters(&self, rx: Option<&ActorId>) -> Result<usize, SystemActorError> {
+        let db = self.surreal_for("dead letters")?;
+        let mut res = match rx {
+            Some(rx) => {
+                db.query("DELETE dead_letter WHERE rx = $rx RETURN BEFORE").bind(("rx", rx.record_id())).await?
+            }
+            None => db.query("DELETE dead_letter RETURN BEFORE").await?,
+        };
+        let purged: Vec<DeadLetter> = res.take(0)?;
+        Ok(purged.len())
+    }
+
+    /// Dead-letters messages that have not been replied within `unreplied_after`.
+    async fn dead_letter_unreplied(&self, after: Duration) -> Result<(), SystemActorError> {
+        let query = include_str!("../sql/dead_letter_scan.surql");
+        let mut res = self.surreal_for("dead letters")?.query(query).bind(("after", after.as_secs())).await?;
+        let messages: Vec<FrameMessage> = res.take(0)?;
+        for frame in messages {
+            self.dead_letter(&frame, DeadLetterReason::Unreplied).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Spawns the background task applying the unreplied rule, if configured.
+pub(crate) fn spawn_dead_letter_scan(engine: &Engine) {
+    let options = engine.options().dead_letter.clone();
+    let Some(after) = options.unreplied_after else {
+        return;
+    };
+    let engine = engine.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(options.scan_interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = engine.shutdown_token().cancelled() => break,
+            }
+            if let Err(e) = engine.dead_letter_unreplied(after).await {
+                error!("dead-letter-scan {}", e);
+            }
+        }
+    });
+}
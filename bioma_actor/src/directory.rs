@@ -0,0 +1,64 @@
+use crate::actor::ActorRecord;
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Whether an engine currently runs an actor, based on its lease.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActorStatus {
+    /// An engine holds a valid lease on the actor
+    Running,
+    /// The actor has no lease, or its lease expired
+    Stopped,
+}
+
+/// Filters for listing actors with `Engine::actors`.
+///
+/// Every filter that is set must match. An empty query lists all the actors.
+///
+/// # Example
+///
+/// ```rust
+/// // The running children of /rag/indexer
+/// let query = ActorQuery::builder().prefix("/rag/indexer/".into()).status(ActorStatus::Running).build();
+///
+/// // All the actors of a type
+/// let query = ActorQuery::of::<MyActor>();
+/// ```
+#[derive(bon::Builder, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ActorQuery {
+    /// Actor type tag, see `ActorId::tag`
+    #[serde(default)]
+    pub tag: Option<Cow<'static, str>>,
+    /// Start of the actor name, see `ActorId::name`
+    #[serde(default)]
+    pub prefix: Option<Cow<'static, str>>,
+    /// Whether the actor runs
+    #[serde(default)]
+    pub status: Option<ActorStatus>,
+}
+
+impl ActorQuery {
+    /// A query for the actors of type `T`.
+    pub fn of<T: Actor>() -> Self {
+        Self { tag: Some(std::any::type_name::<T>().into()), ..Default::default() }
+    }
+
+    /// Whether a record matches the query.
+    pub fn matches(&self, record: &ActorRecord) -> bool {
+        self.tag.as_ref().is_none_or(|tag| record.tag() == tag.as_ref())
+            && self.prefix.as_ref().is_none_or(|prefix| record.name().starts_with(prefix.as_ref()))
+            && self.status.is_none_or(|status| record.status() == status)
+    }
+}
+
+impl Engine {
+    /// Lists the actors matching a query, ordered by name.
+    ///
+    /// Records are shared by every engine using the same storage, so this includes actors
+    /// running in other processes, and stopped actors whose record was kept.
+    pub async fn actors(&self, query: &ActorQuery) -> Result<Vec<ActorRecord>, SystemActorError> {
+        self.backend().find_actors(query).await
+    }
+}
@@ -1,22 +1,28 @@
 use crate::actor::SystemActorError;
+use crate::backend::{
+    spawn_claim_check_gc, ClaimCheckBackend, ClaimCheckOptions, EngineBackend, ReconnectOptions, SurrealBackend,
+};
+use crate::cancel::{spawn_cancel_listener, Cancellations};
+use crate::config::{validate_dir, validate_endpoint, validate_url, ConfigSources};
+use crate::dead_letter::{spawn_dead_letter_scan, DeadLetterOptions};
 use crate::factory::ActorTagRegistry;
+use crate::lease::LeaseOptions;
+use crate::local::{spawn_deferred_writer, LocalDelivery, LocalTransport};
+use crate::metrics::{Metrics, MetricsOptions};
+use crate::retention::{spawn_compaction, RetentionOptions};
+use crate::schedule::{spawn_scheduler, ScheduleOptions};
+use crate::store::ObjectStoreOptions;
+use crate::trace::{TraceExporter, Tracer, TracingOptions};
 use crate::util::find_project_root;
 use derive_more::Display;
-use object_store::local::LocalFileSystem;
+use object_store::{local::LocalFileSystem, ObjectStore};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
-use surrealdb::{
-    engine::any::{Any, IntoEndpoint},
-    opt::auth::Root,
-    value::RecordId,
-    Surreal,
-};
-use tokio::sync::Mutex;
-use tokio::time::sleep;
-use tracing::{debug, info, warn};
+use surrealdb::{engine::any::Any, sql::Id, value::RecordId, Surreal};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
 
 #[macro_export]
 macro_rules! dbg_export_db {
@@ -25,7 +31,7 @@ macro_rules! dbg_export_db {
         std::fs::create_dir_all(&output_dir).unwrap();
         let file_name = format!("dbg_{}_{}", file!().replace("/", "_").replace(".", "_"), line!());
         let file_path = output_dir.join(format!("{}.surql", file_name));
-        $engine.db().lock().await.export(file_path.to_str().unwrap()).await.unwrap();
+        $engine.db().unwrap().export(file_path.to_str().unwrap()).await.unwrap();
     }};
 }
 
@@ -36,7 +42,10 @@ pub struct Record {
 }
 
 /// Configuration options for the Engine.
-#[derive(Clone, Debug, Serialize, Deserialize, bon::Builder)]
+///
+/// Built in code with `EngineOptions::builder()`, or loaded from a file, the environment and
+/// overrides with `EngineOptions::load`.
+#[derive(Clone, Serialize, Deserialize, bon::Builder)]
 pub struct EngineOptions {
     /// Database endpoint
     #[builder(default = "memory".into())]
@@ -59,9 +68,49 @@ pub struct EngineOptions {
     /// The local file system path for object store.
     #[builder(default = default_local_store_dir())]
     pub local_store_dir: PathBuf,
+    /// The object store shared by actors, local files by default.
+    #[builder(default)]
+    #[serde(default)]
+    pub object_store: ObjectStoreOptions,
     /// The HuggingFace cache directory.
     #[builder(default = default_hf_cache_dir())]
     pub hf_cache_dir: PathBuf,
+    /// Rules for moving messages to the dead-letter queue.
+    #[builder(default)]
+    #[serde(default)]
+    pub dead_letter: DeadLetterOptions,
+    /// Rules for removing old messages and replies.
+    #[builder(default)]
+    #[serde(default)]
+    pub retention: RetentionOptions,
+    /// Settings for delayed and periodic message delivery.
+    #[builder(default)]
+    #[serde(default)]
+    pub schedule: ScheduleOptions,
+    /// How messages between actors in this engine are delivered.
+    #[builder(default)]
+    #[serde(default)]
+    pub local_delivery: LocalDelivery,
+    /// Retry policy for connecting and reconnecting to the database.
+    #[builder(default)]
+    #[serde(default)]
+    pub reconnect: ReconnectOptions,
+    /// Ownership of actors by engines sharing the database.
+    #[builder(default)]
+    #[serde(default)]
+    pub lease: LeaseOptions,
+    /// Export of the spans of sent and processed messages.
+    #[builder(default)]
+    #[serde(default)]
+    pub tracing: TracingOptions,
+    /// Recording of actor runtime metrics.
+    #[builder(default)]
+    #[serde(default)]
+    pub metrics: MetricsOptions,
+    /// Storage of large payloads in the object store instead of the database.
+    #[builder(default)]
+    #[serde(default)]
+    pub claim_check: ClaimCheckOptions,
 }
 
 fn default_output_dir() -> PathBuf {
@@ -85,89 +134,229 @@ impl Default for EngineOptions {
     }
 }
 
+impl std::fmt::Debug for EngineOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EngineOptions")
+            .field("endpoint", &self.endpoint)
+            .field("namespace", &self.namespace)
+            .field("database", &self.database)
+            .field("username", &self.username)
+            .field("password", &REDACTED)
+            .field("output_dir", &self.output_dir)
+            .field("local_store_dir", &self.local_store_dir)
+            .field("object_store", &self.object_store)
+            .field("hf_cache_dir", &self.hf_cache_dir)
+            .field("dead_letter", &self.dead_letter)
+            .field("retention", &self.retention)
+            .field("schedule", &self.schedule)
+            .field("local_delivery", &self.local_delivery)
+            .field("reconnect", &self.reconnect)
+            .field("lease", &self.lease)
+            .field("tracing", &self.tracing)
+            .field("metrics", &self.metrics)
+            .field("claim_check", &self.claim_check)
+            .finish()
+    }
+}
+
+/// Shown instead of the password in logs
+const REDACTED: &str = "***";
+
 impl EngineOptions {
+    /// Loads the options from a file, `BIOMA_*` environment variables and overrides, on top of
+    /// the defaults, and validates them. See `ConfigSources` for the format of each source.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // BIOMA_ENDPOINT=ws://localhost:9123 BIOMA_PASSWORD=secret
+    /// let options = EngineOptions::load(&ConfigSources::builder().file("bioma.toml").build())?;
+    /// let engine = Engine::connect(options).await?;
+    /// ```
+    pub fn load(sources: &ConfigSources) -> Result<EngineOptions, SystemActorError> {
+        let options = sources.load(&EngineOptions::default())?;
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Checks the endpoints and directories, without connecting or creating anything.
+    pub fn validate(&self) -> Result<(), SystemActorError> {
+        validate_endpoint(&self.endpoint)?;
+        if self.namespace.is_empty() || self.database.is_empty() {
+            return Err(SystemActorError::InvalidConfig("namespace and database must not be empty".into()));
+        }
+        validate_dir("output_dir", &self.output_dir)?;
+        validate_dir("local_store_dir", &self.local_store_dir)?;
+        validate_dir("hf_cache_dir", &self.hf_cache_dir)?;
+        if let ObjectStoreOptions::S3 { bucket, endpoint, .. } = &self.object_store {
+            if bucket.is_empty() {
+                return Err(SystemActorError::InvalidConfig("object_store.bucket must not be empty".into()));
+            }
+            if let Some(endpoint) = endpoint {
+                validate_url("object_store.endpoint", endpoint)?;
+            }
+        }
+        if let TraceExporter::Otlp { endpoint } = &self.tracing.exporter {
+            validate_url("tracing.exporter.endpoint", endpoint)?;
+        }
+        Ok(())
+    }
+
     pub fn info(&self) {
-        info!("Engine: {}, ns: {}, db: {}, user: {}", self.endpoint, self.namespace, self.database, self.username);
+        info!(
+            "Engine: {}, ns: {}, db: {}, user: {}, password: {}",
+            self.endpoint, self.namespace, self.database, self.username, REDACTED
+        );
     }
 }
 
 /// The engine is the main entry point for the Actor framework.
 /// Responsible for creating and managing the database connection.
+///
+/// Actors, messages and replies are kept in an `EngineBackend`, SurrealDB by default. The
+/// SurrealDB client multiplexes concurrent queries over one connection, so the engine and its
+/// clones share it without locking.
 #[derive(Clone, Debug)]
 pub struct Engine {
-    db: Arc<Mutex<Surreal<Any>>>,
+    backend: Arc<dyn EngineBackend>,
+    node_id: Cow<'static, str>,
     options: EngineOptions,
     registry: ActorTagRegistry,
+    local: LocalTransport,
+    cancellations: Cancellations,
+    tracer: Tracer,
+    metrics: Metrics,
+    store: Arc<dyn ObjectStore>,
+    shutdown: CancellationToken,
 }
 
 impl Engine {
-    pub fn db(&self) -> Arc<Mutex<Surreal<Any>>> {
-        Arc::clone(&self.db)
+    /// A handle to the database client, cheap to clone and safe to use concurrently.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SystemActorError::Unsupported` if the backend is not SurrealDB, see
+    /// `EngineBackend::surreal`.
+    pub fn db(&self) -> Result<Surreal<Any>, SystemActorError> {
+        self.surreal_for("database client")
     }
 
-    pub async fn connect(options: EngineOptions) -> Result<Engine, SystemActorError> {
-        options.info();
+    /// The database client for a feature that needs SurrealDB, or `Unsupported` on other backends.
+    pub(crate) fn surreal_for(&self, feature: &'static str) -> Result<Surreal<Any>, SystemActorError> {
+        self.backend.surreal().ok_or(SystemActorError::Unsupported(feature.into()))
+    }
+
+    /// The storage of actors, messages and replies.
+    pub fn backend(&self) -> &Arc<dyn EngineBackend> {
+        &self.backend
+    }
+
+    /// Creates an engine on top of a custom storage backend.
+    ///
+    /// Dead letters, retention and schedules are only enabled when the backend is SurrealDB.
+    /// Watches, the journal, dead letters, retention, schedules and topics return
+    /// `SystemActorError::Unsupported` on other backends.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+    /// ```
+    pub fn with_backend(
+        backend: impl EngineBackend + 'static,
+        options: EngineOptions,
+    ) -> Result<Engine, SystemActorError> {
+        Self::start(Arc::new(backend), options)
+    }
 
-        let mut retry_delay = Duration::from_secs(1);
-        let max_delay = Duration::from_secs(10);
-
-        loop {
-            match Self::attempt_connect(options.endpoint.to_string(), &options).await {
-                Ok(engine) => return Ok(engine),
-                Err(e) => {
-                    warn!("Failed to connect: {}. Retrying in {:?}...", e, retry_delay);
-                    sleep(retry_delay).await;
-                    retry_delay = std::cmp::min(retry_delay * 2, max_delay);
-                }
+    fn start(backend: Arc<dyn EngineBackend>, options: EngineOptions) -> Result<Engine, SystemActorError> {
+        let node_id = options.lease.node_id.clone().unwrap_or_else(|| Id::ulid().to_string().into());
+        let tracer = Tracer::start(&options.tracing);
+        // Metrics only count messages, without loading offloaded payloads
+        let metrics = Metrics::new(&options.metrics, backend.clone());
+        let store = options.object_store.open(&options.local_store_dir)?;
+        let backend: Arc<dyn EngineBackend> = match options.claim_check.threshold {
+            Some(threshold) => {
+                Arc::new(ClaimCheckBackend::new(backend, store.clone(), threshold, &options.claim_check.prefix))
             }
+            None => backend,
+        };
+        let engine = Engine {
+            backend,
+            node_id,
+            options,
+            registry: ActorTagRegistry::default(),
+            local: LocalTransport::default(),
+            cancellations: Cancellations::default(),
+            tracer,
+            metrics,
+            store,
+            shutdown: CancellationToken::new(),
+        };
+        if engine.backend.surreal().is_some() {
+            spawn_dead_letter_scan(&engine);
+            spawn_compaction(&engine);
+            spawn_scheduler(&engine);
         }
+        spawn_deferred_writer(&engine);
+        spawn_cancel_listener(&engine);
+        spawn_claim_check_gc(&engine);
+        Ok(engine)
     }
 
-    async fn attempt_connect(address: impl IntoEndpoint, options: &EngineOptions) -> Result<Engine, SystemActorError> {
-        let db: Surreal<Any> = Surreal::init();
-        db.connect(address).await?;
-        db.signin(Root { username: &options.username, password: &options.password }).await?;
-        db.use_ns(options.namespace.clone()).use_db(options.database.clone()).await?;
-        Engine::define(&db).await?;
-        Ok(Engine { db: Arc::new(Mutex::new(db)), options: options.clone(), registry: ActorTagRegistry::default() })
+    /// Connects to the database, retrying and reconnecting as configured in `EngineOptions::reconnect`.
+    pub async fn connect(options: EngineOptions) -> Result<Engine, SystemActorError> {
+        options.info();
+        let backend = SurrealBackend::connect(&options).await?;
+        Self::start(Arc::new(backend), options)
     }
 
     pub async fn test() -> Result<Engine, SystemActorError> {
-        let options = EngineOptions::default();
+        Self::test_with(EngineOptions::default()).await
+    }
+
+    /// Creates an in-memory engine for tests, using the given options except for the endpoint.
+    pub async fn test_with(options: EngineOptions) -> Result<Engine, SystemActorError> {
         options.info();
         let db: Surreal<Any> = Surreal::init();
         db.connect("memory").await?;
-        db.use_ns(options.namespace.clone()).use_db(options.database.clone()).await?;
-        Engine::define(&db).await?;
-        Ok(Engine { db: Arc::new(Mutex::new(db)), options, registry: ActorTagRegistry::default() })
+        let backend = SurrealBackend::new(db, options.namespace.clone(), options.database.clone()).await?;
+        Self::start(Arc::new(backend), options)
+    }
+
+    /// Stops the background tasks of the engine, such as compaction and the scheduler.
+    ///
+    /// Shared by all clones of the engine. Actors keep running until stopped on their own.
+    pub fn shutdown(&self) {
+        self.shutdown.cancel();
+    }
+
+    /// Cancelled when the engine is shut down, for its background tasks to stop on.
+    pub(crate) fn shutdown_token(&self) -> &CancellationToken {
+        &self.shutdown
     }
 
     pub async fn reset(&self) -> Result<(), SystemActorError> {
-        let db = self.db.lock().await;
-        let db_name = self.options.database.clone();
-        let ns_name = self.options.namespace.clone();
-        db.query(format!("REMOVE DATABASE `{}`;", db_name)).await?;
-        db.use_ns(ns_name).use_db(db_name).await?;
-        Engine::define(&db).await?;
-        Ok(())
+        self.backend.reset().await
     }
 
     pub async fn health(&self) -> bool {
-        self.db.lock().await.health().await.is_ok()
+        self.backend.health().await.unwrap_or(false)
     }
 
-    async fn define(db: &Surreal<Any>) -> Result<(), SystemActorError> {
-        // load the surreal definition file
-        // let def = std::fs::read_to_string("assets/surreal/def.surql").unwrap();
-        let def = include_str!("../sql/def.surql").parse::<String>().unwrap();
-        let mut res = db.query(&def).await?;
-        let err = res.take_errors();
-        for (k, v) in &err {
-            debug!("{}: {}", k, v);
-        }
-        Ok(())
+    /// The object store configured in `EngineOptions::object_store`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let store = ctx.engine().store();
+    /// store.put(&Path::from("reports/summary.md"), summary.into()).await?;
+    /// ```
+    pub fn store(&self) -> Arc<dyn ObjectStore> {
+        self.store.clone()
     }
 
+    /// The local file system under `EngineOptions::local_store_dir`, whatever the configured store.
     pub fn local_store(&self) -> Result<LocalFileSystem, SystemActorError> {
         let store = LocalFileSystem::new_with_prefix(self.options.local_store_dir.clone())?;
         Ok(store)
@@ -185,6 +374,11 @@ impl Engine {
         &self.options.hf_cache_dir
     }
 
+    /// Id of this engine in actor leases.
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
     pub fn options(&self) -> &EngineOptions {
         &self.options
     }
@@ -196,6 +390,30 @@ impl Engine {
     pub fn registry(&self) -> &ActorTagRegistry {
         &self.registry
     }
+
+    pub(crate) fn local(&self) -> &LocalTransport {
+        &self.local
+    }
+
+    pub(crate) fn cancellations(&self) -> &Cancellations {
+        &self.cancellations
+    }
+
+    /// The runtime metrics of the actors of this engine.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // Serve the metrics to Prometheus
+    /// let body = engine.metrics().prometheus().await;
+    /// ```
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    pub(crate) fn tracer(&self) -> &Tracer {
+        &self.tracer
+    }
 }
 
 #[cfg(test)]
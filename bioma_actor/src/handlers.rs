@@ -0,0 +1,104 @@
+use crate::prelude::*;
+use tracing::{error, warn};
+
+/// Routes incoming frames to the actor's `Message` implementations.
+///
+/// Usually derived together with the list of handled message types, and driven by
+/// `ActorContext::serve` instead of a hand-written receive loop.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(ActorHandlers, Debug, Serialize, Deserialize)]
+/// #[handles(StoreEmbeddings, GenerateEmbeddings, TopK)]
+/// pub struct Embeddings {
+///     // ...
+/// }
+///
+/// impl Actor for Embeddings {
+///     type Error = EmbeddingsError;
+///
+///     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), EmbeddingsError> {
+///         self.init(ctx).await?;
+///         ctx.serve(self).await
+///     }
+/// }
+/// ```
+pub trait ActorHandlers: Actor {
+    /// Handles the frame with the matching `Message` implementation.
+    ///
+    /// Returns `Ok(false)` if the actor does not handle this message name. A handled message whose
+    /// content can't be decoded is rejected, see `ActorContext::reject`, and the error is returned.
+    fn dispatch(
+        &mut self,
+        ctx: &mut ActorContext<Self>,
+        frame: &FrameMessage,
+    ) -> impl Future<Output = Result<bool, Self::Error>>;
+}
+
+impl<T: ActorHandlers> ActorContext<T> {
+    /// Receives messages and dispatches them to the actor until the message stream ends.
+    ///
+    /// Handler errors are logged and the actor keeps serving; the sender receives the error with
+    /// the final reply, see `ActorContext::send_and_wait_reply`. Messages the actor does not
+    /// handle, or whose content it can't decode, are rejected the same way, so they are not
+    /// delivered again. Unhandled messages are also moved to the dead-letter queue if
+    /// `DeadLetterOptions::on_unhandled` is set. Stored messages that can't be decoded as a frame
+    /// are logged and skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message stream can't be set up.
+    pub async fn serve(&mut self, actor: &mut T) -> Result<(), T::Error> {
+        let mut stream = self.recv().await?;
+        while let Some(frame) = stream.next().await {
+            let Some(frame) = self.decoded(frame) else {
+                continue;
+            };
+            match actor.dispatch(self, &frame).await {
+                Ok(true) => {}
+                Ok(false) => self.unhandled(&frame).await,
+                Err(e) => error!("[{}] msg-error {} {} {}", self.id().record_id(), frame.name, frame.id(), e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Receives messages until the first one the actor handles, dispatches it and returns.
+    ///
+    /// For actors that stop after a single request, such as behavior nodes. Unlike `serve`, a
+    /// handler error ends serving and is returned, after the sender receives it with the final
+    /// reply. Messages the actor does not handle are treated as in `serve`, content of a handled
+    /// message that can't be decoded ends serving like a handler error.
+    ///
+    /// # Errors
+    ///
+    /// Returns the handler error, or an error if the message stream can't be set up.
+    pub async fn serve_once(&mut self, actor: &mut T) -> Result<(), T::Error> {
+        let mut stream = self.recv().await?;
+        while let Some(frame) = stream.next().await {
+            let Some(frame) = self.decoded(frame) else {
+                continue;
+            };
+            if actor.dispatch(self, &frame).await? {
+                break;
+            }
+            self.unhandled(&frame).await;
+        }
+        Ok(())
+    }
+
+    fn decoded(&self, frame: Result<FrameMessage, SystemActorError>) -> Option<FrameMessage> {
+        frame.inspect_err(|e| error!("[{}] msg-decode-error {}", self.id().record_id(), e)).ok()
+    }
+
+    async fn unhandled(&mut self, frame: &FrameMessage) {
+        warn!("[{}] msg-unhandled {} {}", self.id().record_id(), frame.name, frame.id());
+        if self.engine().options().dead_letter.on_unhandled {
+            if let Err(e) = self.engine().dead_letter(frame, DeadLetterReason::Unhandled).await {
+                error!("Error recording dead letter: {}", e);
+            }
+        }
+        self.reject(frame, format!("Unhandled message {}", frame.name)).await;
+    }
+}
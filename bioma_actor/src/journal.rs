@@ -0,0 +1,250 @@
+use crate::actor::StateEnvelope;
+use crate::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Debug;
+use surrealdb::sql::Datetime;
+use tracing::debug;
+
+/// An actor whose state is persisted as a journal of domain events.
+///
+/// Instead of overwriting the state with `Actor::save`, handlers record each change with
+/// `ActorContext::persist`. The events are appended to the `journal` table, and the state is
+/// rebuilt with `ActorContext::recover` from the latest snapshot plus the events after it.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(Debug, Serialize, Deserialize)]
+/// pub enum ChatEvent {
+///     Asked(ChatMessage),
+///     Answered(ChatMessage),
+/// }
+///
+/// impl EventSourced for Chat {
+///     type Event = ChatEvent;
+///     const SNAPSHOT_EVERY: u64 = 50;
+///
+///     fn apply(&mut self, event: &ChatEvent) {
+///         match event {
+///             ChatEvent::Asked(message) | ChatEvent::Answered(message) => self.history.push(message.clone()),
+///         }
+///     }
+/// }
+///
+/// impl Actor for Chat {
+///     type Error = ChatError;
+///
+///     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), ChatError> {
+///         ctx.recover(self).await?;
+///         ctx.serve(self).await
+///     }
+/// }
+/// ```
+pub trait EventSourced: Actor {
+    /// The domain events changing the actor state.
+    type Event: Serialize + DeserializeOwned + Debug + Send + Sync;
+
+    /// Take a snapshot of the state every `SNAPSHOT_EVERY` events, `0` to never snapshot automatically.
+    const SNAPSHOT_EVERY: u64 = 100;
+
+    /// Applies an event to the state.
+    ///
+    /// Called both when an event is persisted and when it is replayed, so it must not have
+    /// side effects besides changing the state.
+    fn apply(&mut self, event: &Self::Event);
+}
+
+/// An event recorded in an actor's journal.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JournalEntry<E> {
+    /// Position of the event in the journal, starting at 1
+    pub seq: u64,
+    /// The event
+    pub event: E,
+    /// When the event was recorded
+    pub created: Datetime,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotRecord {
+    seq: u64,
+    state: Value,
+}
+
+impl Engine {
+    /// Rebuilds the state of an event-sourced actor.
+    ///
+    /// Starts from the latest snapshot, or from `initial` if there is none, and applies the
+    /// journaled events after it.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the actor.
+    /// * `initial` - The state before the first event.
+    /// * `until` - Replay up to and including this sequence number, for point-in-time recovery.
+    ///   `None` replays the whole journal.
+    ///
+    /// # Returns
+    ///
+    /// The state and the sequence number of the last applied event.
+    pub async fn replay<T: EventSourced>(
+        &self,
+        id: &ActorId,
+        initial: T,
+        until: Option<u64>,
+    ) -> Result<(T, u64), T::Error> {
+        let mut actor = initial;
+        let seq = self.replay_into(id, &mut actor, until).await?;
+        Ok((actor, seq))
+    }
+
+    async fn replay_into<T: EventSourced>(
+        &self,
+        id: &ActorId,
+        actor: &mut T,
+        until: Option<u64>,
+    ) -> Result<u64, T::Error> {
+        let query = include_str!("../sql/journal_replay.surql");
+        let mut res = self
+            .surreal_for("journal")?
+            .query(query)
+            .bind(("actor", id.record_id()))
+            .bind(("until", until))
+            .await
+            .map_err(SystemActorError::from)?;
+        let snapshot: Option<SnapshotRecord> = res.take(1).map_err(SystemActorError::from)?;
+        let entries: Vec<JournalEntry<Value>> = res.take(2).map_err(SystemActorError::from)?;
+
+        let mut seq = 0;
+        if let Some(snapshot) = snapshot {
+            let envelope = StateEnvelope::from_stored(snapshot.state).upgrade::<T>(id)?;
+            *actor = serde_json::from_value(envelope.state).map_err(SystemActorError::from)?;
+            seq = snapshot.seq;
+        }
+        debug!("[{}] journal-replay {} events from {}", id.record_id(), entries.len(), seq);
+        for entry in entries {
+            let event: T::Event = serde_json::from_value(entry.event).map_err(SystemActorError::from)?;
+            actor.apply(&event);
+            seq = entry.seq;
+        }
+        Ok(seq)
+    }
+
+    /// The journaled events of an actor after sequence number `after`, oldest first.
+    pub async fn journal<E: DeserializeOwned>(
+        &self,
+        id: &ActorId,
+        after: u64,
+    ) -> Result<Vec<JournalEntry<E>>, SystemActorError> {
+        let mut res = self
+            .surreal_for("journal")?
+            .query("SELECT seq, event, created FROM journal WHERE actor = $actor AND seq > $after ORDER BY seq")
+            .bind(("actor", id.record_id()))
+            .bind(("after", after))
+            .await?;
+        let entries: Vec<JournalEntry<E>> = res.take(0)?;
+        Ok(entries)
+    }
+
+    /// Deletes the journal and snapshots of an actor.
+    pub(crate) async fn clear_journal(&self, id: &ActorId) -> Result<(), SystemActorError> {
+        // There is no journal without SurrealDB
+        let Some(db) = self.backend().surreal() else {
+            return Ok(());
+        };
+        db.query("DELETE journal WHERE actor = $actor; DELETE snapshot WHERE actor = $actor;")
+            .bind(("actor", id.record_id()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn last_journal_seq(&self, id: &ActorId) -> Result<u64, SystemActorError> {
+        let mut res = self
+            .surreal_for("journal")?
+            .query("math::max(SELECT VALUE seq FROM journal WHERE actor = $actor) OR 0")
+            .bind(("actor", id.record_id()))
+            .await?;
+        let seq: Option<u64> = res.take(0)?;
+        Ok(seq.unwrap_or_default())
+    }
+}
+
+impl<T: EventSourced> ActorContext<T> {
+    /// Restores the actor state from its snapshot and journal.
+    ///
+    /// Usually called at the beginning of `Actor::start`. Actors without a journal keep their
+    /// current state.
+    ///
+    /// # Returns
+    ///
+    /// The sequence number of the last applied event.
+    pub async fn recover(&mut self, actor: &mut T) -> Result<u64, T::Error> {
+        let seq = self.engine().replay_into(self.id(), actor, None).await?;
+        self.journal_seq = Some(seq);
+        Ok(seq)
+    }
+
+    /// Appends an event to the journal and applies it to the actor.
+    ///
+    /// The event is applied only once it is stored. A snapshot is taken every
+    /// `EventSourced::SNAPSHOT_EVERY` events.
+    ///
+    /// # Returns
+    ///
+    /// The sequence number of the event.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the event can't be stored, e.g. because another instance of the
+    /// actor appended an event with the same sequence number.
+    pub async fn persist(&mut self, actor: &mut T, event: T::Event) -> Result<u64, T::Error> {
+        let seq = match self.journal_seq {
+            Some(seq) => seq,
+            None => self.engine().last_journal_seq(self.id()).await?,
+        } + 1;
+        let value = serde_json::to_value(&event).map_err(SystemActorError::from)?;
+        self.engine()
+            .surreal_for("journal")?
+            .query("CREATE type::thing('journal', [$actor, $seq]) CONTENT { actor: $actor, seq: $seq, event: $event }")
+            .bind(("actor", self.id().record_id()))
+            .bind(("seq", seq))
+            .bind(("event", value))
+            .await
+            .map_err(SystemActorError::from)?
+            .check()
+            .map_err(SystemActorError::from)?;
+        self.journal_seq = Some(seq);
+        actor.apply(&event);
+
+        if T::SNAPSHOT_EVERY > 0 && seq % T::SNAPSHOT_EVERY == 0 {
+            self.snapshot(actor).await?;
+        }
+        Ok(seq)
+    }
+
+    /// Stores a snapshot of the actor state at the current journal position.
+    ///
+    /// Recovery starts from the latest snapshot instead of replaying the whole journal.
+    pub async fn snapshot(&mut self, actor: &T) -> Result<u64, T::Error> {
+        let seq = match self.journal_seq {
+            Some(seq) => seq,
+            None => self.engine().last_journal_seq(self.id()).await?,
+        };
+        debug!("[{}] journal-snapshot {}", self.id().record_id(), seq);
+        let state = StateEnvelope::of(actor)?.into_stored()?;
+        self.engine()
+            .surreal_for("journal")?
+            .query("UPSERT type::thing('snapshot', [$actor, $seq]) CONTENT { actor: $actor, seq: $seq, state: $state }")
+            .bind(("actor", self.id().record_id()))
+            .bind(("seq", seq))
+            .bind(("state", state))
+            .await
+            .map_err(SystemActorError::from)?
+            .check()
+            .map_err(SystemActorError::from)?;
+        Ok(seq)
+    }
+}
@@ -0,0 +1,193 @@
+use crate::actor::ActorRecord;
+use crate::prelude::*;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::time::Duration;
+use surrealdb::sql::Datetime;
+use tokio::sync::watch;
+use tokio::task::AbortHandle;
+use tokio::time::Instant;
+use tracing::{debug, error, warn};
+
+/// Rules for the ownership of actors by engines.
+///
+/// An actor runs in a single engine at a time: `Actor::spawn` takes a lease on the actor record,
+/// which the engine renews every `heartbeat_interval`. An engine spawning an actor whose lease is
+/// held by another engine waits until the lease is released or expires, which makes it a standby
+/// that takes over when the owner stops or crashes, see `SpawnOptions::lease_timeout` to bound the
+/// wait. If an engine can't renew a lease within `ttl`, the actor's message stream ends.
+///
+/// # Example
+///
+/// ```rust
+/// // Fail over after 10 seconds without heartbeat
+/// let lease = LeaseOptions::builder()
+///     .node_id("worker-1".into())
+///     .ttl(std::time::Duration::from_secs(10))
+///     .heartbeat_interval(std::time::Duration::from_secs(3))
+///     .build();
+/// let options = EngineOptions::builder().lease(lease).build();
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct LeaseOptions {
+    /// Id of this engine in the leases, a new ULID for every engine if not set.
+    #[serde(default)]
+    pub node_id: Option<Cow<'static, str>>,
+    /// How long a lease is valid without heartbeat.
+    #[builder(default = default_ttl())]
+    #[serde(default = "default_ttl", with = "humantime_serde")]
+    pub ttl: Duration,
+    /// How often leases are renewed, must be shorter than `ttl`.
+    #[builder(default = default_heartbeat_interval())]
+    #[serde(default = "default_heartbeat_interval", with = "humantime_serde")]
+    pub heartbeat_interval: Duration,
+}
+
+fn default_ttl() -> Duration {
+    Duration::from_secs(15)
+}
+
+fn default_heartbeat_interval() -> Duration {
+    Duration::from_secs(5)
+}
+
+impl Default for LeaseOptions {
+    fn default() -> Self {
+        LeaseOptions::builder().build()
+    }
+}
+
+/// Ownership of an actor record by an engine.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Lease {
+    /// Node id of the owning engine
+    pub owner: String,
+    /// When the lease expires unless renewed
+    pub expires: Datetime,
+    /// When the lease was last acquired or renewed
+    pub heartbeat: Datetime,
+}
+
+impl Lease {
+    /// A lease for `owner` starting now.
+    pub fn new(owner: &str, ttl: Duration) -> Self {
+        let now = Utc::now();
+        let expires = TimeDelta::from_std(ttl)
+            .ok()
+            .and_then(|ttl| now.checked_add_signed(ttl))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        Self { owner: owner.to_string(), expires: Datetime::from(expires), heartbeat: Datetime::from(now) }
+    }
+
+    /// Whether `owner` may take this lease.
+    pub fn is_free_for(&self, owner: &str) -> bool {
+        self.owner == owner || self.expires.0 < Utc::now()
+    }
+}
+
+/// Result of `EngineBackend::acquire_lease`.
+#[derive(Debug)]
+pub enum LeaseClaim {
+    /// The lease was acquired, with the actor record as it was before, if it existed.
+    Acquired(Option<ActorRecord>),
+    /// Another engine holds the lease.
+    Held(Lease),
+}
+
+/// A lease held by an actor context, renewed in the background.
+#[derive(Debug)]
+pub(crate) struct LeaseHandle {
+    held: watch::Receiver<bool>,
+    heartbeat: AbortHandle,
+}
+
+impl LeaseHandle {
+    /// Resolves once the lease is lost.
+    pub(crate) fn lost(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut held = self.held.clone();
+        async move {
+            while *held.borrow_and_update() {
+                if held.changed().await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for LeaseHandle {
+    fn drop(&mut self) {
+        self.heartbeat.abort();
+    }
+}
+
+impl Engine {
+    /// Waits until the lease of an actor is acquired for `instance`, for at most `timeout` if set.
+    ///
+    /// Returns the actor record as it was before.
+    pub(crate) async fn acquire_lease(
+        &self,
+        id: &ActorId,
+        instance: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Option<ActorRecord>, SystemActorError> {
+        let options = &self.options().lease;
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let claim = self.backend().acquire_lease(id, self.node_id(), instance, options.ttl).await?;
+            match claim {
+                LeaseClaim::Acquired(record) => {
+                    debug!("[{}] lease-acquired {}", id.record_id(), self.node_id());
+                    return Ok(record);
+                }
+                LeaseClaim::Held(lease) => {
+                    // Check again when the lease expires, or earlier in case it is released
+                    let expires_in = (lease.expires.0 - Utc::now()).to_std().unwrap_or_default();
+                    let mut wait = expires_in.clamp(Duration::from_millis(100), options.heartbeat_interval);
+                    if let (Some(deadline), Some(timeout)) = (deadline, timeout) {
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        if remaining.is_zero() {
+                            warn!("[{}] lease-timeout {} {:?}", id.record_id(), lease.owner, timeout);
+                            return Err(SystemActorError::LeaseTimeout(id.clone(), timeout));
+                        }
+                        wait = wait.min(remaining);
+                    }
+                    debug!("[{}] lease-wait {} {:?}", id.record_id(), lease.owner, wait);
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    /// Renews the lease of an actor until it is lost or the handle is dropped.
+    pub(crate) fn keep_lease(&self, id: &ActorId, instance: &str) -> LeaseHandle {
+        let (tx, held) = watch::channel(true);
+        let engine = self.clone();
+        let record_id = id.record_id();
+        let instance = instance.to_string();
+        let heartbeat = tokio::spawn(async move {
+            let options = engine.options().lease.clone();
+            let mut renewed = Instant::now();
+            loop {
+                tokio::time::sleep(options.heartbeat_interval).await;
+                match engine.backend().renew_lease(&record_id, engine.node_id(), &instance, options.ttl).await {
+                    Ok(true) => renewed = Instant::now(),
+                    Ok(false) => {
+                        warn!("[{}] lease-lost {}", record_id, engine.node_id());
+                        let _ = tx.send(false);
+                        return;
+                    }
+                    // Once the lease expired, a standby may have taken the actor over
+                    Err(e) if renewed.elapsed() >= options.ttl => {
+                        error!("[{}] lease-expired {} {}", record_id, engine.node_id(), e);
+                        let _ = tx.send(false);
+                        return;
+                    }
+                    Err(e) => error!("[{}] lease-renew-error {}", record_id, e),
+                }
+            }
+        });
+        LeaseHandle { held, heartbeat: heartbeat.abort_handle() }
+    }
+}
@@ -1,16 +1,71 @@
+// Allows the derive macros to refer to `::bioma_actor` from within this crate
+extern crate self as bioma_actor;
+
 mod actor;
+mod backend;
+mod cancel;
+mod config;
+mod dead_letter;
+mod directory;
 mod engine;
 mod factory;
+mod handlers;
+mod journal;
+mod lease;
+mod local;
+mod message_name;
+mod metrics;
+mod retention;
+mod router;
+mod schedule;
+mod store;
+mod supervisor;
+#[cfg(feature = "testkit")]
+pub mod testkit;
+mod trace;
 mod util;
+mod watch;
 
 pub use crate::actor::{
-    Actor, ActorContext, ActorError, ActorId, FrameMessage, Message, MessageType, SendOptions, SpawnExistsOptions,
-    SpawnOptions, SystemActorError,
+    Actor, ActorContext, ActorError, ActorId, ActorRecord, FrameMessage, FrameReply, Message, MessageStream,
+    MessageType, SendOptions, SpawnExistsOptions, SpawnOptions, StateEnvelope, SystemActorError,
+};
+pub use crate::backend::{
+    BackendFuture, CancelStream, ClaimCheckOptions, EngineBackend, ExitStream, MemoryBackend, ReconnectOptions,
+    ReplyFrameStream, SurrealBackend,
 };
+pub use crate::config::ConfigSources;
+pub use crate::dead_letter::{DeadLetter, DeadLetterOptions, DeadLetterReason};
+pub use crate::directory::{ActorQuery, ActorStatus};
 pub use crate::engine::{Engine, EngineOptions, Record};
 pub use crate::factory::{ActorFactory, ActorHandle, ActorTagRegistry};
+pub use crate::handlers::ActorHandlers;
+pub use crate::journal::{EventSourced, JournalEntry};
+pub use crate::lease::{Lease, LeaseClaim, LeaseOptions};
+pub use crate::local::LocalDelivery;
+pub use crate::message_name::{register_message, MessageName};
+pub use crate::metrics::{ActorMetrics, Histogram, MessageMetrics, Metrics, MetricsOptions};
+pub use crate::retention::RetentionOptions;
+pub use crate::router::{AddRoutee, ListRoutees, RemoveRoutee, Router, RoutingStrategy};
+pub use crate::schedule::{Schedule, ScheduleOptions};
+pub use crate::store::ObjectStoreOptions;
+pub use crate::supervisor::{
+    ChildRestart, ChildSpec, ExitReason, RestartStrategy, Supervisor, SupervisorHandle, SupervisorOptions,
+};
+pub use crate::trace::{TraceContext, TraceExporter, TracingOptions};
 pub use crate::util::Relay;
+pub use crate::watch::Terminated;
+pub use bioma_actor_derive::{ActorHandlers, MessageName};
 pub use futures::{Future, StreamExt};
+pub use tokio_util::sync::CancellationToken;
+
+// Used by the derive macros, not part of the public API
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::message_name::MessageNameRegistration;
+    pub use inventory;
+    pub use serde_json::Value;
+}
 
 /// The prelude module provides a convenient way to import all the public items from this crate.
 pub mod prelude {
@@ -0,0 +1,230 @@
+use crate::actor::FrameReply;
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use surrealdb::RecordId;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+/// How messages between actors running in the same `Engine` are delivered.
+///
+/// By default every message goes through the database: the sender creates a `message` record,
+/// the receiver gets it from a live query, and every reply chunk is a `reply` record. With local
+/// delivery, messages to an actor currently receiving in the same engine are passed through
+/// in-memory channels instead, and the reply stream is fed directly by the receiver. The
+/// `ActorContext` API and the reply stream semantics are the same.
+///
+/// Messages to actors in other processes, published and scheduled messages always go through
+/// the database.
+///
+/// # Example
+///
+/// ```rust
+/// let options = EngineOptions::builder().local_delivery(LocalDelivery::WriteBehind).build();
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalDelivery {
+    /// Always deliver through the database.
+    #[default]
+    Disabled,
+    /// Deliver in memory, then store messages and all reply chunks in the background.
+    ///
+    /// The database keeps the same records as with `Disabled`, so retention, dead letters and
+    /// redelivery of unreplied messages after a restart keep working.
+    WriteBehind,
+    /// Deliver in memory, and store messages and their final reply only, for auditing.
+    AuditOnly,
+    /// Deliver in memory without storing anything.
+    ///
+    /// Messages still queued when the receiver stops are lost.
+    MemoryOnly,
+}
+
+impl LocalDelivery {
+    /// Whether messages to co-located actors skip the database.
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, LocalDelivery::Disabled)
+    }
+
+    /// Whether locally delivered messages are stored.
+    pub(crate) fn stores_messages(&self) -> bool {
+        matches!(self, LocalDelivery::WriteBehind | LocalDelivery::AuditOnly)
+    }
+
+    /// Whether the replies to locally delivered messages are stored, `final_reply` for the last one.
+    pub(crate) fn stores_reply(&self, final_reply: bool) -> bool {
+        match self {
+            LocalDelivery::WriteBehind => true,
+            LocalDelivery::AuditOnly => final_reply,
+            _ => false,
+        }
+    }
+}
+
+/// A database write deferred by local delivery.
+#[derive(Debug)]
+pub(crate) enum Deferred {
+    Message(FrameMessage),
+    Reply { msg_id: RecordId, reply: FrameReply },
+    Delete(RecordId),
+    DeadLetter { frame: FrameMessage, reason: DeadLetterReason },
+}
+
+/// In-memory mailboxes and pending reply streams of the actors receiving in this engine.
+#[derive(Clone, Debug)]
+pub(crate) struct LocalTransport {
+    mailboxes: Arc<RwLock<HashMap<String, mpsc::UnboundedSender<FrameMessage>>>>,
+    replies: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<FrameReply>>>>,
+    deferred: mpsc::UnboundedSender<Deferred>,
+    deferred_rx: Arc<Mutex<Option<mpsc::UnboundedReceiver<Deferred>>>>,
+}
+
+impl Default for LocalTransport {
+    fn default() -> Self {
+        let (deferred, deferred_rx) = mpsc::unbounded_channel();
+        Self {
+            mailboxes: Default::default(),
+            replies: Default::default(),
+            deferred,
+            deferred_rx: Arc::new(Mutex::new(Some(deferred_rx))),
+        }
+    }
+}
+
+/// Removes an actor's local mailbox when its message stream is dropped.
+pub(crate) struct MailboxGuard {
+    transport: LocalTransport,
+    rx: RecordId,
+    tx: mpsc::UnboundedSender<FrameMessage>,
+}
+
+impl Drop for MailboxGuard {
+    fn drop(&mut self) {
+        let mut mailboxes = self.transport.mailboxes.write().unwrap();
+        // A newer stream of the same actor may have replaced this mailbox
+        if mailboxes.get(&self.rx.to_string()).is_some_and(|tx| tx.same_channel(&self.tx)) {
+            mailboxes.remove(&self.rx.to_string());
+        }
+    }
+}
+
+impl LocalTransport {
+    /// Registers the local mailbox of a receiving actor, replacing any previous one.
+    pub(crate) fn open_mailbox(&self, rx: &ActorId) -> (mpsc::UnboundedReceiver<FrameMessage>, MailboxGuard) {
+        let (tx, mailbox) = mpsc::unbounded_channel();
+        self.mailboxes.write().unwrap().insert(rx.record_id().to_string(), tx.clone());
+        (mailbox, MailboxGuard { transport: self.clone(), rx: rx.record_id(), tx })
+    }
+
+    /// Whether `rx` is currently receiving in this engine.
+    pub(crate) fn is_receiving(&self, rx: &RecordId) -> bool {
+        self.mailboxes.read().unwrap().contains_key(&rx.to_string())
+    }
+
+    /// Delivers a frame to a co-located actor.
+    ///
+    /// Returns the reply stream if `replies` is set, or gives the frame back if the receiver
+    /// is not receiving in this engine.
+    pub(crate) fn deliver(
+        &self,
+        mut frame: FrameMessage,
+        replies: bool,
+    ) -> Result<Option<mpsc::UnboundedReceiver<FrameReply>>, FrameMessage> {
+        let Some(mailbox) = self.mailboxes.read().unwrap().get(&frame.rx.to_string()).cloned() else {
+            return Err(frame);
+        };
+
+        let key = frame.id().to_string();
+        let reply_rx = replies.then(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.replies.lock().unwrap().insert(key.clone(), tx);
+            rx
+        });
+
+        frame.local = true;
+        match mailbox.send(frame) {
+            Ok(()) => Ok(reply_rx),
+            Err(mpsc::error::SendError(mut frame)) => {
+                self.replies.lock().unwrap().remove(&key);
+                frame.local = false;
+                Err(frame)
+            }
+        }
+    }
+
+    /// Takes the reply channel of a locally delivered message, if the sender is waiting for replies.
+    pub(crate) fn take_replies(&self, msg_id: &RecordId) -> Option<mpsc::UnboundedSender<FrameReply>> {
+        self.replies.lock().unwrap().remove(&msg_id.to_string())
+    }
+
+    /// Queues a database write behind local delivery.
+    pub(crate) fn defer(&self, write: Deferred) {
+        if self.deferred.send(write).is_err() {
+            error!("local-defer writer stopped");
+        }
+    }
+}
+
+impl Engine {
+    /// Delivers a frame to a co-located actor if local delivery is enabled.
+    ///
+    /// Gives the frame back if it has to go through the database.
+    pub(crate) fn deliver_local(
+        &self,
+        frame: FrameMessage,
+        replies: bool,
+    ) -> Result<Option<mpsc::UnboundedReceiver<FrameReply>>, FrameMessage> {
+        let delivery = self.options().local_delivery;
+        if !delivery.is_enabled() {
+            return Err(frame);
+        }
+        let stored = delivery.stores_messages().then(|| frame.clone());
+        let reply_rx = self.local().deliver(frame, replies)?;
+        if let Some(mut frame) = stored {
+            frame.local = true;
+            self.local().defer(Deferred::Message(frame));
+        }
+        Ok(reply_rx)
+    }
+}
+
+/// Spawns the background task storing messages and replies delivered locally, in order.
+pub(crate) fn spawn_deferred_writer(engine: &Engine) {
+    if !engine.options().local_delivery.is_enabled() {
+        return;
+    }
+    let Some(mut deferred) = engine.local().deferred_rx.lock().unwrap().take() else {
+        return;
+    };
+    let engine = engine.clone();
+    tokio::spawn(async move {
+        loop {
+            let next = tokio::select! {
+                biased;
+                write = deferred.recv() => write,
+                // Writes already queued are flushed before stopping
+                _ = engine.shutdown_token().cancelled() => deferred.try_recv().ok(),
+            };
+            let Some(write) = next else {
+                break;
+            };
+            let result = match write {
+                Deferred::Message(frame) => {
+                    debug!("[{}] local-store {} {}", frame.rx, frame.name, frame.id());
+                    engine.backend().enqueue(frame).await
+                }
+                Deferred::Reply { msg_id, reply } => engine.backend().append_reply(msg_id, reply).await,
+                Deferred::Delete(msg_id) => engine.delete_messages(vec![msg_id]).await.map(|_| ()),
+                Deferred::DeadLetter { mut frame, reason } => {
+                    frame.local = false;
+                    engine.dead_letter(&frame, reason).await
+                }
+            };
+            if let Err(e) = result {
+                error!("local-store {}", e);
+            }
+        }
+    });
+}
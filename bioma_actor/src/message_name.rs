@@ -0,0 +1,139 @@
+use crate::prelude::*;
+use lazy_static::lazy_static;
+use serde_json::Value;
+use std::any::TypeId;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tracing::warn;
+
+/// A stable, versioned name for a message type.
+///
+/// By default frames are named after `std::any::type_name`, which changes when a type is moved
+/// to another module or crate. Message types deriving `MessageName` are sent under `NAME`
+/// instead, and frames named after any of the `ALIASES` (or the Rust type name) are still decoded.
+///
+/// The derive registers the type for the whole process. Generic types, and types implementing
+/// the trait by hand, must be registered with `register_message` before they are sent or received.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(MessageName, Clone, Debug, Serialize, Deserialize)]
+/// #[message(name = "bioma.llm.IndexGlobs", alias = "bioma_llm::indexer::IndexGlobs", version = 2, upgrade = upgrade_globs)]
+/// pub struct IndexGlobs {
+///     pub globs: Vec<String>,
+/// }
+/// ```
+pub trait MessageName: MessageType {
+    /// Stable name used in message frames.
+    const NAME: &'static str;
+    /// Previous names still accepted when decoding frames.
+    const ALIASES: &'static [&'static str] = &[];
+    /// Schema version of the message content.
+    const VERSION: u32 = 0;
+
+    /// Upgrades message content written with an older schema `version` to the current one.
+    fn upgrade(version: u32, msg: Value) -> Result<Value, SystemActorError> {
+        let _ = version;
+        Ok(msg)
+    }
+}
+
+#[derive(Clone)]
+struct MessageNameInfo {
+    type_name: fn() -> &'static str,
+    name: &'static str,
+    aliases: &'static [&'static str],
+    version: u32,
+    upgrade: fn(u32, Value) -> Result<Value, SystemActorError>,
+}
+
+/// A message type registered at compile time by `#[derive(MessageName)]`.
+#[doc(hidden)]
+pub struct MessageNameRegistration {
+    type_id: fn() -> TypeId,
+    info: MessageNameInfo,
+}
+
+impl MessageNameRegistration {
+    pub const fn of<MT: MessageName>() -> Self {
+        let info = MessageNameInfo {
+            type_name: std::any::type_name::<MT>,
+            name: MT::NAME,
+            aliases: MT::ALIASES,
+            version: MT::VERSION,
+            upgrade: MT::upgrade,
+        };
+        Self { type_id: TypeId::of::<MT>, info }
+    }
+}
+
+inventory::collect!(MessageNameRegistration);
+
+lazy_static! {
+    static ref MESSAGE_NAMES: RwLock<HashMap<TypeId, MessageNameInfo>> = {
+        let mut names = HashMap::new();
+        for registration in inventory::iter::<MessageNameRegistration> {
+            insert(&mut names, (registration.type_id)(), registration.info.clone());
+        }
+        RwLock::new(names)
+    };
+}
+
+fn insert(names: &mut HashMap<TypeId, MessageNameInfo>, type_id: TypeId, info: MessageNameInfo) {
+    if names.iter().any(|(id, other)| *id != type_id && other.name == info.name) {
+        warn!("Message name {} of {} is already used by another type", info.name, (info.type_name)());
+    }
+    names.insert(type_id, info);
+}
+
+/// Registers the stable name of a message type for this process.
+///
+/// Only needed for generic types and hand-written `MessageName` implementations, the derive
+/// registers the type already. Register message types before actors send or receive them,
+/// usually at startup. Registering a type twice has no effect.
+pub fn register_message<MT: MessageName>() {
+    let info = MessageNameRegistration::of::<MT>().info;
+    insert(&mut MESSAGE_NAMES.write().unwrap(), TypeId::of::<MT>(), info);
+}
+
+fn message_info<MT: MessageType>() -> Option<MessageNameInfo> {
+    MESSAGE_NAMES.read().unwrap().get(&TypeId::of::<MT>()).cloned()
+}
+
+/// The name and schema version frames of this message type are sent with.
+pub(crate) fn message_name<MT: MessageType>() -> (Cow<'static, str>, Option<u32>) {
+    match message_info::<MT>() {
+        Some(info) => (info.name.into(), Some(info.version)),
+        None => (std::any::type_name::<MT>().into(), None),
+    }
+}
+
+/// Decodes message content named `name` as `MT`, upgrading it from an older schema `version`.
+///
+/// Content without a version, e.g. written before the type had one, is taken as version 0.
+///
+/// Returns `None` if the name does not belong to `MT`, and an error if the content can't be decoded.
+pub(crate) fn decode_message<MT: MessageType>(
+    name: &str,
+    version: Option<u32>,
+    msg: &Value,
+) -> Option<Result<MT, SystemActorError>> {
+    let type_name = std::any::type_name::<MT>();
+    let Some(info) = message_info::<MT>() else {
+        return (name == type_name).then(|| Ok(serde_json::from_value(msg.clone())?));
+    };
+
+    if name != info.name && name != type_name && !info.aliases.contains(&name) {
+        return None;
+    }
+    let version = version.unwrap_or_default();
+    if version >= info.version {
+        return Some(serde_json::from_value(msg.clone()).map_err(SystemActorError::from));
+    }
+    let decoded = (info.upgrade)(version, msg.clone())
+        .inspect_err(|e| warn!("Failed to upgrade {} from version {} to {}: {}", info.name, version, info.version, e))
+        .and_then(|msg| Ok(serde_json::from_value(msg)?));
+    Some(decoded)
+}
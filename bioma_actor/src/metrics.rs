@@ -0,0 +1,286 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+/// Settings for recording actor metrics.
+///
+/// # Example
+///
+/// ```rust
+/// let metrics = MetricsOptions::builder().latency_buckets(vec![0.01, 0.1, 1.0, 10.0]).build();
+/// let options = EngineOptions::builder().metrics(metrics).build();
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct MetricsOptions {
+    /// Whether metrics are recorded.
+    #[builder(default = true)]
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Upper bounds of the handler latency histogram buckets, in seconds.
+    #[builder(default = default_latency_buckets())]
+    #[serde(default = "default_latency_buckets")]
+    pub latency_buckets: Vec<f64>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_latency_buckets() -> Vec<f64> {
+    vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]
+}
+
+impl Default for MetricsOptions {
+    fn default() -> Self {
+        MetricsOptions::builder().build()
+    }
+}
+
+/// Distribution of durations, in seconds.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    /// Upper bounds of the buckets
+    pub bounds: Vec<f64>,
+    /// Number of observations in each bucket, the last one counts those above every bound
+    pub counts: Vec<u64>,
+    /// Number of observations
+    pub count: u64,
+    /// Sum of the observations
+    pub sum: f64,
+}
+
+impl Histogram {
+    fn new(bounds: &[f64]) -> Self {
+        Self { bounds: bounds.to_vec(), counts: vec![0; bounds.len() + 1], count: 0, sum: 0.0 }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = self.bounds.iter().position(|bound| value <= *bound).unwrap_or(self.bounds.len());
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// Metrics of the messages with one name received by one actor.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MessageMetrics {
+    /// Message name, see `FrameMessage::name`
+    pub name: String,
+    /// Messages received
+    pub received: u64,
+    /// Reply chunks sent, not counting the final reply
+    pub reply_chunks: u64,
+    /// Messages whose handler failed
+    pub errors: u64,
+    /// Time spent in the handler
+    pub latency: Histogram,
+}
+
+/// Metrics of one actor.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActorMetrics {
+    /// The actor
+    pub actor: ActorId,
+    /// Messages waiting for a reply, as stored by the backend
+    pub backlog: u64,
+    /// Times the live query of the mailbox was opened again, after a connection loss
+    pub stream_restarts: u64,
+    /// Metrics by message name
+    pub messages: Vec<MessageMetrics>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    /// By actor record id, then by message name
+    actors: BTreeMap<String, (ActorMetrics, BTreeMap<String, MessageMetrics>)>,
+}
+
+/// Runtime metrics of the actors of an engine, see `Engine::metrics`.
+///
+/// Records, per actor and per message name, the messages received, the handler latency, the
+/// reply chunks and the handler errors, and per actor the mailbox backlog and the restarts of
+/// its live query.
+#[derive(Clone)]
+pub struct Metrics {
+    options: MetricsOptions,
+    backend: Arc<dyn EngineBackend>,
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl std::fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metrics").field("options", &self.options).finish()
+    }
+}
+
+impl Metrics {
+    pub(crate) fn new(options: &MetricsOptions, backend: Arc<dyn EngineBackend>) -> Self {
+        Self { options: options.clone(), backend, registry: Arc::default() }
+    }
+
+    /// Updates the metrics of an actor and one of its message names.
+    fn update(
+        &self,
+        actor: &ActorId,
+        name: Option<&str>,
+        update: impl FnOnce(&mut ActorMetrics, Option<&mut MessageMetrics>),
+    ) {
+        if !self.options.enabled {
+            return;
+        }
+        let mut registry = self.registry.lock().unwrap();
+        let (actor_metrics, messages) = registry.actors.entry(actor.record_id().to_string()).or_insert_with(|| {
+            let metrics = ActorMetrics { actor: actor.clone(), backlog: 0, stream_restarts: 0, messages: vec![] };
+            (metrics, BTreeMap::new())
+        });
+        let message = name.map(|name| {
+            messages.entry(name.to_string()).or_insert_with(|| MessageMetrics {
+                name: name.to_string(),
+                received: 0,
+                reply_chunks: 0,
+                errors: 0,
+                latency: Histogram::new(&self.options.latency_buckets),
+            })
+        });
+        update(actor_metrics, message);
+    }
+
+    pub(crate) fn received(&self, actor: &ActorId, name: &str) {
+        self.update(actor, Some(name), |_, message| {
+            if let Some(message) = message {
+                message.received += 1;
+            }
+        });
+    }
+
+    pub(crate) fn handled(&self, actor: &ActorId, name: &str, elapsed: Duration, failed: bool) {
+        self.update(actor, Some(name), |_, message| {
+            if let Some(message) = message {
+                message.latency.observe(elapsed.as_secs_f64());
+                if failed {
+                    message.errors += 1;
+                }
+            }
+        });
+    }
+
+    pub(crate) fn reply_chunk(&self, actor: &ActorId, name: &str) {
+        self.update(actor, Some(name), |_, message| {
+            if let Some(message) = message {
+                message.reply_chunks += 1;
+            }
+        });
+    }
+
+    /// Starts recording the metrics of an actor receiving messages.
+    pub(crate) fn register(&self, actor: &ActorId) {
+        self.update(actor, None, |_, _| {});
+    }
+
+    /// The metrics of every actor that received messages in this engine, ordered by actor.
+    ///
+    /// The backlog is loaded from the backend, so messages delivered in memory only (see
+    /// `LocalDelivery::MemoryOnly`) are not counted.
+    pub async fn actors(&self) -> Vec<ActorMetrics> {
+        let restarts = self.backend.live_restarts();
+        let actors: Vec<(String, ActorMetrics)> = {
+            let registry = self.registry.lock().unwrap();
+            registry
+                .actors
+                .iter()
+                .map(|(key, (actor, messages))| {
+                    let metrics = ActorMetrics { messages: messages.values().cloned().collect(), ..actor.clone() };
+                    (key.clone(), metrics)
+                })
+                .collect()
+        };
+        let mut snapshot = Vec::with_capacity(actors.len());
+        for (key, mut metrics) in actors {
+            metrics.stream_restarts = restarts.get(&key).copied().unwrap_or_default();
+            match self.backend.unreplied_count(&metrics.actor.record_id()).await {
+                Ok(count) => metrics.backlog = count as u64,
+                Err(e) => warn!("metrics-backlog-error {} {}", key, e),
+            }
+            snapshot.push(metrics);
+        }
+        snapshot
+    }
+
+    /// The metrics of an actor, if it received messages in this engine.
+    pub async fn actor(&self, actor: &ActorId) -> Option<ActorMetrics> {
+        self.actors().await.into_iter().find(|metrics| &metrics.actor == actor)
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub async fn prometheus(&self) -> String {
+        let actors = self.actors().await;
+        let mut out = String::new();
+
+        let message_counters: [(&str, &str, fn(&MessageMetrics) -> u64); 3] = [
+            ("bioma_actor_messages_received_total", "Messages received by an actor.", |m| m.received),
+            ("bioma_actor_reply_chunks_total", "Reply chunks sent by an actor.", |m| m.reply_chunks),
+            ("bioma_actor_handler_errors_total", "Messages whose handler failed.", |m| m.errors),
+        ];
+        for (metric, help, value) in message_counters {
+            header(&mut out, metric, help, "counter");
+            for actor in &actors {
+                for message in &actor.messages {
+                    let _ =
+                        writeln!(out, "{}{{{}}} {}", metric, labels(&actor.actor, Some(&message.name)), value(message));
+                }
+            }
+        }
+
+        let metric = "bioma_actor_handler_duration_seconds";
+        header(&mut out, metric, "Time spent in message handlers.", "histogram");
+        for actor in &actors {
+            for message in &actor.messages {
+                let labels = labels(&actor.actor, Some(&message.name));
+                let latency = &message.latency;
+                let mut cumulative = 0;
+                for (bound, count) in latency.bounds.iter().zip(&latency.counts) {
+                    cumulative += count;
+                    let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", metric, labels, bound, cumulative);
+                }
+                let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", metric, labels, latency.count);
+                let _ = writeln!(out, "{}_sum{{{}}} {}", metric, labels, latency.sum);
+                let _ = writeln!(out, "{}_count{{{}}} {}", metric, labels, latency.count);
+            }
+        }
+
+        let metric = "bioma_actor_mailbox_backlog";
+        header(&mut out, metric, "Messages waiting for a reply.", "gauge");
+        for actor in &actors {
+            let _ = writeln!(out, "{}{{{}}} {}", metric, labels(&actor.actor, None), actor.backlog);
+        }
+
+        let metric = "bioma_actor_stream_restarts_total";
+        header(&mut out, metric, "Times the live query of a mailbox was opened again.", "counter");
+        for actor in &actors {
+            let _ = writeln!(out, "{}{{{}}} {}", metric, labels(&actor.actor, None), actor.stream_restarts);
+        }
+        out
+    }
+}
+
+fn header(out: &mut String, metric: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", metric, help);
+    let _ = writeln!(out, "# TYPE {} {}", metric, kind);
+}
+
+fn labels(actor: &ActorId, message: Option<&str>) -> String {
+    let mut labels = format!("actor=\"{}\",tag=\"{}\"", escape(actor.name()), escape(actor.tag()));
+    if let Some(message) = message {
+        let _ = write!(labels, ",message=\"{}\"", escape(message));
+    }
+    labels
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
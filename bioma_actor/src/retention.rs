@@ -0,0 +1,142 @@
+use crate::engine::Record;
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use surrealdb::RecordId;
+use tracing::{debug, error};
+
+/// Rules deciding when messages and replies are removed from the database.
+///
+/// Compaction only removes messages that were replied or dead-lettered: unreplied messages are
+/// still pending delivery and are never removed. A message counts as replied once its final reply
+/// is stored, so the chunks of a stream still being written are kept too.
+///
+/// # Example
+///
+/// ```rust
+/// // Keep an hour of messages, at most 1000 per receiver
+/// let retention = RetentionOptions::builder()
+///     .message_ttl(std::time::Duration::from_secs(3600))
+///     .keep_last(1000)
+///     .build();
+/// let options = EngineOptions::builder().retention(retention).build();
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct RetentionOptions {
+    /// Remove replied messages, and their replies, older than this duration.
+    #[serde(default, with = "humantime_serde")]
+    pub message_ttl: Option<Duration>,
+    /// Remove reply chunks older than this duration. The final reply is kept with its message.
+    #[serde(default, with = "humantime_serde")]
+    pub reply_ttl: Option<Duration>,
+    /// Keep at most this many replied messages per receiver.
+    #[serde(default)]
+    pub keep_last: Option<usize>,
+    /// Remove a message and its replies as soon as the sender has consumed the final reply.
+    #[builder(default)]
+    #[serde(default)]
+    pub delete_on_final_reply: bool,
+    /// How often to run compaction.
+    #[builder(default = default_compaction_interval())]
+    #[serde(default = "default_compaction_interval", with = "humantime_serde")]
+    pub compaction_interval: Duration,
+}
+
+fn default_compaction_interval() -> Duration {
+    Duration::from_secs(60)
+}
+
+impl Default for RetentionOptions {
+    fn default() -> Self {
+        RetentionOptions::builder().build()
+    }
+}
+
+impl RetentionOptions {
+    /// Whether any rule requires the periodic compaction task.
+    fn needs_compaction(&self) -> bool {
+        self.message_ttl.is_some() || self.reply_ttl.is_some() || self.keep_last.is_some()
+    }
+}
+
+#[derive(Deserialize)]
+struct RxRecord {
+    rx: RecordId,
+}
+
+impl Engine {
+    /// Applies the retention rules once.
+    ///
+    /// Returns the number of messages removed.
+    pub async fn compact(&self) -> Result<usize, SystemActorError> {
+        let db = self.surreal_for("retention")?;
+        let options = self.options().retention.clone();
+        let mut removed = 0;
+
+        if let Some(ttl) = options.message_ttl {
+            let query = include_str!("../sql/retention_expired.surql");
+            let mut res = db.query(query).bind(("ttl", ttl.as_secs())).await?;
+            let expired: Vec<Record> = res.take(0)?;
+            removed += self.delete_messages(expired.into_iter().map(|r| r.id).collect()).await?;
+        }
+
+        if let Some(keep) = options.keep_last {
+            let mut res = db.query("SELECT rx FROM message GROUP BY rx").await?;
+            let receivers: Vec<RxRecord> = res.take(0)?;
+            for RxRecord { rx } in receivers {
+                let query = include_str!("../sql/retention_keep_last.surql");
+                let mut res = db.query(query).bind(("rx", rx)).bind(("keep", keep)).await?;
+                let expired: Vec<Record> = res.take(0)?;
+                removed += self.delete_messages(expired.into_iter().map(|r| r.id).collect()).await?;
+            }
+        }
+
+        if let Some(ttl) = options.reply_ttl {
+            let query = include_str!("../sql/retention_chunks.surql");
+            let mut res = db.query(query).bind(("ttl", ttl.as_secs())).await?;
+            let chunks: Vec<Record> = res.take(0)?;
+            if !chunks.is_empty() {
+                let ids: Vec<RecordId> = chunks.into_iter().map(|r| r.id).collect();
+                debug!("retention-chunks {}", ids.len());
+                let query = include_str!("../sql/retention_delete_chunks.surql");
+                db.query(query).bind(("ids", ids)).await?;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Removes messages together with their replies.
+    pub(crate) async fn delete_messages(&self, ids: Vec<RecordId>) -> Result<usize, SystemActorError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let db = self.surreal_for("retention")?;
+        let count = ids.len();
+        debug!("retention-delete {}", count);
+        let query = include_str!("../sql/retention_delete.surql");
+        db.query(query).bind(("ids", ids)).await?;
+        Ok(count)
+    }
+}
+
+/// Spawns the background compaction task, if any periodic rule is configured.
+pub(crate) fn spawn_compaction(engine: &Engine) {
+    let options = engine.options().retention.clone();
+    if !options.needs_compaction() {
+        return;
+    }
+    let engine = engine.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(options.compaction_interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = engine.shutdown_token().cancelled() => break,
+            }
+            if let Err(e) = engine.compact().await {
+                error!("retention-compaction {}", e);
+            }
+        }
+    });
+}
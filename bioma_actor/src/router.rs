@@ -0,0 +1,380 @@
+use crate::actor::{send_reply, FrameReply};
+use crate::backend::ReplyFrameStream;
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use surrealdb::RecordId;
+use tokio::time::Instant;
+use tracing::{debug, error, warn};
+
+/// How a `Router` picks the routees of a message.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingStrategy {
+    /// Each routee in turn.
+    #[default]
+    RoundRobin,
+    /// The routee with the fewest unreplied messages, counting the messages forwarded by the
+    /// router and not answered yet.
+    LeastLoaded,
+    /// The same routee for every message with the same key, as long as that routee is in the pool.
+    ///
+    /// `key` is a JSON pointer into the message content, e.g. `/user_id`. The whole content is
+    /// the key if not set, or if the pointer doesn't match.
+    ConsistentHash { key: Option<String> },
+    /// Every routee, with their replies merged into a single stream.
+    Broadcast,
+}
+
+/// An actor forwarding the messages it receives to a pool of actors.
+///
+/// Senders address the router as if it were one of the routees: messages are forwarded as they
+/// are, and the replies of the routees are streamed back to the sender. The router answers the
+/// `AddRoutee`, `RemoveRoutee` and `ListRoutees` messages itself, to resize the pool while it
+/// runs. With `discover`, the running actors matching an `ActorQuery` join the pool too.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let router = Router::builder()
+///     .routees(vec![ActorId::of::<Rerank>("/rerank/0"), ActorId::of::<Rerank>("/rerank/1")])
+///     .strategy(RoutingStrategy::LeastLoaded)
+///     .build();
+/// let router_id = ActorId::of::<Router>("/rerank");
+/// let (mut router_ctx, mut router) = Actor::spawn(engine.clone(), router_id.clone(), router, SpawnOptions::default()).await?;
+/// tokio::spawn(async move { router.start(&mut router_ctx).await });
+///
+/// // Handled by one of the rerank actors
+/// let ranked = relay_ctx.send_and_wait_reply::<Rerank, RankTexts>(rank_texts, &router_id, SendOptions::default()).await?;
+/// ```
+#[derive(bon::Builder, Debug, Serialize, Deserialize)]
+pub struct Router {
+    /// Actors to forward messages to
+    #[builder(default)]
+    #[serde(default)]
+    pub routees: Vec<ActorId>,
+    /// How routees are picked
+    #[builder(default)]
+    #[serde(default)]
+    pub strategy: RoutingStrategy,
+    /// Also forward to the actors matching this query, e.g. the running actors under a name prefix
+    #[serde(default)]
+    pub discover: Option<ActorQuery>,
+    /// How often the actors matching `discover` are looked up
+    #[builder(default = default_refresh_interval())]
+    #[serde(default = "default_refresh_interval", with = "humantime_serde")]
+    pub refresh_interval: Duration,
+    /// How long to wait for each reply of a routee before failing the message
+    #[builder(default = default_reply_timeout())]
+    #[serde(default = "default_reply_timeout", with = "humantime_serde")]
+    pub reply_timeout: Duration,
+    #[builder(skip)]
+    #[serde(skip)]
+    pool: Pool,
+}
+
+fn default_refresh_interval() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn default_reply_timeout() -> Duration {
+    SendOptions::default().timeout
+}
+
+/// Routing state that is not persisted.
+#[derive(Debug, Default)]
+struct Pool {
+    /// Round-robin position
+    next: usize,
+    /// Actors matching `discover` at the last lookup
+    discovered: Vec<ActorId>,
+    refreshed: Option<Instant>,
+    /// Forwarded messages without a final reply, per routee
+    in_flight: Arc<Mutex<HashMap<ActorId, usize>>>,
+}
+
+/// A message forwarded to a routee, until its replies are relayed.
+struct InFlight {
+    counts: Arc<Mutex<HashMap<ActorId, usize>>>,
+    routee: ActorId,
+}
+
+impl InFlight {
+    fn new(counts: &Arc<Mutex<HashMap<ActorId, usize>>>, routee: &ActorId) -> Self {
+        *counts.lock().unwrap().entry(routee.clone()).or_default() += 1;
+        Self { counts: counts.clone(), routee: routee.clone() }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut counts = self.counts.lock().unwrap();
+        if let Some(count) = counts.get_mut(&self.routee) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                counts.remove(&self.routee);
+            }
+        }
+    }
+}
+
+/// Adds an actor to the pool of a `Router`, replying with the routees.
+#[derive(Clone, Debug, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.actor.AddRoutee")]
+pub struct AddRoutee(pub ActorId);
+
+/// Removes an actor from the pool of a `Router`, replying with the routees.
+///
+/// Messages already forwarded to the actor are still answered.
+#[derive(Clone, Debug, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.actor.RemoveRoutee")]
+pub struct RemoveRoutee(pub ActorId);
+
+/// Asks a `Router` for its routees, including discovered actors.
+#[derive(Clone, Debug, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.actor.ListRoutees")]
+pub struct ListRoutees;
+
+impl Router {
+    /// The routees, then the discovered actors that are not routees, never the router itself.
+    fn current(&self, ctx: &ActorContext<Self>) -> Vec<ActorId> {
+        let discovered = self.pool.discovered.iter().filter(|id| !self.routees.contains(id));
+        // The router may match its own query, e.g. `/rerank` discovering `/rerank/...`
+        self.routees.iter().chain(discovered).filter(|id| *id != ctx.id()).cloned().collect()
+    }
+
+    /// Looks up the actors matching `discover`, if due.
+    async fn refresh(&mut self, ctx: &ActorContext<Self>, force: bool) {
+        let Some(query) = &self.discover else {
+            return;
+        };
+        let due = self.pool.refreshed.is_none_or(|refreshed| refreshed.elapsed() >= self.refresh_interval);
+        if !force && !due {
+            return;
+        }
+        match ctx.engine().actors(query).await {
+            Ok(records) => {
+                self.pool.discovered = records.iter().map(ActorRecord::actor_id).collect();
+                self.pool.refreshed = Some(Instant::now());
+            }
+            Err(e) => error!("[{}] router-discover-error {}", ctx.id().record_id(), e),
+        }
+    }
+
+    /// Picks the routees of a message.
+    async fn pick(&mut self, ctx: &ActorContext<Self>, frame: &FrameMessage) -> Vec<ActorId> {
+        let routees = self.current(ctx);
+        if routees.is_empty() {
+            return vec![];
+        }
+        match &self.strategy {
+            RoutingStrategy::RoundRobin => {
+                let routee = routees[self.pool.next % routees.len()].clone();
+                self.pool.next = self.pool.next.wrapping_add(1);
+                vec![routee]
+            }
+            RoutingStrategy::LeastLoaded => {
+                // Start from the round-robin position to spread messages between idle routees
+                let start = self.pool.next % routees.len();
+                self.pool.next = self.pool.next.wrapping_add(1);
+                let mut least: Option<(usize, &ActorId)> = None;
+                for routee in routees[start..].iter().chain(&routees[..start]) {
+                    let load = self.load(ctx, routee).await;
+                    if least.is_none_or(|(least, _)| load < least) {
+                        least = Some((load, routee));
+                    }
+                }
+                least.map(|(_, routee)| routee.clone()).into_iter().collect()
+            }
+            RoutingStrategy::ConsistentHash { key } => {
+                let key = key.as_deref().and_then(|key| frame.msg.pointer(key)).unwrap_or(&frame.msg);
+                let key = key.to_string();
+                // Rendezvous hashing: a key only moves when its routee leaves the pool
+                let routee = routees.iter().max_by_key(|routee| hash(&[key.as_bytes(), routee.name().as_bytes()]));
+                routee.cloned().into_iter().collect()
+            }
+            RoutingStrategy::Broadcast => routees,
+        }
+    }
+
+    /// Unreplied messages of a routee, counted without loading them.
+    async fn load(&self, ctx: &ActorContext<Self>, routee: &ActorId) -> usize {
+        let in_flight = self.pool.in_flight.lock().unwrap().get(routee).copied().unwrap_or_default();
+        // Forwarded messages may not be stored yet, or never with local delivery
+        match ctx.engine().backend().unreplied_count(&routee.record_id()).await {
+            Ok(unreplied) => unreplied.max(in_flight),
+            Err(e) => {
+                warn!("[{}] router-load-error {} {}", ctx.id().record_id(), routee.record_id(), e);
+                in_flight
+            }
+        }
+    }
+
+    /// Forwards a message and relays the replies in the background.
+    async fn route(&mut self, ctx: &ActorContext<Self>, frame: FrameMessage) {
+        self.refresh(ctx, false).await;
+        let routees = self.pick(ctx, &frame).await;
+        let mut forwarded = vec![];
+        let mut replies = vec![];
+        let mut in_flight = vec![];
+        for routee in &routees {
+            match ctx.forward(&frame, routee).await {
+                Ok((msg_id, stream)) => {
+                    forwarded.push(msg_id);
+                    replies.push(stream);
+                    in_flight.push(InFlight::new(&self.pool.in_flight, routee));
+                }
+                Err(e) => error!("[{}] router-forward-error {} {}", ctx.id().record_id(), routee.record_id(), e),
+            }
+        }
+        if replies.is_empty() {
+            warn!("[{}] router-no-routee {} {}", ctx.id().record_id(), frame.name, frame.id());
+        }
+        debug!("[{}] router-route {} {} {:?}", ctx.id().record_id(), frame.name, frame.id(), routees);
+        let forwarded = Forwarded { msg_ids: forwarded, replies, _in_flight: in_flight };
+        tokio::spawn(relay(ctx.engine().clone(), frame, forwarded, self.reply_timeout));
+    }
+}
+
+/// The messages forwarded for one message sent to the router.
+struct Forwarded {
+    msg_ids: Vec<RecordId>,
+    replies: Vec<ReplyFrameStream>,
+    _in_flight: Vec<InFlight>,
+}
+
+/// Relays the replies of the routees to the sender of `frame`, as a single stream.
+///
+/// If the sender cancels `frame`, the forwarded messages are cancelled and the relay stops.
+async fn relay(engine: Engine, frame: FrameMessage, forwarded: Forwarded, timeout: Duration) {
+    let cancel = engine.cancellations().register(frame.id());
+    let local_replies = if frame.local { engine.local().take_replies(frame.id()) } else { None };
+    let id = frame.id().key().to_string();
+    let chunk_reply = |chunk: u64, msg: Value, err: Value| {
+        let mut reply =
+            FrameReply::new_chunk(id.clone(), chunk, frame.name.clone(), frame.rx.clone(), frame.tx.clone(), msg);
+        reply.err = err;
+        reply
+    };
+
+    let mut chunk = 0;
+    let mut errors = vec![];
+    if forwarded.replies.is_empty() {
+        errors.push(format!("No routee for {}", frame.name));
+    }
+    let mut replies = futures::stream::select_all(forwarded.replies);
+    loop {
+        let next = match cancel.run_until_cancelled(tokio::time::timeout(timeout, replies.next())).await {
+            Some(next) => next,
+            None => {
+                debug!("[{}] router-cancelled {} {}", frame.rx, frame.name, id);
+                for msg_id in &forwarded.msg_ids {
+                    engine.cancel_message(msg_id).await;
+                }
+                break;
+            }
+        };
+        let (msg, err) = match next {
+            // Each routee ends its replies, the sender gets a single final reply
+            Ok(Some(Ok(reply))) if reply.is_final() && reply.err.is_null() => continue,
+            Ok(Some(Ok(reply))) => (reply.msg, reply.err),
+            Ok(Some(Err(e))) => (Value::Null, Value::String(e.to_string())),
+            Ok(None) => break,
+            Err(_) => {
+                errors.push(SystemActorError::MessageTimeout(frame.name.clone(), timeout).to_string());
+                break;
+            }
+        };
+        chunk += 1;
+        if let Err(e) = send_reply(&engine, &frame, local_replies.as_ref(), chunk_reply(chunk, msg, err)).await {
+            error!("[{}] router-reply-error {} {} {}", frame.rx, frame.name, id, e);
+        }
+    }
+    for error in errors {
+        chunk += 1;
+        let reply = chunk_reply(chunk, Value::Null, Value::String(error));
+        if let Err(e) = send_reply(&engine, &frame, local_replies.as_ref(), reply).await {
+            error!("[{}] router-reply-error {} {} {}", frame.rx, frame.name, id, e);
+        }
+    }
+
+    let reply = FrameReply::new_final(id.clone(), frame.name.clone(), frame.rx.clone(), frame.tx.clone());
+    if let Err(e) = send_reply(&engine, &frame, local_replies.as_ref(), reply).await {
+        error!("[{}] router-final-error {} {} {}", frame.rx, frame.name, id, e);
+    }
+    engine.cancellations().unregister(frame.id());
+}
+
+/// FNV-1a over the parts, stable across processes and builds.
+fn hash(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        for byte in part.iter().chain(&[0xffu8]) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+impl Message<AddRoutee> for Router {
+    type Response = Vec<ActorId>;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &AddRoutee) -> Result<(), SystemActorError> {
+        if !self.routees.contains(&message.0) {
+            self.routees.push(message.0.clone());
+            self.save(ctx).await?;
+        }
+        ctx.reply(self.current(ctx)).await
+    }
+}
+
+impl Message<RemoveRoutee> for Router {
+    type Response = Vec<ActorId>;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &RemoveRoutee) -> Result<(), SystemActorError> {
+        if self.routees.contains(&message.0) {
+            self.routees.retain(|routee| routee != &message.0);
+            self.save(ctx).await?;
+        }
+        self.pool.discovered.retain(|routee| routee != &message.0);
+        ctx.reply(self.current(ctx)).await
+    }
+}
+
+impl Message<ListRoutees> for Router {
+    type Response = Vec<ActorId>;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _message: &ListRoutees) -> Result<(), SystemActorError> {
+        self.refresh(ctx, true).await;
+        ctx.reply(self.current(ctx)).await
+    }
+}
+
+impl Actor for Router {
+    type Error = SystemActorError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), SystemActorError> {
+        let mut stream = ctx.recv().await?;
+        while let Some(frame) = stream.next().await {
+            let frame = frame?;
+            let result = if let Some(message) = frame.is::<AddRoutee>() {
+                self.reply(ctx, &message, &frame).await
+            } else if let Some(message) = frame.is::<RemoveRoutee>() {
+                self.reply(ctx, &message, &frame).await
+            } else if let Some(message) = frame.is::<ListRoutees>() {
+                self.reply(ctx, &message, &frame).await
+            } else {
+                self.route(ctx, frame).await;
+                Ok(())
+            };
+            if let Err(e) = result {
+                error!("[{}] router-error {}", ctx.id().record_id(), e);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+use crate::actor::DB_TABLE_MESSAGE;
+use crate::engine::Record;
+use crate::prelude::*;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::str::FromStr;
+use std::time::Duration;
+use surrealdb::sql::Datetime;
+use surrealdb::RecordId;
+use tracing::{debug, error, warn};
+
+const DB_TABLE_SCHEDULE: &str = "schedule";
+
+/// Settings for the scheduler delivering delayed and periodic messages.
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct ScheduleOptions {
+    /// How often to check for due scheduled messages.
+    #[builder(default = default_poll_interval())]
+    #[serde(default = "default_poll_interval", with = "humantime_serde")]
+    pub poll_interval: Duration,
+}
+
+fn default_poll_interval() -> Duration {
+    Duration::from_secs(1)
+}
+
+impl Default for ScheduleOptions {
+    fn default() -> Self {
+        ScheduleOptions::builder().build()
+    }
+}
+
+/// When a scheduled message is delivered.
+///
+/// # Example
+///
+/// ```rust
+/// // Every day at 09:00 UTC
+/// let daily = Schedule::Cron("0 0 9 * * *".to_string());
+/// // Every 30 seconds
+/// let periodic = Schedule::Every(std::time::Duration::from_secs(30));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Schedule {
+    /// Deliver once at the given time, immediately if it is in the past.
+    At(DateTime<Utc>),
+    /// Deliver repeatedly with a fixed period, starting one period from now.
+    Every(#[serde(with = "humantime_serde")] Duration),
+    /// Deliver repeatedly following a cron expression with seconds (`sec min hour day month weekday`).
+    Cron(String),
+}
+
+impl Schedule {
+    /// Schedule a single delivery after `delay`.
+    pub fn after(delay: Duration) -> Result<Self, SystemActorError> {
+        let delay = TimeDelta::from_std(delay).map_err(|e| SystemActorError::InvalidSchedule(e.to_string().into()))?;
+        Ok(Schedule::At(Utc::now() + delay))
+    }
+
+    fn validate(&self) -> Result<(), SystemActorError> {
+        match self {
+            Schedule::At(_) => Ok(()),
+            Schedule::Every(period) if period.is_zero() => {
+                Err(SystemActorError::InvalidSchedule("period must not be zero".into()))
+            }
+            Schedule::Every(period) => TimeDelta::from_std(*period)
+                .map(|_| ())
+                .map_err(|e| SystemActorError::InvalidSchedule(e.to_string().into())),
+            Schedule::Cron(expr) => cron::Schedule::from_str(expr)
+                .map(|_| ())
+                .map_err(|e| SystemActorError::InvalidSchedule(format!("{}: {}", expr, e).into())),
+        }
+    }
+
+    /// The first delivery time.
+    fn first(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::At(at) => Some(*at),
+            _ => self.next_after(now),
+        }
+    }
+
+    /// The next delivery time after `after`, `None` once the schedule is exhausted.
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::At(_) => None,
+            Schedule::Every(period) => TimeDelta::from_std(*period).ok().map(|period| after + period),
+            Schedule::Cron(expr) => cron::Schedule::from_str(expr).ok()?.after(&after).next(),
+        }
+    }
+}
+
+/// A message waiting in the `schedule` table for its delivery time.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ScheduledMessage {
+    id: RecordId,
+    name: Cow<'static, str>,
+    tx: RecordId,
+    rx: RecordId,
+    #[serde(default)]
+    msg: Value,
+    #[serde(default)]
+    version: Option<u32>,
+    schedule: Schedule,
+    fire_at: Datetime,
+    /// Schedule id key, only returned by the due query
+    #[serde(default, skip_serializing)]
+    key: String,
+}
+
+impl Engine {
+    /// Persists a message to be delivered according to `schedule`.
+    ///
+    /// Returns the schedule id, which can be used with `Engine::cancel_schedule`.
+    pub(crate) async fn schedule_message(
+        &self,
+        frame: FrameMessage,
+        schedule: Schedule,
+    ) -> Result<RecordId, SystemActorError> {
+        schedule.validate()?;
+        let Some(fire_at) = schedule.first(Utc::now()) else {
+            return Err(SystemActorError::InvalidSchedule("schedule never fires".into()));
+        };
+
+        let id = RecordId::from_table_key(DB_TABLE_SCHEDULE, ulid::Ulid::new().to_string());
+        debug!("[{}] schedule {} {} {:?} at {}", frame.rx, frame.name, id, schedule, fire_at);
+        let scheduled = ScheduledMessage {
+            id: id.clone(),
+            name: frame.name,
+            tx: frame.tx,
+            rx: frame.rx,
+            msg: frame.msg,
+            version: frame.version,
+            schedule,
+            fire_at: fire_at.into(),
+            key: String::new(),
+        };
+        let _record: Option<Record> =
+            self.surreal_for("schedules")?.create(DB_TABLE_SCHEDULE).content(scheduled).await?;
+        Ok(id)
+    }
+
+    /// Cancels a scheduled message.
+    ///
+    /// Returns `false` if the schedule does not exist, e.g. a single delivery that already fired.
+    pub async fn cancel_schedule(&self, id: &RecordId) -> Result<bool, SystemActorError> {
+        let removed: Option<Record> = self.surreal_for("schedules")?.delete(id).await?;
+        Ok(removed.is_some())
+    }
+
+    /// Delivers all scheduled messages that are due.
+    ///
+    /// Each firing gets a message id derived from the schedule and its fire time. The message is
+    /// stored before the fire time is claimed in a transaction, and a message that is already stored
+    /// is not stored again, so a failed firing is retried on the next poll and every delivery
+    /// happens exactly once even when several processes share the same database.
+    async fn fire_due_schedules(&self) -> Result<(), SystemActorError> {
+        let query = include_str!("../sql/schedule_due.surql");
+        let mut res = self.surreal_for("schedules")?.query(query).await?;
+        let due: Vec<ScheduledMessage> = res.take(0)?;
+
+        let now = Utc::now();
+        for scheduled in due {
+            let fire_at = scheduled.fire_at.0;
+            // Missed deliveries (e.g. while no engine was running) are coalesced into one
+            let next = match scheduled.schedule.next_after(fire_at) {
+                Some(next) if next <= now => scheduled.schedule.next_after(now),
+                next => next,
+            };
+
+            let seed = ulid::Ulid::from_string(&scheduled.key).map(|key| key.random()).unwrap_or_default();
+            let msg_key = ulid::Ulid::from_parts(fire_at.timestamp_millis() as u64, seed).to_string();
+            let mut frame = FrameMessage::from_parts_with_id(
+                RecordId::from_table_key(DB_TABLE_MESSAGE, msg_key),
+                scheduled.name,
+                scheduled.tx,
+                scheduled.rx,
+                scheduled.msg,
+            );
+            frame.version = scheduled.version;
+            debug!("[{}] schedule-fire {} {} -> {}", frame.rx, frame.name, scheduled.id, frame.id());
+
+            // Receivers in this engine get the message in memory once the firing is claimed. A copy
+            // stored by another process has the same id, and receivers skip ids they already got.
+            let local = self.options().local_delivery.is_enabled() && self.local().is_receiving(&frame.rx);
+            if !local {
+                if let Err(e) = self.enqueue_once(&frame).await {
+                    warn!("schedule-fire {} {}", scheduled.id, e);
+                    continue;
+                }
+            }
+
+            let query = include_str!("../sql/schedule_fire.surql");
+            let mut res = self
+                .surreal_for("schedules")?
+                .query(query)
+                .bind(("id", scheduled.id.clone()))
+                .bind(("fire_at", scheduled.fire_at))
+                .bind(("next", next.map(Datetime::from)))
+                .await?;
+            let last = res.num_statements() - 1;
+            let claimed: Option<bool> = match res.take(last) {
+                Ok(claimed) => claimed,
+                Err(e) => {
+                    warn!("schedule-fire {} {}", scheduled.id, e);
+                    continue;
+                }
+            };
+            if !local || !claimed.unwrap_or_default() {
+                continue;
+            }
+
+            if let Err(frame) = self.deliver_local(frame, false) {
+                self.enqueue_claimed(&scheduled.id, frame).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Enqueues the message of a claimed firing whose receiver stopped receiving in this engine.
+    ///
+    /// The schedule has already moved on, so the enqueue is retried until it succeeds or the
+    /// engine shuts down.
+    async fn enqueue_claimed(&self, id: &RecordId, frame: FrameMessage) {
+        let mut backoff = Duration::from_millis(100);
+        while let Err(e) = self.enqueue_once(&frame).await {
+            error!("schedule-fire {} {}", id, e);
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = self.shutdown_token().cancelled() => return,
+            }
+            backoff = (backoff * 2).min(self.options().schedule.poll_interval);
+        }
+    }
+
+    /// Enqueues the message of a firing, unless an earlier attempt already stored it.
+    async fn enqueue_once(&self, frame: &FrameMessage) -> Result<(), SystemActorError> {
+        if self.backend().message_exists(frame.id()).await? {
+            return Ok(());
+        }
+        self.backend().enqueue(frame.clone()).await
+    }
+}
+
+/// Spawns the background task delivering scheduled messages.
+pub(crate) fn spawn_scheduler(engine: &Engine) {
+    let engine = engine.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(engine.options().schedule.poll_interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = engine.shutdown_token().cancelled() => break,
+            }
+            if let Err(e) = engine.fire_due_schedules().await {
+                error!("schedule-scan {}", e);
+            }
+        }
+    });
+}
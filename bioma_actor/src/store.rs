@@ -0,0 +1,78 @@
+use crate::actor::SystemActorError;
+use object_store::{aws::AmazonS3Builder, local::LocalFileSystem, memory::InMemory, ObjectStore};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::info;
+
+/// The object store of an engine, shared by its actors through `Engine::store`.
+///
+/// # Example
+///
+/// ```rust
+/// // A local MinIO, with credentials in AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY
+/// let store = ObjectStoreOptions::S3 {
+///     bucket: "bioma".into(),
+///     endpoint: Some("http://localhost:9000".into()),
+///     region: None,
+///     allow_http: true,
+/// };
+/// let options = EngineOptions::builder().object_store(store).build();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObjectStoreOptions {
+    /// Files under `EngineOptions::local_store_dir`.
+    #[default]
+    Local,
+    /// Objects in the memory of the process, lost when it exits.
+    Memory,
+    /// A bucket of Amazon S3 or of an S3-compatible service, such as MinIO.
+    ///
+    /// Credentials, and any setting not given here, are read from the `AWS_*` environment
+    /// variables, e.g. `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_DEFAULT_REGION`.
+    S3 {
+        /// Name of the bucket
+        bucket: String,
+        /// URL of an S3-compatible service, instead of Amazon S3
+        #[serde(default)]
+        endpoint: Option<String>,
+        /// Region of the bucket
+        #[serde(default)]
+        region: Option<String>,
+        /// Whether the endpoint can be plain HTTP
+        #[serde(default)]
+        allow_http: bool,
+    },
+}
+
+impl ObjectStoreOptions {
+    /// Opens the store, creating `local_store_dir` if it is used and missing.
+    pub(crate) fn open(&self, local_store_dir: &Path) -> Result<Arc<dyn ObjectStore>, SystemActorError> {
+        match self {
+            ObjectStoreOptions::Local => {
+                std::fs::create_dir_all(local_store_dir)?;
+                info!("Object store: local {}", local_store_dir.display());
+                Ok(Arc::new(LocalFileSystem::new_with_prefix(local_store_dir)?))
+            }
+            ObjectStoreOptions::Memory => {
+                info!("Object store: memory");
+                Ok(Arc::new(InMemory::new()))
+            }
+            ObjectStoreOptions::S3 { bucket, endpoint, region, allow_http } => {
+                let mut builder = AmazonS3Builder::from_env().with_bucket_name(bucket);
+                if *allow_http {
+                    builder = builder.with_allow_http(true);
+                }
+                if let Some(endpoint) = endpoint {
+                    builder = builder.with_endpoint(endpoint);
+                }
+                if let Some(region) = region {
+                    builder = builder.with_region(region);
+                }
+                info!("Object store: s3 {} {}", bucket, endpoint.as_deref().unwrap_or("aws"));
+                Ok(Arc::new(builder.build()?))
+            }
+        }
+    }
+}
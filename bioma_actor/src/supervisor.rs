@@ -0,0 +1,524 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::{AbortHandle, JoinError, JoinHandle};
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+
+/// How a supervisor reacts when one of its children terminates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RestartStrategy {
+    /// Only the terminated child is restarted.
+    #[default]
+    OneForOne,
+    /// Every child is stopped and restarted.
+    ///
+    /// Siblings that are `Temporary` or no longer running are stopped but not restarted.
+    OneForAll,
+    /// The terminated child and every child declared after it are restarted.
+    ///
+    /// Siblings that are `Temporary` or no longer running are stopped but not restarted.
+    RestForOne,
+}
+
+/// When a terminated child is eligible for a restart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChildRestart {
+    /// Always restart the child, even after a normal exit.
+    #[default]
+    Permanent,
+    /// Restart the child only if it returned an error or panicked.
+    Transient,
+    /// Never restart the child.
+    Temporary,
+}
+
+/// The reason an actor task terminated.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExitReason {
+    /// `Actor::start` returned `Ok(())`.
+    Normal,
+    /// `Actor::start` (or spawning the actor) returned an error.
+    Error(String),
+    /// The actor task panicked.
+    Panic(String),
+    /// The actor task was aborted.
+    Aborted,
+    /// The actor record was removed from the database, e.g. by `ActorContext::kill`.
+    Killed,
+    /// The actor did not exist when it was watched.
+    NotFound,
+}
+
+impl ExitReason {
+    /// Returns `true` if the actor terminated abnormally.
+    pub fn is_failure(&self) -> bool {
+        matches!(self, ExitReason::Error(_) | ExitReason::Panic(_))
+    }
+
+    /// Builds an exit reason from the output of an actor task.
+    pub fn from_join(result: &Result<Result<(), SystemActorError>, JoinError>) -> Self {
+        match result {
+            Ok(Ok(())) => ExitReason::Normal,
+            Ok(Err(e)) => ExitReason::Error(e.to_string()),
+            Err(e) if e.is_cancelled() => ExitReason::Aborted,
+            Err(e) => ExitReason::Panic(e.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitReason::Normal => write!(f, "normal"),
+            ExitReason::Error(e) => write!(f, "error: {}", e),
+            ExitReason::Panic(e) => write!(f, "panic: {}", e),
+            ExitReason::Aborted => write!(f, "aborted"),
+            ExitReason::Killed => write!(f, "killed"),
+            ExitReason::NotFound => write!(f, "not found"),
+        }
+    }
+}
+
+/// Configuration options for a supervisor.
+///
+/// # Example
+///
+/// ```rust
+/// // Restart everything if any child fails, at most 5 times per minute
+/// let options = SupervisorOptions::builder()
+///     .strategy(RestartStrategy::OneForAll)
+///     .max_restarts(5)
+///     .within(std::time::Duration::from_secs(60))
+///     .build();
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct SupervisorOptions {
+    /// The restart strategy applied when a child terminates.
+    #[builder(default)]
+    #[serde(default)]
+    pub strategy: RestartStrategy,
+    /// The maximum number of restarts allowed within `within` before the supervisor gives up.
+    #[builder(default = 3)]
+    pub max_restarts: usize,
+    /// The sliding time window used to count restarts.
+    #[builder(default = Duration::from_secs(5))]
+    #[serde(with = "humantime_serde")]
+    pub within: Duration,
+    /// The delay before the first restart in a window, doubled for each following restart.
+    #[builder(default = Duration::from_millis(100))]
+    #[serde(with = "humantime_serde")]
+    pub min_backoff: Duration,
+    /// The upper bound for the restart delay.
+    #[builder(default = Duration::from_secs(10))]
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
+}
+
+impl Default for SupervisorOptions {
+    fn default() -> Self {
+        SupervisorOptions::builder().build()
+    }
+}
+
+impl SupervisorOptions {
+    /// The delay to wait before the `restarts`-th restart within the current window.
+    fn backoff(&self, restarts: usize) -> Duration {
+        let exp = restarts.saturating_sub(1).min(31) as u32;
+        self.min_backoff.saturating_mul(2u32.saturating_pow(exp)).min(self.max_backoff)
+    }
+}
+
+/// Reports whether a child was spawned, before its actor starts running.
+type Spawned = oneshot::Sender<Result<(), String>>;
+
+type ChildStart = Arc<dyn Fn(Engine, ActorId, SpawnOptions, Spawned) -> ActorHandle + Send + Sync>;
+
+/// Declares a child actor managed by a [`Supervisor`].
+///
+/// The start function is called every time the child needs to be (re)started. It receives
+/// the spawn options to use: the child's own options for the first start, and
+/// `SpawnExistsOptions::Restore` for every restart so the child picks up its persisted state.
+///
+/// # Example
+///
+/// ```rust
+/// let echo = Echo { max_echoes: 10 };
+/// let spec = ChildSpec::of(ActorId::of::<Echo>("/parent/echo"), echo);
+/// ```
+#[derive(Clone)]
+pub struct ChildSpec {
+    id: ActorId,
+    start: ChildStart,
+    restart: ChildRestart,
+    options: SpawnOptions,
+}
+
+impl ChildSpec {
+    /// Creates a child spec that spawns a clone of `actor` and runs it with `ActorContext::run`.
+    ///
+    /// Errors of the actor are reported to the supervisor as `SystemActorError::ActorFailed`.
+    pub fn of<A: Actor + Clone + 'static>(id: ActorId, actor: A) -> Self {
+        let start = move |engine: Engine, id: ActorId, options: SpawnOptions, spawned: Spawned| {
+            let actor = actor.clone();
+            tokio::spawn(async move {
+                let (mut ctx, mut actor) = match Actor::spawn(engine, id, actor, options).await {
+                    Ok(spawned_actor) => {
+                        let _ = spawned.send(Ok(()));
+                        spawned_actor
+                    }
+                    Err(e) => {
+                        let _ = spawned.send(Err(e.to_string()));
+                        return Err(SystemActorError::ActorFailed(e.to_string().into()));
+                    }
+                };
+                ctx.run(&mut actor).await.map_err(|e| SystemActorError::ActorFailed(e.to_string().into()))
+            })
+        };
+        Self { id, start: Arc::new(start), restart: ChildRestart::default(), options: SpawnOptions::default() }
+    }
+
+    /// Creates a child spec from a start function.
+    ///
+    /// The child counts as spawned as soon as the function returns, so prefer `ChildSpec::of`
+    /// for a supervisor to notice children that cannot be spawned.
+    pub fn new<F>(id: ActorId, start: F) -> Self
+    where
+        F: Fn(Engine, ActorId, SpawnOptions) -> ActorHandle + Send + Sync + 'static,
+    {
+        let start = move |engine: Engine, id: ActorId, options: SpawnOptions, spawned: Spawned| {
+            let handle = start(engine, id, options);
+            let _ = spawned.send(Ok(()));
+            handle
+        };
+        Self { id, start: Arc::new(start), restart: ChildRestart::default(), options: SpawnOptions::default() }
+    }
+
+    /// Creates a child spec that is started through an `ActorFactory` with the given config.
+    pub fn from_factory<F>(id: ActorId, factory: F, config: serde_json::Value) -> Self
+    where
+        F: ActorFactory + 'static,
+    {
+        let factory = Arc::new(factory);
+        let start = move |engine: Engine, id: ActorId, options: SpawnOptions, spawned: Spawned| {
+            let result = factory.spawn(engine, config.clone(), id, options);
+            let _ = spawned.send(result.as_ref().map(|_| ()).map_err(|e| e.to_string()));
+            result.unwrap_or_else(|e| tokio::spawn(async move { Err(e) }))
+        };
+        Self { id, start: Arc::new(start), restart: ChildRestart::default(), options: SpawnOptions::default() }
+    }
+
+    /// Sets when the child should be restarted.
+    pub fn restart(mut self, restart: ChildRestart) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    /// Sets the spawn options used for the first start of the child.
+    ///
+    /// Restarts use `SpawnExistsOptions::Restore` with the same `SpawnOptions::lease_timeout`.
+    pub fn options(mut self, options: SpawnOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Get the child actor id
+    pub fn id(&self) -> &ActorId {
+        &self.id
+    }
+}
+
+impl std::fmt::Debug for ChildSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChildSpec").field("id", &self.id).field("restart", &self.restart).finish()
+    }
+}
+
+/// Supervises a set of child actors and restarts them when they terminate.
+///
+/// Children are started in declaration order, each one once the previous one is spawned. If a
+/// child cannot be spawned at all, the other children are stopped and the supervisor fails.
+/// When a child terminates, the supervisor decides whether to restart it based on its
+/// [`ChildRestart`] and applies the [`RestartStrategy`].
+/// If more than `max_restarts` restarts happen within `within`, all children are stopped
+/// and the supervisor fails with `SystemActorError::RestartLimitExceeded`.
+///
+/// # Example
+///
+/// ```rust
+/// async fn init(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
+///     let supervisor = Supervisor::new(ctx.engine().clone(), SupervisorOptions::default())
+///         .child(embeddings_spec)
+///         .child(rerank_spec)
+///         .spawn()
+///         .await?;
+///     // Dropping the handle stops the supervisor and all its children
+///     self.supervisor = Some(supervisor);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Supervisor {
+    engine: Engine,
+    options: SupervisorOptions,
+    children: Vec<ChildSpec>,
+}
+
+impl Supervisor {
+    /// Creates a supervisor without children.
+    pub fn new(engine: Engine, options: SupervisorOptions) -> Self {
+        Self { engine, options, children: Vec::new() }
+    }
+
+    /// Declares a child, started after all previously declared children.
+    pub fn child(mut self, spec: ChildSpec) -> Self {
+        self.children.push(spec);
+        self
+    }
+
+    /// Starts all children and the supervision task.
+    pub fn start(self) -> SupervisorHandle {
+        self.start_with(None)
+    }
+
+    /// Starts all children and the supervision task, and waits until every child is spawned.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first child that could not be spawned, after stopping the
+    /// children spawned before it.
+    pub async fn spawn(self) -> Result<SupervisorHandle, SystemActorError> {
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let mut supervisor = self.start_with(Some(ready_tx));
+        if ready_rx.await.is_err() {
+            // The supervisor ended before spawning every child
+            return Err(match (&mut supervisor.handle).await {
+                Ok(Err(e)) => e,
+                Ok(Ok(())) => SystemActorError::ActorFailed("supervisor stopped while spawning".into()),
+                Err(e) => e.into(),
+            });
+        }
+        Ok(supervisor)
+    }
+
+    fn start_with(self, ready: Option<oneshot::Sender<()>>) -> SupervisorHandle {
+        let children = self.children.iter().map(|spec| spec.id.clone()).collect();
+        let handle = tokio::spawn(self.run(ready));
+        SupervisorHandle { handle, children }
+    }
+
+    async fn run(self, ready: Option<oneshot::Sender<()>>) -> Result<(), SystemActorError> {
+        let (exit_tx, mut exit_rx) = mpsc::unbounded_channel();
+        let mut running = RunningChildren { children: self.children.iter().map(|_| None).collect(), generation: 0 };
+
+        for (idx, spec) in self.children.iter().enumerate() {
+            let spawned = running.start(idx, spec, &self.engine, spec.options.clone(), &exit_tx);
+            let error = match spawned.await {
+                Ok(Ok(())) => continue,
+                Ok(Err(e)) => e,
+                Err(_) => "child task ended before spawning".to_string(),
+            };
+            // Dropping the running children stops those already spawned
+            error!("[{}] child-spawn-failed {}", spec.id.record_id(), error);
+            return Err(SystemActorError::ActorFailed(format!("{}: {}", spec.id.name(), error).into()));
+        }
+        if let Some(ready) = ready {
+            let _ = ready.send(());
+        }
+
+        let mut restarts: VecDeque<Instant> = VecDeque::new();
+
+        while let Some(exit) = exit_rx.recv().await {
+            // Ignore exits from children we stopped ourselves
+            if !running.is_current(exit.index, exit.generation) {
+                continue;
+            }
+            running.children[exit.index] = None;
+
+            let spec = &self.children[exit.index];
+            if exit.reason.is_failure() {
+                error!("[{}] child-exit {}", spec.id.record_id(), exit.reason);
+            } else {
+                debug!("[{}] child-exit {}", spec.id.record_id(), exit.reason);
+            }
+
+            let restart = match spec.restart {
+                ChildRestart::Permanent => true,
+                ChildRestart::Transient => exit.reason.is_failure(),
+                ChildRestart::Temporary => false,
+            };
+
+            if !restart {
+                if running.is_empty() {
+                    return Ok(());
+                }
+                continue;
+            }
+
+            // Enforce the restart intensity within the sliding window
+            let now = Instant::now();
+            restarts.push_back(now);
+            while restarts.front().is_some_and(|t| now.duration_since(*t) > self.options.within) {
+                restarts.pop_front();
+            }
+            if restarts.len() > self.options.max_restarts {
+                error!(
+                    "[{}] child-restart-limit {} restarts within {:?}",
+                    spec.id.record_id(),
+                    self.options.max_restarts,
+                    self.options.within
+                );
+                return Err(SystemActorError::RestartLimitExceeded(spec.id.clone(), self.options.max_restarts));
+            }
+
+            let backoff = self.options.backoff(restarts.len());
+            warn!("[{}] child-restart in {:?}", spec.id.record_id(), backoff);
+            tokio::time::sleep(backoff).await;
+
+            let targets = match self.options.strategy {
+                RestartStrategy::OneForOne => exit.index..exit.index + 1,
+                RestartStrategy::OneForAll => 0..self.children.len(),
+                RestartStrategy::RestForOne => exit.index..self.children.len(),
+            };
+
+            // Siblings are only restarted if they are still running and not temporary
+            let restarted: Vec<usize> = targets
+                .clone()
+                .filter(|&idx| {
+                    idx == exit.index
+                        || (running.children[idx].is_some() && self.children[idx].restart != ChildRestart::Temporary)
+                })
+                .collect();
+
+            // Stop siblings in reverse start order, then start them again in order
+            for idx in targets.rev() {
+                running.stop(idx);
+            }
+            for idx in restarted {
+                let spec = &self.children[idx];
+                info!("[{}] child-restart", spec.id.record_id());
+                let restore = SpawnOptions::builder()
+                    .exists(SpawnExistsOptions::Restore)
+                    .maybe_lease_timeout(spec.options.lease_timeout())
+                    .build();
+                // A restart that cannot be spawned fails the child task, and counts as a restart
+                let _ = running.start(idx, spec, &self.engine, restore, &exit_tx);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Notification sent by a child monitor when the child task completes.
+struct ChildExit {
+    index: usize,
+    generation: u64,
+    reason: ExitReason,
+}
+
+struct RunningChild {
+    generation: u64,
+    abort: AbortHandle,
+}
+
+/// Bookkeeping for the running children of a supervisor.
+///
+/// Dropping it aborts every child, so stopping the supervisor task stops its children.
+struct RunningChildren {
+    children: Vec<Option<RunningChild>>,
+    generation: u64,
+}
+
+impl RunningChildren {
+    /// Starts a child, returning whether it was spawned.
+    fn start(
+        &mut self,
+        idx: usize,
+        spec: &ChildSpec,
+        engine: &Engine,
+        options: SpawnOptions,
+        exit_tx: &mpsc::UnboundedSender<ChildExit>,
+    ) -> oneshot::Receiver<Result<(), String>> {
+        self.generation += 1;
+        let generation = self.generation;
+        debug!("[{}] child-start {}", spec.id.record_id(), generation);
+        let (spawned_tx, spawned_rx) = oneshot::channel();
+        let handle = (spec.start)(engine.clone(), spec.id.clone(), options, spawned_tx);
+        self.children[idx] = Some(RunningChild { generation, abort: handle.abort_handle() });
+
+        // Monitor the child task and report its termination
+        let exit_tx = exit_tx.clone();
+        tokio::spawn(async move {
+            let result = handle.await;
+            let reason = ExitReason::from_join(&result);
+            let _ = exit_tx.send(ChildExit { index: idx, generation, reason });
+        });
+        spawned_rx
+    }
+
+    fn stop(&mut self, idx: usize) {
+        if let Some(child) = self.children[idx].take() {
+            child.abort.abort();
+        }
+    }
+
+    fn is_current(&self, idx: usize, generation: u64) -> bool {
+        self.children[idx].as_ref().is_some_and(|child| child.generation == generation)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.children.iter().all(|child| child.is_none())
+    }
+}
+
+impl Drop for RunningChildren {
+    fn drop(&mut self) {
+        for idx in (0..self.children.len()).rev() {
+            self.stop(idx);
+        }
+    }
+}
+
+/// Handle to a running [`Supervisor`].
+///
+/// Dropping the handle stops the supervisor and all of its children.
+#[derive(Debug)]
+pub struct SupervisorHandle {
+    handle: JoinHandle<Result<(), SystemActorError>>,
+    children: Vec<ActorId>,
+}
+
+impl SupervisorHandle {
+    /// The ids of the supervised children, in declaration order.
+    pub fn children(&self) -> &[ActorId] {
+        &self.children
+    }
+
+    /// Returns `true` if the supervisor is no longer running.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Stops the supervisor and all of its children.
+    pub fn stop(&self) {
+        self.handle.abort();
+    }
+
+    /// Waits for the supervisor to finish.
+    ///
+    /// Resolves once every child has terminated without being restarted, or with
+    /// `SystemActorError::RestartLimitExceeded` if the supervisor gave up.
+    pub async fn join(mut self) -> Result<(), SystemActorError> {
+        (&mut self.handle).await?
+    }
+}
+
+impl Drop for SupervisorHandle {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
@@ -0,0 +1,273 @@
+//! Helpers for testing actors without a database or real waiting.
+//!
+//! A `TestKit` runs an engine on the in-memory backend, spawns the actors under test and
+//! aborts them when dropped. `TestProbe`s stand in for the actors receiving messages, so tests
+//! can assert on what was sent, and stubs stand in for the actors being asked, with canned
+//! replies. With a paused clock, timeouts and delays elapse as soon as every task is waiting.
+//!
+//! Only built with the `testkit` feature, typically enabled for the dev-dependencies.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! #[tokio::test]
+//! async fn test_lookup() -> Result<(), SystemActorError> {
+//!     let mut kit = TestKit::paused().await?;
+//!     let mut audit = kit.probe("/audit").await?;
+//!     let store = kit.stub::<Store, Get>("/store", vec![Some(42)]).await?;
+//!     let lookup = kit.spawn("/lookup", Lookup { store, audit: audit.id().clone() }).await?;
+//!
+//!     let value = kit.relay().send_and_wait_reply::<Lookup, Find>(Find, &lookup, SendOptions::default()).await?;
+//!     assert_eq!(value, Some(42));
+//!     let logged = audit.expect_msg::<Found>(Duration::from_secs(1)).await;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::marker::PhantomData;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// An engine on the in-memory backend, with the actors spawned for a test.
+///
+/// The actors are aborted when the kit is dropped.
+pub struct TestKit {
+    engine: Engine,
+    relay: ActorContext<Relay>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl TestKit {
+    /// Starts an engine with the default options.
+    pub async fn new() -> Result<Self, SystemActorError> {
+        Self::with_options(EngineOptions::default()).await
+    }
+
+    /// Starts an engine with the given options.
+    pub async fn with_options(options: EngineOptions) -> Result<Self, SystemActorError> {
+        let engine = Engine::with_backend(MemoryBackend::default(), options)?;
+        let (relay, _) =
+            Actor::spawn(engine.clone(), ActorId::of::<Relay>("/testkit/relay"), Relay, SpawnOptions::default())
+                .await?;
+        Ok(Self { engine, relay, tasks: vec![] })
+    }
+
+    /// Pauses the clock of the runtime, then starts an engine with the default options.
+    ///
+    /// While paused, time only moves forward when every task is waiting, jumping straight to
+    /// the next timer. `SendOptions` timeouts and delays in actors elapse instantly, in the same
+    /// order as in real time.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless called from a current-thread runtime, the default of `#[tokio::test]`.
+    pub async fn paused() -> Result<Self, SystemActorError> {
+        tokio::time::pause();
+        Self::new().await
+    }
+
+    /// Moves the paused clock forward, firing the timers due in the meantime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the clock is not paused.
+    pub async fn advance(&self, duration: Duration) {
+        tokio::time::advance(duration).await;
+    }
+
+    /// The engine of the test.
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// An actor to send messages from the test.
+    pub fn relay(&self) -> &ActorContext<Relay> {
+        &self.relay
+    }
+
+    /// Spawns an actor and runs it until the kit is dropped.
+    pub async fn spawn<T: Actor + 'static>(&mut self, name: &str, actor: T) -> Result<ActorId, T::Error> {
+        let id = ActorId::of::<T>(name.to_string());
+        let (mut ctx, mut actor) =
+            Actor::spawn(self.engine.clone(), id.clone(), actor, SpawnOptions::default()).await?;
+        self.tasks.push(tokio::spawn(async move {
+            if let Err(e) = ctx.run(&mut actor).await {
+                warn!("[{}] testkit-actor-error {}", ctx.id().record_id(), e);
+            }
+        }));
+        Ok(id)
+    }
+
+    /// Spawns a probe, recording the messages sent to it.
+    pub async fn probe(&self, name: &str) -> Result<TestProbe, SystemActorError> {
+        let id = ActorId::of::<Probe>(name.to_string());
+        let (ctx, _) = Actor::spawn(self.engine.clone(), id, Probe, SpawnOptions::default()).await?;
+        let stream = ctx.recv().await?;
+        Ok(TestProbe { ctx, stream, received: vec![], last: None })
+    }
+
+    /// Spawns a stub of the actor `M`, replying to each `MT` message with `replies`.
+    ///
+    /// The stub has the tag of `M`, and each reply is sent as a chunk of the reply stream.
+    /// Messages of other types are logged and left unreplied.
+    pub async fn stub<M, MT>(&mut self, name: &str, replies: Vec<M::Response>) -> Result<ActorId, SystemActorError>
+    where
+        M: Message<MT>,
+        MT: MessageType,
+    {
+        let id = ActorId::of::<M>(name.to_string());
+        let replies = replies.iter().map(serde_json::to_value).collect::<Result<Vec<_>, _>>()?;
+        let stub = Stub::<MT> { replies, message: PhantomData };
+        let (mut ctx, mut stub) = Actor::spawn(self.engine.clone(), id.clone(), stub, SpawnOptions::default()).await?;
+        self.tasks.push(tokio::spawn(async move {
+            let _ = ctx.run(&mut stub).await;
+        }));
+        Ok(id)
+    }
+}
+
+impl Drop for TestKit {
+    fn drop(&mut self) {
+        self.tasks.iter().for_each(JoinHandle::abort);
+        self.engine.shutdown();
+    }
+}
+
+/// The actor behind a `TestProbe`, never started.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Probe;
+
+impl Actor for Probe {
+    type Error = SystemActorError;
+
+    async fn start(&mut self, _ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
+        panic!("Probe should not be started");
+    }
+}
+
+/// Records the messages sent to it, for assertions in tests.
+///
+/// Messages are only received while waiting in `expect_msg` or `expect_no_msg`, and stay
+/// unreplied unless answered with `reply`.
+pub struct TestProbe {
+    ctx: ActorContext<Probe>,
+    stream: MessageStream,
+    received: Vec<FrameMessage>,
+    last: Option<FrameMessage>,
+}
+
+impl TestProbe {
+    /// The id to send messages to the probe.
+    pub fn id(&self) -> &ActorId {
+        self.ctx.id()
+    }
+
+    /// The context of the probe, to send messages as the probe.
+    pub fn ctx(&self) -> &ActorContext<Probe> {
+        &self.ctx
+    }
+
+    /// Every message received so far.
+    pub fn received(&self) -> &[FrameMessage] {
+        &self.received
+    }
+
+    /// Waits for the next message and decodes it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no message arrives within `timeout`, or if the message is not a `T`.
+    pub async fn expect_msg<T: MessageType>(&mut self, timeout: Duration) -> T {
+        let Some(frame) = self.next_frame(timeout).await else {
+            panic!("[{}] no message within {:?}, expected {}", self.id(), timeout, std::any::type_name::<T>());
+        };
+        match frame.is::<T>() {
+            Some(message) => message,
+            None => {
+                panic!("[{}] expected {}, received {} {}", self.id(), std::any::type_name::<T>(), frame.name, frame.msg)
+            }
+        }
+    }
+
+    /// Waits for `duration`, checking that no message arrives.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a message arrives.
+    pub async fn expect_no_msg(&mut self, duration: Duration) {
+        if let Some(frame) = self.next_frame(duration).await {
+            panic!("[{}] expected no message, received {} {}", self.id(), frame.name, frame.msg);
+        }
+    }
+
+    /// Replies to the last received message with `response`, ending its reply stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no message was received yet.
+    pub async fn reply<R: MessageType>(&mut self, response: R) -> Result<(), SystemActorError> {
+        let frame = self.last.take().unwrap_or_else(|| panic!("[{}] no message to reply to", self.id()));
+        let handle = self.ctx.start_message_processing(frame).await;
+        let result = self.ctx.reply(response).await;
+        self.ctx.finish_message_processing(None).await;
+        let _ = handle.await;
+        result
+    }
+
+    async fn next_frame(&mut self, timeout: Duration) -> Option<FrameMessage> {
+        match tokio::time::timeout(timeout, self.stream.next()).await {
+            Ok(Some(Ok(frame))) => {
+                self.received.push(frame.clone());
+                self.last = Some(frame.clone());
+                Some(frame)
+            }
+            Ok(Some(Err(e))) => panic!("[{}] message stream error {}", self.id(), e),
+            Ok(None) | Err(_) => None,
+        }
+    }
+}
+
+/// Replies to `MT` messages with canned replies, see `TestKit::stub`.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Stub<MT> {
+    replies: Vec<Value>,
+    #[serde(skip)]
+    message: PhantomData<fn() -> MT>,
+}
+
+impl<MT> std::fmt::Debug for Stub<MT> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Stub").field("message", &std::any::type_name::<MT>()).field("replies", &self.replies).finish()
+    }
+}
+
+impl<MT: MessageType> Message<MT> for Stub<MT> {
+    type Response = Value;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _message: &MT) -> Result<(), SystemActorError> {
+        for reply in &self.replies {
+            ctx.reply(reply.clone()).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<MT: MessageType> Actor for Stub<MT> {
+    type Error = SystemActorError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
+        let mut stream = ctx.recv().await?;
+        while let Some(Ok(frame)) = stream.next().await {
+            match frame.is::<MT>() {
+                Some(message) => self.reply(ctx, &message, &frame).await?,
+                None => warn!("[{}] stub-unhandled {} {}", ctx.id().record_id(), frame.name, frame.id()),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+/// Trace context carried by messages and replies, compatible with W3C Trace Context.
+///
+/// Every message sent while processing another one belongs to the same trace, so the messages
+/// of a request fanning out to several actors can be correlated in logs and in exported spans.
+///
+/// # Example
+///
+/// ```rust
+/// // Continue the trace of an incoming HTTP request, or start a new one
+/// let trace = traceparent
+///     .and_then(TraceContext::from_traceparent)
+///     .unwrap_or_default()
+///     .with_baggage("user", "alice");
+/// let options = SendOptions::builder().timeout(Duration::from_secs(30)).trace(trace).build();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceContext {
+    /// Id of the trace, 32 lowercase hex digits
+    pub trace_id: String,
+    /// Id of the span that sent the message or reply, 16 lowercase hex digits
+    pub span_id: String,
+    /// Key-value pairs propagated to every message of the trace
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub baggage: BTreeMap<String, String>,
+}
+
+impl TraceContext {
+    /// A context starting a new trace.
+    pub fn new() -> Self {
+        Self { trace_id: format!("{:032x}", ulid::Ulid::new().0), span_id: new_span_id(), baggage: BTreeMap::new() }
+    }
+
+    /// A context for a new span in the same trace, with the same baggage.
+    pub fn child(&self) -> Self {
+        Self { trace_id: self.trace_id.clone(), span_id: new_span_id(), baggage: self.baggage.clone() }
+    }
+
+    /// Adds a baggage item, propagated to every message of the trace.
+    pub fn with_baggage(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.baggage.insert(key.into(), value.into());
+        self
+    }
+
+    /// The context as a W3C `traceparent` header value.
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", self.trace_id, self.span_id)
+    }
+
+    /// Parses a W3C `traceparent` header value, `None` if it is not valid.
+    pub fn from_traceparent(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let (version, trace_id, span_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        let is_hex = |id: &str, len: usize| {
+            id.len() == len && id.bytes().all(|b| b.is_ascii_hexdigit()) && id.bytes().any(|b| b != b'0')
+        };
+        if version.len() != 2 || version == "ff" || flags.len() != 2 || !is_hex(trace_id, 32) || !is_hex(span_id, 16) {
+            return None;
+        }
+        Some(Self {
+            trace_id: trace_id.to_ascii_lowercase(),
+            span_id: span_id.to_ascii_lowercase(),
+            baggage: BTreeMap::new(),
+        })
+    }
+}
+
+impl Default for TraceContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn new_span_id() -> String {
+    // The low bits of a ULID are random
+    format!("{:016x}", ulid::Ulid::new().random() as u64)
+}
+
+/// Where the spans of sent and processed messages are exported.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TraceExporter {
+    /// Spans are not recorded. Trace contexts are still propagated and logged.
+    #[default]
+    None,
+    /// OTLP over HTTP with JSON encoding, e.g. to a local OpenTelemetry collector.
+    Otlp {
+        /// Collector endpoint, spans are posted to `{endpoint}/v1/traces`
+        #[serde(default = "default_otlp_endpoint")]
+        endpoint: Cow<'static, str>,
+    },
+    /// OTLP JSON appended to a file, one export request per line.
+    File {
+        /// Path of the file, created if missing
+        path: PathBuf,
+    },
+}
+
+fn default_otlp_endpoint() -> Cow<'static, str> {
+    "http://localhost:4318".into()
+}
+
+/// Settings for exporting the spans of messages.
+///
+/// A span is recorded when an actor sends a message, and when an actor processes one. The
+/// spans of a request share its trace id, and each span points to the span that caused it.
+///
+/// # Example
+///
+/// ```rust
+/// let tracing = TracingOptions::builder()
+///     .exporter(TraceExporter::Otlp { endpoint: "http://localhost:4318".into() })
+///     .service_name("rag".into())
+///     .build();
+/// let options = EngineOptions::builder().tracing(tracing).build();
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct TracingOptions {
+    /// Where spans are exported, nowhere by default.
+    #[builder(default)]
+    #[serde(default)]
+    pub exporter: TraceExporter,
+    /// The `service.name` of the exported spans.
+    #[builder(default = default_service_name())]
+    #[serde(default = "default_service_name")]
+    pub service_name: Cow<'static, str>,
+    /// How often finished spans are exported.
+    #[builder(default = default_export_interval())]
+    #[serde(default = "default_export_interval", with = "humantime_serde")]
+    pub export_interval: Duration,
+    /// Largest number of spans in one export.
+    #[builder(default = default_max_batch())]
+    #[serde(default = "default_max_batch")]
+    pub max_batch: usize,
+}
+
+fn default_service_name() -> Cow<'static, str> {
+    "bioma".into()
+}
+
+fn default_export_interval() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_max_batch() -> usize {
+    512
+}
+
+impl Default for TracingOptions {
+    fn default() -> Self {
+        TracingOptions::builder().build()
+    }
+}
+
+/// The role of a span in a message exchange.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SpanKind {
+    /// Sending a message
+    Producer,
+    /// Processing a message
+    Consumer,
+}
+
+/// A finished span, waiting to be exported.
+#[derive(Debug)]
+struct SpanRecord {
+    name: String,
+    kind: SpanKind,
+    trace_id: String,
+    span_id: String,
+    parent_span_id: Option<String>,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(&'static str, String)>,
+    error: Option<String>,
+}
+
+/// A span being recorded, exported when it ends.
+pub(crate) struct Span {
+    record: Option<SpanRecord>,
+    spans: Option<mpsc::UnboundedSender<SpanRecord>>,
+}
+
+impl Span {
+    /// Adds an attribute to the span.
+    pub(crate) fn attribute(mut self, key: &'static str, value: impl ToString) -> Self {
+        if let Some(record) = &mut self.record {
+            record.attributes.push((key, value.to_string()));
+        }
+        self
+    }
+
+    /// Ends the span, failed if `error` is set.
+    pub(crate) fn end(mut self, error: Option<String>) {
+        let (Some(mut record), Some(spans)) = (self.record.take(), &self.spans) else {
+            return;
+        };
+        record.end = SystemTime::now();
+        record.error = error;
+        let _ = spans.send(record);
+    }
+}
+
+/// Records spans and exports them in the background, as configured in `TracingOptions`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Tracer {
+    spans: Option<mpsc::UnboundedSender<SpanRecord>>,
+}
+
+impl Tracer {
+    /// Starts exporting spans, if an exporter is configured.
+    pub(crate) fn start(options: &TracingOptions) -> Self {
+        if options.exporter == TraceExporter::None {
+            return Self::default();
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(export(options.clone(), rx));
+        Self { spans: Some(tx) }
+    }
+
+    /// Starts a span for `context`, the child of `parent` if set.
+    pub(crate) fn span(
+        &self,
+        name: impl Into<String>,
+        kind: SpanKind,
+        context: &TraceContext,
+        parent: Option<&TraceContext>,
+    ) -> Span {
+        let record = self.spans.as_ref().map(|_| SpanRecord {
+            name: name.into(),
+            kind,
+            trace_id: context.trace_id.clone(),
+            span_id: context.span_id.clone(),
+            parent_span_id: parent.map(|parent| parent.span_id.clone()),
+            start: SystemTime::now(),
+            end: SystemTime::now(),
+            attributes: vec![],
+            error: None,
+        });
+        Span { record, spans: self.spans.clone() }
+    }
+}
+
+/// Exports the recorded spans in batches, until every `Tracer` is dropped.
+async fn export(options: TracingOptions, mut spans: mpsc::UnboundedReceiver<SpanRecord>) {
+    let client = reqwest::Client::new();
+    let mut interval = tokio::time::interval(options.export_interval);
+    let mut batch = vec![];
+    loop {
+        let closed = tokio::select! {
+            span = spans.recv() => match span {
+                Some(span) => {
+                    batch.push(span);
+                    if batch.len() < options.max_batch {
+                        continue;
+                    }
+                    false
+                }
+                None => true,
+            },
+            _ = interval.tick() => false,
+        };
+        if !batch.is_empty() {
+            let request = otlp_request(&options, std::mem::take(&mut batch));
+            if let Err(e) = write(&options.exporter, &client, &request).await {
+                error!("trace-export-error {}", e);
+            }
+        }
+        if closed {
+            break;
+        }
+    }
+}
+
+async fn write(exporter: &TraceExporter, client: &reqwest::Client, request: &Value) -> Result<(), String> {
+    match exporter {
+        TraceExporter::None => Ok(()),
+        TraceExporter::Otlp { endpoint } => {
+            let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+            let response = client
+                .post(&url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(request.to_string())
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            debug!("trace-export {} {}", url, response.status());
+            response.error_for_status().map(|_| ()).map_err(|e| e.to_string())
+        }
+        TraceExporter::File { path } => {
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await.map_err(|e| e.to_string())?;
+            }
+            let mut file =
+                tokio::fs::OpenOptions::new().create(true).append(true).open(path).await.map_err(|e| e.to_string())?;
+            let line = format!("{}\n", request);
+            file.write_all(line.as_bytes()).await.map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// An OTLP `ExportTraceServiceRequest` in its JSON encoding.
+fn otlp_request(options: &TracingOptions, spans: Vec<SpanRecord>) -> Value {
+    let spans: Vec<Value> = spans.into_iter().map(otlp_span).collect();
+    json!({
+        "resourceSpans": [{
+            "resource": { "attributes": [otlp_attribute("service.name", &options.service_name)] },
+            "scopeSpans": [{
+                "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn otlp_span(span: SpanRecord) -> Value {
+    let unix_nanos = |time: SystemTime| time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string();
+    // SPAN_KIND_PRODUCER and SPAN_KIND_CONSUMER
+    let kind = match span.kind {
+        SpanKind::Producer => 4,
+        SpanKind::Consumer => 5,
+    };
+    // STATUS_CODE_OK and STATUS_CODE_ERROR
+    let status = match &span.error {
+        None => json!({ "code": 1 }),
+        Some(error) => json!({ "code": 2, "message": error }),
+    };
+    let attributes: Vec<Value> = span.attributes.iter().map(|(key, value)| otlp_attribute(key, value)).collect();
+    let mut value = json!({
+        "traceId": span.trace_id,
+        "spanId": span.span_id,
+        "name": span.name,
+        "kind": kind,
+        "startTimeUnixNano": unix_nanos(span.start),
+        "endTimeUnixNano": unix_nanos(span.end),
+        "attributes": attributes,
+        "status": status,
+    });
+    if let Some(parent_span_id) = span.parent_span_id {
+        value["parentSpanId"] = Value::String(parent_span_id);
+    }
+    value
+}
+
+fn otlp_attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
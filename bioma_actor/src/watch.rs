@@ -0,0 +1,61 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio::task::AbortHandle;
+use tracing::{debug, error};
+
+/// Message delivered to a watcher when a watched actor terminates.
+///
+/// # Example
+///
+/// ```rust
+/// ctx.watch(&child_id).await?;
+///
+/// let mut stream = ctx.recv().await?;
+/// while let Some(Ok(frame)) = stream.next().await {
+///     if let Some(terminated) = frame.is::<Terminated>() {
+///         warn!("{} terminated: {}", terminated.id, terminated.reason);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.actor.Terminated")]
+pub struct Terminated {
+    /// The id of the terminated actor
+    pub id: ActorId,
+    /// Why the actor terminated
+    pub reason: ExitReason,
+}
+
+/// Spawns a task that delivers `Terminated` to `watcher` once `watched` terminates.
+pub(crate) async fn watch(engine: Engine, watcher: ActorId, watched: ActorId) -> Result<AbortHandle, SystemActorError> {
+    debug!("[{}] watch-live {}", watcher.record_id(), watched.record_id());
+    // The stream survives connection losses, the record is checked again after resubscribing
+    let mut exits = engine.backend().subscribe_exit(&watched.record_id()).await?;
+
+    let handle = tokio::spawn(async move {
+        let reason = loop {
+            match exits.next().await {
+                Some(Ok(reason)) => break reason,
+                Some(Err(e)) => error!("[{}] watch-error {} {}", watcher.record_id(), watched.record_id(), e),
+                None => return,
+            }
+        };
+
+        debug!("[{}] watch-terminated {} {}", watcher.record_id(), watched.record_id(), reason);
+
+        // Deliver the notification as a regular message from the watched actor
+        let terminated = Terminated { id: watched.clone(), reason };
+        let frame = match FrameMessage::new(&watched, &watcher, &terminated) {
+            Ok(frame) => frame,
+            Err(e) => {
+                error!("[{}] watch-error {} {}", watcher.record_id(), watched.record_id(), e);
+                return;
+            }
+        };
+        if let Err(e) = engine.backend().enqueue(frame).await {
+            error!("[{}] watch-send-error {} {}", watcher.record_id(), watched.record_id(), e);
+        }
+    });
+
+    Ok(handle.abort_handle())
+}
@@ -0,0 +1,143 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::{sleep, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+/// Replies with the numbers from 1 to `n`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Count(u64);
+
+#[derive(Debug, Default, Serialize, Deserialize, ActorHandlers)]
+#[handles(Count)]
+struct Counter {
+    total: u64,
+}
+
+impl Message<Count> for Counter {
+    type Response = u64;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &Count) -> Result<(), TestError> {
+        for i in 1..=message.0 {
+            ctx.reply(i).await?;
+        }
+        self.total += message.0;
+        self.save(ctx).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Counter {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+async fn spawn_counter(
+    engine: &Engine,
+    options: SpawnOptions,
+) -> Result<(ActorId, tokio::task::JoinHandle<()>), TestError> {
+    let counter_id = ActorId::of::<Counter>("/counter");
+    let (mut counter_ctx, mut counter) =
+        Actor::spawn(engine.clone(), counter_id.clone(), Counter::default(), options).await?;
+    let handle = tokio::spawn(async move {
+        let _ = counter_ctx.run(&mut counter).await;
+    });
+    // Give the counter time to start receiving
+    sleep(Duration::from_millis(100)).await;
+    Ok((counter_id, handle))
+}
+
+#[test(tokio::test)]
+async fn test_memory_backend_send() -> Result<(), TestError> {
+    let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+    assert!(engine.health().await);
+    let (counter_id, counter_handle) = spawn_counter(&engine, SpawnOptions::default()).await?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    let replies = relay_ctx.send_and_collect::<Counter, Count>(Count(3), &counter_id, SendOptions::default()).await?;
+    assert_eq!(replies, vec![1, 2, 3]);
+    let replies = relay_ctx.send_and_collect::<Counter, Count>(Count(0), &counter_id, SendOptions::default()).await?;
+    assert!(replies.is_empty());
+
+    // Nothing is left unreplied
+    let unreplied = engine.backend().unreplied(&counter_id.record_id()).await?;
+    assert!(unreplied.is_empty());
+
+    counter_handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_memory_backend_immediate_replies() -> Result<(), TestError> {
+    let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+    let (counter_id, counter_handle) = spawn_counter(&engine, SpawnOptions::default()).await?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    // Replies are subscribed before sending, so replies sent right away are not lost
+    for _ in 0..50 {
+        let options = SendOptions::builder().timeout(Duration::from_secs(5)).build();
+        let replies = relay_ctx.send_and_collect::<Counter, Count>(Count(1), &counter_id, options).await?;
+        assert_eq!(replies, vec![1]);
+    }
+
+    counter_handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_memory_backend_restore() -> Result<(), TestError> {
+    let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    // Sent before the counter runs, the message waits in the backlog
+    let counter_id = ActorId::of::<Counter>("/counter");
+    relay_ctx.do_send::<Counter, Count>(Count(2), &counter_id).await?;
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(engine.backend().unreplied(&counter_id.record_id()).await?.len(), 1);
+
+    let (_, counter_handle) = spawn_counter(&engine, SpawnOptions::default()).await?;
+    assert!(engine.backend().unreplied(&counter_id.record_id()).await?.is_empty());
+    counter_handle.abort();
+    sleep(Duration::from_millis(50)).await;
+
+    // The saved state survives a restart
+    let options = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).build();
+    let (counter_ctx, counter) = Actor::spawn(engine.clone(), counter_id.clone(), Counter::default(), options).await?;
+    assert_eq!(counter.total, 2);
+    let record = engine.backend().get_actor(&counter_id.record_id()).await?.unwrap();
+    assert!(record.instance().is_some());
+
+    counter_ctx.kill().await?;
+    assert!(engine.backend().get_actor(&counter_id.record_id()).await?.is_none());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_memory_backend_unsupported() -> Result<(), TestError> {
+    let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    // Features needing SurrealDB fail with a typed error instead of querying an unconnected client
+    let unsupported = |result: Result<(), SystemActorError>| matches!(result, Err(SystemActorError::Unsupported(_)));
+    assert!(unsupported(relay_ctx.subscribe("news").await));
+    assert!(unsupported(engine.compact().await.map(|_| ())));
+    assert!(unsupported(engine.journal::<serde_json::Value>(relay_ctx.id(), 0).await.map(|_| ())));
+    assert!(unsupported(engine.db().map(|_| ())));
+
+    engine.shutdown();
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use surrealdb::{engine::any::Any, Surreal};
+use test_log::test;
+use tokio::time::{sleep, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+/// Works for up to 5 seconds, or forwards the work to `child` if set
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Slow {
+    child: Option<ActorId>,
+}
+
+/// Replies with how many `Slow` messages were cancelled
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CancelledCount;
+
+#[derive(Debug, Default, Serialize, Deserialize, ActorHandlers)]
+#[handles(Slow, CancelledCount)]
+struct Worker {
+    cancelled: usize,
+}
+
+impl Message<Slow> for Worker {
+    type Response = bool;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &Slow) -> Result<(), TestError> {
+        if let Some(child) = &message.child {
+            let forward = Slow { child: None };
+            ctx.send_and_collect::<Worker, Slow>(forward, child, SendOptions::default()).await?;
+        } else {
+            let cancel = ctx.cancellation_token();
+            if cancel.run_until_cancelled(sleep(Duration::from_secs(5))).await.is_some() {
+                ctx.reply(true).await?;
+                return Ok(());
+            }
+        }
+        if ctx.is_cancelled() {
+            self.cancelled += 1;
+        }
+        Ok(())
+    }
+}
+
+impl Message<CancelledCount> for Worker {
+    type Response = usize;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _message: &CancelledCount) -> Result<(), TestError> {
+        ctx.reply(self.cancelled).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Worker {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+async fn spawn_worker(engine: &Engine, name: &str) -> Result<(ActorId, tokio::task::JoinHandle<()>), TestError> {
+    let id = ActorId::of::<Worker>(name.to_string());
+    let (mut ctx, mut actor) =
+        Actor::spawn(engine.clone(), id.clone(), Worker::default(), SpawnOptions::default()).await?;
+    let handle = tokio::spawn(async move {
+        let _ = ctx.run(&mut actor).await;
+    });
+    Ok((id, handle))
+}
+
+async fn cancelled_count(relay: &ActorContext<Relay>, id: &ActorId) -> Result<usize, TestError> {
+    let options = SendOptions::builder().timeout(Duration::from_secs(2)).build();
+    Ok(relay.send_and_wait_reply::<Worker, CancelledCount>(CancelledCount, id, options).await?)
+}
+
+async fn setup(
+    names: &[&str],
+) -> Result<(ActorContext<Relay>, Vec<ActorId>, Vec<tokio::task::JoinHandle<()>>), TestError> {
+    let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+    let mut ids = vec![];
+    let mut handles = vec![];
+    for name in names {
+        let (id, handle) = spawn_worker(&engine, name).await?;
+        ids.push(id);
+        handles.push(handle);
+    }
+    let (relay, _) = Actor::spawn(engine, ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    // Give the actors time to start receiving
+    sleep(Duration::from_millis(100)).await;
+    Ok((relay, ids, handles))
+}
+
+#[test(tokio::test)]
+async fn test_cancel_on_timeout() -> Result<(), TestError> {
+    let (relay, ids, handles) = setup(&["/worker"]).await?;
+
+    let options = SendOptions::builder().timeout(Duration::from_millis(200)).build();
+    let result = relay.send_and_wait_reply::<Worker, Slow>(Slow { child: None }, &ids[0], options).await;
+    assert!(matches!(result, Err(SystemActorError::MessageTimeout(_, _))));
+    assert_eq!(cancelled_count(&relay, &ids[0]).await?, 1);
+
+    handles.iter().for_each(|handle| handle.abort());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_cancel_on_drop() -> Result<(), TestError> {
+    let (relay, ids, handles) = setup(&["/worker"]).await?;
+
+    let mut stream = relay.send::<Worker, Slow>(Slow { child: None }, &ids[0], SendOptions::default()).await?;
+    assert!(tokio::time::timeout(Duration::from_millis(200), stream.next()).await.is_err());
+    drop(stream);
+    assert_eq!(cancelled_count(&relay, &ids[0]).await?, 1);
+
+    handles.iter().for_each(|handle| handle.abort());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_cancel_cascades() -> Result<(), TestError> {
+    let (relay, ids, handles) = setup(&["/parent", "/child"]).await?;
+
+    let options = SendOptions::builder().timeout(Duration::from_millis(300)).build();
+    let slow = Slow { child: Some(ids[1].clone()) };
+    let result = relay.send_and_wait_reply::<Worker, Slow>(slow, &ids[0], options).await;
+    assert!(result.is_err());
+    assert_eq!(cancelled_count(&relay, &ids[0]).await?, 1);
+    assert_eq!(cancelled_count(&relay, &ids[1]).await?, 1);
+
+    handles.iter().for_each(|handle| handle.abort());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_cancel_removed_with_final_reply() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    let (id, handle) = spawn_worker(&engine, "/worker").await?;
+    let (relay, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    sleep(Duration::from_millis(100)).await;
+
+    let options = SendOptions::builder().timeout(Duration::from_millis(200)).build();
+    let result = relay.send_and_wait_reply::<Worker, Slow>(Slow { child: None }, &id, options).await;
+    assert!(result.is_err());
+    assert_eq!(cancelled_count(&relay, &id).await?, 1);
+
+    // The cancelled message got its final reply, its cancel is not kept around
+    sleep(Duration::from_millis(200)).await;
+    let mut res =
+        engine.db()?.query("SELECT count() AS count FROM cancel GROUP ALL").await.map_err(SystemActorError::from)?;
+    let cancels: Option<usize> = res.take((0, "count")).map_err(SystemActorError::from)?;
+    assert_eq!(cancels.unwrap_or_default(), 0);
+
+    handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_cancel_other_engine() -> Result<(), TestError> {
+    // Two engines sharing the same database
+    let db: Surreal<Any> = Surreal::init();
+    db.connect("memory").await.map_err(SystemActorError::from)?;
+    let backend = SurrealBackend::new(db, "test".into(), "test".into()).await?;
+    let engine = |node_id: &'static str| {
+        let lease = LeaseOptions::builder().node_id(node_id.into()).build();
+        Engine::with_backend(backend.clone(), EngineOptions::builder().lease(lease).build())
+    };
+    let (engine_a, engine_b) = (engine("node-a")?, engine("node-b")?);
+
+    let (id, handle) = spawn_worker(&engine_b, "/worker").await?;
+    let (relay, _) = Actor::spawn(engine_a, ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    sleep(Duration::from_millis(100)).await;
+
+    // The cancel is stored by the sender's engine and applied by the receiver's engine
+    let options = SendOptions::builder().timeout(Duration::from_millis(200)).build();
+    let result = relay.send_and_wait_reply::<Worker, Slow>(Slow { child: None }, &id, options).await;
+    assert!(matches!(result, Err(SystemActorError::MessageTimeout(_, _))));
+    assert_eq!(cancelled_count(&relay, &id).await?, 1);
+
+    handle.abort();
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use test_log::test;
+use tokio::time::{sleep, timeout, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+/// Replies with the same data
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Echo {
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(Echo)]
+struct Echoer;
+
+impl Message<Echo> for Echoer {
+    type Response = Echo;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &Echo) -> Result<(), TestError> {
+        ctx.reply(message.clone()).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Echoer {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+/// The files stored under a directory
+fn stored_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut files = vec![];
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(stored_files(&path));
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+#[test(tokio::test)]
+async fn test_claim_check() -> Result<(), TestError> {
+    let store_dir = std::env::temp_dir().join(format!("bioma-claim-check-{}", ulid::Ulid::new()));
+    let claim_check = ClaimCheckOptions::builder().threshold(1024).gc_grace(Duration::ZERO).build();
+    let options = EngineOptions::builder().local_store_dir(store_dir.clone()).claim_check(claim_check).build();
+    let engine = Engine::with_backend(MemoryBackend::default(), options)?;
+    let payloads = store_dir.join("claim-check");
+
+    let echoer = ActorId::of::<Echoer>("/echoer");
+    let (mut ctx, mut actor) = Actor::spawn(engine.clone(), echoer.clone(), Echoer, SpawnOptions::default()).await?;
+    let handle = tokio::spawn(async move {
+        let _ = actor.start(&mut ctx).await;
+    });
+    let (relay, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    sleep(Duration::from_millis(100)).await;
+
+    // Small payloads stay in the message
+    let small = Echo { data: vec![1; 16] };
+    let options = SendOptions::builder().timeout(Duration::from_secs(5)).build();
+    let reply = relay.send_and_wait_reply::<Echoer, Echo>(small.clone(), &echoer, options).await?;
+    assert_eq!(reply, small);
+    assert!(stored_files(&payloads).is_empty());
+
+    // Large payloads of the message and of the reply are stored, and loaded back
+    let large = Echo { data: (0..10_000).map(|i| (i % 251) as u8).collect() };
+    let options = SendOptions::builder().timeout(Duration::from_secs(5)).build();
+    let reply = relay.send_and_wait_reply::<Echoer, Echo>(large.clone(), &echoer, options).await?;
+    assert_eq!(reply, large);
+    assert_eq!(stored_files(&payloads).len(), 2);
+
+    // The payload of a message still waiting for its receiver is kept
+    relay.do_send::<Echoer, Echo>(large.clone(), &ActorId::of::<Echoer>("/absent")).await?;
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(stored_files(&payloads).len(), 3);
+
+    // The payloads of the replied message are removed with it
+    assert_eq!(engine.collect_claim_checks().await?, 2);
+    assert_eq!(stored_files(&payloads).len(), 1);
+
+    handle.abort();
+    let _ = std::fs::remove_dir_all(&store_dir);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_claim_check_dead_letter() -> Result<(), TestError> {
+    let store_dir = std::env::temp_dir().join(format!("bioma-claim-check-{}", ulid::Ulid::new()));
+    let claim_check = ClaimCheckOptions::builder().threshold(1024).gc_grace(Duration::ZERO).build();
+    let dead_letter = DeadLetterOptions::builder()
+        .unreplied_after(Duration::from_secs(1))
+        .scan_interval(Duration::from_millis(200))
+        .build();
+    let retention =
+        RetentionOptions::builder().message_ttl(Duration::ZERO).compaction_interval(Duration::from_secs(3600)).build();
+    let options = EngineOptions::builder()
+        .local_store_dir(store_dir.clone())
+        .claim_check(claim_check)
+        .dead_letter(dead_letter)
+        .retention(retention)
+        .build();
+    let engine = Engine::test_with(options).await?;
+    let payloads = store_dir.join("claim-check");
+
+    let (relay, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    // Nobody receives the large message, it is dead-lettered
+    let absent = ActorId::of::<Relay>("/absent");
+    let large = Echo { data: (0..10_000).map(|i| (i % 251) as u8).collect() };
+    relay.do_send_as(large.clone(), &absent).await?;
+    sleep(Duration::from_secs(2)).await;
+    let dead_letters = engine.dead_letters(Some(&absent)).await?;
+    assert_eq!(dead_letters.len(), 1);
+
+    // Compaction removes the dead-lettered message, but the dead letter keeps its payload
+    assert_eq!(engine.compact().await?, 1);
+    assert_eq!(engine.collect_claim_checks().await?, 0);
+    assert_eq!(stored_files(&payloads).len(), 1);
+
+    // The requeued message gets the payload back, stored again under its own id
+    let (absent_ctx, _) = Actor::spawn(engine.clone(), absent.clone(), Relay, SpawnOptions::default()).await?;
+    let mut stream = absent_ctx.recv().await?;
+    let requeued = engine.requeue_dead_letter(&dead_letters[0].id).await?;
+    assert!(requeued.is_some());
+    let frame = timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("requeued message not received")
+        .expect("message stream ended")?;
+    assert_eq!(frame.is::<Echo>(), Some(large));
+
+    // Only the payload of the dead letter is removed
+    assert_eq!(stored_files(&payloads).len(), 2);
+    assert_eq!(engine.collect_claim_checks().await?, 1);
+    assert_eq!(stored_files(&payloads).len(), 1);
+
+    let _ = std::fs::remove_dir_all(&store_dir);
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use bioma_actor::prelude::*;
+use std::path::PathBuf;
+use std::time::Duration;
+use test_log::test;
+
+/// A file in a directory of its own, removed after the test
+fn config_file(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bioma-config-{}", ulid::Ulid::new()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_load_layers() {
+    let file = config_file(
+        "bioma.toml",
+        r#"
+            endpoint = "ws://localhost:9123"
+            namespace = "file"
+            password = "from-file"
+
+            [retention]
+            message_ttl = "1d"
+
+            [object_store]
+            type = "memory"
+        "#,
+    );
+
+    // Variables of a prefix unique to this test, so tests running in parallel do not see them
+    std::env::set_var("BIOMA_LAYERS_NAMESPACE", "env");
+    std::env::set_var("BIOMA_LAYERS_DATABASE", "env");
+    std::env::set_var("BIOMA_LAYERS_CLAIM_CHECK__THRESHOLD", "65536");
+    std::env::set_var("BIOMA_LAYERS_UNRELATED", "ignored");
+
+    let sources = ConfigSources::builder()
+        .file(file.clone())
+        .env_prefix("BIOMA_LAYERS")
+        .overrides(vec!["database=cli".into(), "retention.message_ttl=7d".into(), "lease.ttl=30s".into()])
+        .build();
+    let options = EngineOptions::load(&sources).unwrap();
+
+    assert_eq!(options.endpoint, "ws://localhost:9123");
+    assert_eq!(options.password, "from-file");
+    assert_eq!(options.namespace, "env");
+    assert_eq!(options.database, "cli");
+    assert_eq!(options.claim_check.threshold, Some(65536));
+    assert_eq!(options.retention.message_ttl, Some(Duration::from_secs(7 * 24 * 60 * 60)));
+    assert_eq!(options.lease.ttl, Duration::from_secs(30));
+    assert_eq!(options.object_store, ObjectStoreOptions::Memory);
+    // Untouched options keep their defaults
+    assert_eq!(options.username, "root");
+    assert_eq!(options.claim_check.prefix, ClaimCheckOptions::default().prefix);
+
+    let _ = std::fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+fn test_load_json_and_config_variable() {
+    let file = config_file("bioma.json", r#"{ "endpoint": "surrealkv://bioma.db", "username": "admin" }"#);
+    std::env::set_var("BIOMA_JSON_CONFIG", &file);
+
+    let options = EngineOptions::load(&ConfigSources::builder().env_prefix("BIOMA_JSON").build()).unwrap();
+    assert_eq!(options.endpoint, "surrealkv://bioma.db");
+    assert_eq!(options.username, "admin");
+
+    let _ = std::fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+fn test_load_errors() {
+    let load = |overrides: &[&str]| {
+        let overrides = overrides.iter().map(|o| o.to_string()).collect();
+        EngineOptions::load(&ConfigSources::builder().env_prefix("BIOMA_ERRORS").overrides(overrides).build())
+    };
+    let is_invalid =
+        |result: Result<EngineOptions, SystemActorError>| matches!(result, Err(SystemActorError::InvalidConfig(_)));
+
+    assert!(load(&["endpoint=ws://localhost:9123"]).is_ok());
+    assert!(is_invalid(load(&["endpoint"])));
+    assert!(is_invalid(load(&["unknown=1"])));
+    assert!(is_invalid(load(&["lease.ttl=soon"])));
+    assert!(is_invalid(load(&["endpoint=localhost:9123"])));
+    assert!(is_invalid(load(&["endpoint=ftp://localhost"])));
+    assert!(is_invalid(load(&["endpoint=ws://"])));
+    assert!(is_invalid(load(&["namespace="])));
+    assert!(is_invalid(load(&[r#"object_store={"type":"s3","bucket":"bioma","endpoint":"not a url"}"#])));
+
+    // A directory under a file cannot be created
+    let file = config_file("not-a-dir", "");
+    let output_dir = format!("output_dir={}", file.join("output").display());
+    assert!(is_invalid(load(&[output_dir.as_str()])));
+    let _ = std::fs::remove_dir_all(file.parent().unwrap());
+
+    let file = config_file("bioma.yaml", "endpoint: memory");
+    let sources = ConfigSources::builder().file(file.clone()).env_prefix("BIOMA_ERRORS").build();
+    assert!(is_invalid(EngineOptions::load(&sources)));
+    let _ = std::fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+fn test_load_file_keys() {
+    let file = config_file("bioma.toml", r#"engine_endpoint = "ws://localhost:9123""#);
+    let sources = ConfigSources::builder().file(file.clone()).env_prefix("BIOMA_FILE_KEYS").build();
+    assert!(matches!(EngineOptions::load(&sources), Err(SystemActorError::InvalidConfig(_))));
+
+    let sources = ConfigSources::builder()
+        .file(file.clone())
+        .env_prefix("BIOMA_FILE_KEYS")
+        .aliases(vec![("engine_endpoint".into(), "endpoint".into())])
+        .build();
+    assert_eq!(EngineOptions::load(&sources).unwrap().endpoint, "ws://localhost:9123");
+
+    let _ = std::fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+fn test_password_redacted() {
+    let options = EngineOptions::builder().password("hunter2".into()).build();
+    let debug = format!("{:?}", options);
+    assert!(!debug.contains("hunter2"));
+    assert!(debug.contains("password: \"***\""));
+}
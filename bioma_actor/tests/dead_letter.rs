@@ -0,0 +1,132 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use surrealdb::RecordId;
+use test_log::test;
+use tokio::time::{sleep, timeout, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+    #[error("Fake error")]
+    FakeError,
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TriggerError;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorActor;
+
+impl Message<TriggerError> for ErrorActor {
+    type Response = ();
+
+    async fn handle(&mut self, _ctx: &mut ActorContext<Self>, _: &TriggerError) -> Result<(), TestError> {
+        Err(TestError::FakeError)
+    }
+}
+
+impl Actor for ErrorActor {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        let mut stream = ctx.recv().await?;
+        while let Some(Ok(frame)) = stream.next().await {
+            if let Some(trigger) = frame.is::<TriggerError>() {
+                let _ = self.reply(ctx, &trigger, &frame).await;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test(tokio::test)]
+async fn test_dead_letter_handler_error() -> Result<(), TestError> {
+    let dead_letter = DeadLetterOptions::builder().on_handler_error(true).build();
+    let engine = Engine::test_with(EngineOptions::builder().dead_letter(dead_letter).build()).await?;
+
+    let error_actor_id = ActorId::of::<ErrorActor>("/error_actor");
+    let (mut error_ctx, mut error_actor) =
+        Actor::spawn(engine.clone(), error_actor_id.clone(), ErrorActor, SpawnOptions::default()).await?;
+    let error_handle = tokio::spawn(async move {
+        let _ = error_ctx.run(&mut error_actor).await;
+    });
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    let _ = relay_ctx
+        .send_and_collect::<ErrorActor, TriggerError>(TriggerError, &error_actor_id, SendOptions::default())
+        .await;
+
+    let dead_letters = engine.dead_letters(Some(&error_actor_id)).await?;
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(dead_letters[0].name, std::any::type_name::<TriggerError>());
+    assert_eq!(dead_letters[0].reason, DeadLetterReason::HandlerError("Fake error".to_string()));
+
+    let inspected = engine.get_dead_letter(&dead_letters[0].id).await?;
+    assert!(inspected.is_some());
+
+    assert_eq!(engine.purge_dead_letters(Some(&error_actor_id)).await?, 1);
+    assert!(engine.dead_letters(None).await?.is_empty());
+
+    error_handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_dead_letter_unreplied_and_requeue() -> Result<(), TestError> {
+    let dead_letter = DeadLetterOptions::builder()
+        .unreplied_after(Duration::from_secs(1))
+        .scan_interval(Duration::from_millis(200))
+        .build();
+    let engine = Engine::test_with(EngineOptions::builder().dead_letter(dead_letter).build()).await?;
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    // Nobody is listening on this actor, so the message is never replied
+    let missing_id = ActorId::of::<Relay>("/missing");
+    relay_ctx.do_send_as(TriggerError, &missing_id).await?;
+    sleep(Duration::from_secs(2)).await;
+
+    let dead_letters = engine.dead_letters(Some(&missing_id)).await?;
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(dead_letters[0].reason, DeadLetterReason::Unreplied);
+
+    // Requeue once the receiver exists, it gets the message again
+    let (missing_ctx, _) = Actor::spawn(engine.clone(), missing_id.clone(), Relay, SpawnOptions::default()).await?;
+    let mut stream = missing_ctx.recv().await?;
+    let requeued = engine.requeue_dead_letter(&dead_letters[0].id).await?;
+    assert!(requeued.is_some());
+    assert!(engine.dead_letters(Some(&missing_id)).await?.is_empty());
+    // The dead letter is gone with the requeue, it is sent only once
+    assert!(engine.requeue_dead_letter(&dead_letters[0].id).await?.is_none());
+
+    let frame = timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("requeued message not received")
+        .expect("message stream ended")?;
+    assert_eq!(Some(frame.id().clone()), requeued);
+    assert!(frame.is::<TriggerError>().is_some());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_dead_letter_unsupported_backend() -> Result<(), TestError> {
+    let options =
+        EngineOptions::builder().dead_letter(DeadLetterOptions::builder().on_handler_error(true).build()).build();
+    let engine = Engine::with_backend(MemoryBackend::default(), options)?;
+
+    // Dead letters are kept in SurrealDB, other backends fail instead of dropping them
+    let unsupported = |result: Result<(), SystemActorError>| matches!(result, Err(SystemActorError::Unsupported(_)));
+    let id = RecordId::from_table_key("dead_letter", "missing");
+    assert!(unsupported(engine.dead_letters(None).await.map(|_| ())));
+    assert!(unsupported(engine.get_dead_letter(&id).await.map(|_| ())));
+    assert!(unsupported(engine.requeue_dead_letter(&id).await.map(|_| ())));
+    assert!(unsupported(engine.purge_dead_letters(None).await.map(|_| ())));
+
+    Ok(())
+}
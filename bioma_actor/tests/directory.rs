@@ -0,0 +1,73 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::{sleep, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Indexer;
+
+impl Actor for Indexer {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        let mut stream = ctx.recv().await?;
+        while stream.next().await.is_some() {}
+        Ok(())
+    }
+}
+
+fn names(records: &[ActorRecord]) -> Vec<&str> {
+    records.iter().map(|record| record.name()).collect()
+}
+
+#[test(tokio::test)]
+async fn test_directory_queries() -> Result<(), TestError> {
+    let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+    let mut handles = vec![];
+    for name in ["/rag/indexer/1", "/rag/indexer/0", "/rag/other"] {
+        let (mut ctx, mut actor) =
+            Actor::spawn(engine.clone(), ActorId::of::<Indexer>(name), Indexer, SpawnOptions::default()).await?;
+        handles.push(tokio::spawn(async move { ctx.run(&mut actor).await }));
+    }
+    sleep(Duration::from_millis(100)).await;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/rag/relay"), Relay, SpawnOptions::default()).await?;
+
+    // By name prefix, ordered by name
+    let query = ActorQuery::builder().prefix("/rag/indexer/".into()).build();
+    let records = engine.actors(&query).await?;
+    assert_eq!(names(&records), vec!["/rag/indexer/0", "/rag/indexer/1"]);
+    assert_eq!(records[0].actor_id(), ActorId::of::<Indexer>("/rag/indexer/0"));
+
+    // By tag
+    let records = engine.actors(&ActorQuery::of::<Indexer>()).await?;
+    assert_eq!(names(&records), vec!["/rag/indexer/0", "/rag/indexer/1", "/rag/other"]);
+    let records = engine.actors(&ActorQuery::of::<Relay>()).await?;
+    assert_eq!(names(&records), vec!["/rag/relay"]);
+    assert_eq!(records[0].status(), ActorStatus::Running);
+
+    // By status, stopped actors keep their record
+    handles.remove(0).abort();
+    sleep(Duration::from_millis(100)).await;
+    let query = ActorQuery::builder().tag(std::any::type_name::<Indexer>().into()).status(ActorStatus::Running).build();
+    assert_eq!(names(&engine.actors(&query).await?), vec!["/rag/indexer/0", "/rag/other"]);
+    let query = ActorQuery::builder().status(ActorStatus::Stopped).build();
+    let records = engine.actors(&query).await?;
+    assert_eq!(names(&records), vec!["/rag/indexer/1"]);
+    assert!(records[0].exit().is_some());
+
+    // All actors
+    assert_eq!(engine.actors(&ActorQuery::default()).await?.len(), 4);
+
+    drop(relay_ctx);
+    handles.iter().for_each(|handle| handle.abort());
+    Ok(())
+}
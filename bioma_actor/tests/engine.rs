@@ -23,7 +23,7 @@ pub struct DummyMessage {
 async fn test_engine_db_write() -> Result<(), SystemActorError> {
     let engine = Engine::test().await?;
 
-    let db = engine.db();
+    let db = engine.db()?;
 
     let msg = DummyMessage {
         id: RecordId::from_table_key("test_engine_db_write", "0000001"),
@@ -33,7 +33,7 @@ async fn test_engine_db_write() -> Result<(), SystemActorError> {
         msg: Value::Null,
     };
 
-    let record: Option<Record> = db.lock().await.create("test_engine_db_write").content(msg).await?;
+    let record: Option<Record> = db.create("test_engine_db_write").content(msg).await?;
 
     assert_eq!(record.unwrap().id, RecordId::from_table_key("test_engine_db_write", "0000001"));
 
@@ -41,3 +41,19 @@ async fn test_engine_db_write() -> Result<(), SystemActorError> {
 
     Ok(())
 }
+
+#[test(tokio::test)]
+async fn test_engine_connect_max_startup_wait() -> Result<(), SystemActorError> {
+    // Nothing listens on this port
+    let reconnect = ReconnectOptions::builder()
+        .initial_delay(std::time::Duration::from_millis(100))
+        .max_startup_wait(std::time::Duration::from_secs(1))
+        .build();
+    let options = EngineOptions::builder().endpoint("ws://127.0.0.1:1".into()).reconnect(reconnect).build();
+
+    let started = std::time::Instant::now();
+    let result = Engine::connect(options).await;
+    assert!(matches!(result, Err(SystemActorError::ConnectTimeout(..))));
+    assert!(started.elapsed() < std::time::Duration::from_secs(3));
+    Ok(())
+}
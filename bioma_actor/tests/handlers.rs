@@ -0,0 +1,164 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::{sleep, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+    #[error("Negative value")]
+    Negative,
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Add(i64);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Get;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Unknown;
+
+// Sent with the name of `Add`, but content that `Add` can't be decoded from
+#[derive(Clone, Debug, Serialize, Deserialize, MessageName)]
+#[message(name = "handlers::Add")]
+struct AddText(String);
+
+#[derive(Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(Add, Get)]
+struct Counter {
+    count: i64,
+}
+
+impl Message<Add> for Counter {
+    type Response = i64;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &Add) -> Result<(), TestError> {
+        if message.0 < 0 {
+            return Err(TestError::Negative);
+        }
+        self.count += message.0;
+        ctx.reply(self.count).await?;
+        Ok(())
+    }
+}
+
+impl Message<Get> for Counter {
+    type Response = i64;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _: &Get) -> Result<(), TestError> {
+        ctx.reply(self.count).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Counter {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+#[test(tokio::test)]
+async fn test_serve_dispatch() -> Result<(), TestError> {
+    let dead_letter = DeadLetterOptions::builder().on_unhandled(true).build();
+    let engine = Engine::test_with(EngineOptions::builder().dead_letter(dead_letter).build()).await?;
+
+    let counter_id = ActorId::of::<Counter>("/counter");
+    let (mut counter_ctx, mut counter) =
+        Actor::spawn(engine.clone(), counter_id.clone(), Counter { count: 0 }, SpawnOptions::default()).await?;
+    let counter_handle = tokio::spawn(async move { counter_ctx.run(&mut counter).await });
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    let count = relay_ctx.send_and_wait_reply::<Counter, Add>(Add(2), &counter_id, SendOptions::default()).await?;
+    assert_eq!(count, 2);
+
+    // Handler errors are returned to the sender and the actor keeps serving
+    let result = relay_ctx.send_and_wait_reply::<Counter, Add>(Add(-1), &counter_id, SendOptions::default()).await;
+    match result {
+        Err(SystemActorError::MessageReply(err)) => assert_eq!(err, "Negative value"),
+        other => panic!("expected the handler error, got {:?}", other),
+    }
+    let count = relay_ctx.send_and_wait_reply::<Counter, Get>(Get, &counter_id, SendOptions::default()).await?;
+    assert_eq!(count, 2);
+
+    // Unknown messages are dead-lettered
+    relay_ctx.do_send_as(Unknown, &counter_id).await?;
+    sleep(Duration::from_millis(500)).await;
+    let dead_letters = engine.dead_letters(Some(&counter_id)).await?;
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(dead_letters[0].name, std::any::type_name::<Unknown>());
+    assert_eq!(dead_letters[0].reason, DeadLetterReason::Unhandled);
+
+    assert!(!counter_handle.is_finished());
+    counter_handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_serve_rejects_unhandled_and_undecodable() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let counter_id = ActorId::of::<Counter>("/counter");
+    let (mut counter_ctx, mut counter) =
+        Actor::spawn(engine.clone(), counter_id.clone(), Counter { count: 0 }, SpawnOptions::default()).await?;
+    let counter_handle = tokio::spawn(async move { counter_ctx.run(&mut counter).await });
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    // The sender gets an error with the final reply, instead of waiting for it
+    let options = SendOptions::builder().timeout(Duration::from_secs(5)).build();
+    let result = relay_ctx.send_as_and_wait_reply::<Unknown, i64>(Unknown, counter_id.clone(), options).await;
+    assert!(matches!(result, Err(SystemActorError::MessageReply(err)) if err.starts_with("Unhandled message")));
+
+    let options = SendOptions::builder().timeout(Duration::from_secs(5)).build();
+    let result =
+        relay_ctx.send_as_and_wait_reply::<AddText, i64>(AddText("two".into()), counter_id.clone(), options).await;
+    assert!(matches!(result, Err(SystemActorError::MessageReply(_))));
+
+    // Both are replied, so they are not delivered again
+    assert_eq!(engine.backend().unreplied_count(&counter_id.record_id()).await?, 0);
+    let count = relay_ctx.send_and_wait_reply::<Counter, Add>(Add(1), &counter_id, SendOptions::default()).await?;
+    assert_eq!(count, 1);
+
+    counter_handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_serve_skips_undecodable_message() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let counter_id = ActorId::of::<Counter>("/counter");
+    let (mut counter_ctx, mut counter) =
+        Actor::spawn(engine.clone(), counter_id.clone(), Counter { count: 0 }, SpawnOptions::default()).await?;
+    let counter_handle = tokio::spawn(async move { counter_ctx.run(&mut counter).await });
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    sleep(Duration::from_millis(200)).await;
+
+    // A message written by another client with a name that is not a string
+    engine
+        .db()?
+        .query("CREATE message CONTENT { name: 1, tx: $tx, rx: $rx, msg: {} }")
+        .bind(("tx", relay_ctx.id().record_id()))
+        .bind(("rx", counter_id.record_id()))
+        .await
+        .map_err(SystemActorError::from)?;
+
+    // The message is skipped and the live query is kept open
+    let count = relay_ctx.send_and_wait_reply::<Counter, Add>(Add(3), &counter_id, SendOptions::default()).await?;
+    assert_eq!(count, 3);
+    assert!(!engine.backend().live_restarts().contains_key(&counter_id.record_id().to_string()));
+
+    counter_handle.abort();
+    Ok(())
+}
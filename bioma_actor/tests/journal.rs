@@ -0,0 +1,94 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum AccountEvent {
+    Deposited(i64),
+    Withdrawn(i64),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Account {
+    balance: i64,
+}
+
+impl EventSourced for Account {
+    type Event = AccountEvent;
+    const SNAPSHOT_EVERY: u64 = 3;
+
+    fn apply(&mut self, event: &AccountEvent) {
+        match event {
+            AccountEvent::Deposited(amount) => self.balance += amount,
+            AccountEvent::Withdrawn(amount) => self.balance -= amount,
+        }
+    }
+}
+
+impl Actor for Account {
+    type Error = TestError;
+
+    async fn start(&mut self, _ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        Ok(())
+    }
+}
+
+async fn snapshot_count(engine: &Engine) -> Result<usize, SystemActorError> {
+    let mut res = engine.db()?.query("SELECT id FROM snapshot").await?;
+    let records: Vec<Record> = res.take(0)?;
+    Ok(records.len())
+}
+
+#[test(tokio::test)]
+async fn test_journal_recover() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    let account_id = ActorId::of::<Account>("/account");
+
+    let (mut ctx, mut account) =
+        Actor::spawn(engine.clone(), account_id.clone(), Account::default(), SpawnOptions::default()).await?;
+    ctx.recover(&mut account).await?;
+    for event in [
+        AccountEvent::Deposited(100),
+        AccountEvent::Withdrawn(30),
+        AccountEvent::Deposited(5),
+        AccountEvent::Withdrawn(25),
+    ] {
+        ctx.persist(&mut account, event).await?;
+    }
+    assert_eq!(account.balance, 50);
+    // A snapshot was taken after the third event
+    assert_eq!(snapshot_count(&engine).await?, 1);
+
+    // The journal keeps every event as an audit trail
+    let journal: Vec<JournalEntry<AccountEvent>> = engine.journal(&account_id, 0).await?;
+    assert_eq!(journal.iter().map(|entry| entry.seq).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    assert_eq!(journal[3].event, AccountEvent::Withdrawn(25));
+
+    // A new instance rebuilds the state from the snapshot and the remaining event
+    let options = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).build();
+    let (mut ctx, mut account) = Actor::spawn(engine.clone(), account_id.clone(), Account::default(), options).await?;
+    assert_eq!(ctx.recover(&mut account).await?, 4);
+    assert_eq!(account.balance, 50);
+    assert_eq!(ctx.persist(&mut account, AccountEvent::Deposited(1)).await?, 5);
+
+    // Point-in-time recovery
+    let (account, seq) = engine.replay(&account_id, Account::default(), Some(2)).await?;
+    assert_eq!((account.balance, seq), (70, 2));
+
+    // Resetting the actor drops its journal
+    let options = SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build();
+    let (mut ctx, mut account) = Actor::spawn(engine.clone(), account_id.clone(), Account::default(), options).await?;
+    assert_eq!(ctx.recover(&mut account).await?, 0);
+    assert_eq!(account.balance, 0);
+    assert_eq!(snapshot_count(&engine).await?, 0);
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use test_log::test;
+use tokio::time::{sleep, timeout};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Ping;
+
+#[derive(Debug, Default, Serialize, Deserialize, ActorHandlers)]
+#[handles(Ping)]
+struct Worker;
+
+impl Message<Ping> for Worker {
+    type Response = String;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _message: &Ping) -> Result<(), TestError> {
+        ctx.reply(ctx.engine().node_id().to_string()).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Worker {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+async fn engine(backend: &MemoryBackend, node_id: &'static str) -> Result<Engine, SystemActorError> {
+    let lease = LeaseOptions::builder()
+        .node_id(node_id.into())
+        .ttl(Duration::from_millis(600))
+        .heartbeat_interval(Duration::from_millis(200))
+        .build();
+    Engine::with_backend(backend.clone(), EngineOptions::builder().lease(lease).build())
+}
+
+#[test(tokio::test)]
+async fn test_lease_failover() -> Result<(), TestError> {
+    // Two engines sharing the same storage
+    let backend = MemoryBackend::default();
+    let engine_a = engine(&backend, "node-a").await?;
+    let engine_b = engine(&backend, "node-b").await?;
+    let worker_id = ActorId::of::<Worker>("/worker");
+    let restore = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).build();
+
+    let (mut ctx_a, mut worker_a) = Actor::spawn(engine_a.clone(), worker_id.clone(), Worker, restore.clone()).await?;
+    let handle_a = tokio::spawn(async move {
+        let _ = ctx_a.run(&mut worker_a).await;
+    });
+    let record = backend.get_actor(&worker_id.record_id()).await?.unwrap();
+    assert_eq!(record.lease().unwrap().owner, "node-a");
+
+    // The second engine waits while the first one holds the lease, renewed by heartbeats
+    let spawn_b = tokio::spawn({
+        let worker_id = worker_id.clone();
+        async move { Actor::spawn(engine_b, worker_id, Worker, restore).await }
+    });
+    sleep(Duration::from_secs(1)).await;
+    assert!(!spawn_b.is_finished());
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine_a.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    let node = relay_ctx.send_and_wait_reply::<Worker, Ping>(Ping, &worker_id, SendOptions::default()).await?;
+    assert_eq!(node, "node-a");
+
+    // Once the first engine stops the actor, the second one takes over
+    handle_a.abort();
+    let (mut ctx_b, mut worker_b) = timeout(Duration::from_secs(2), spawn_b).await.unwrap().unwrap()?;
+    let record = backend.get_actor(&worker_id.record_id()).await?.unwrap();
+    assert_eq!(record.lease().unwrap().owner, "node-b");
+
+    let handle_b = tokio::spawn(async move {
+        let _ = ctx_b.run(&mut worker_b).await;
+    });
+    sleep(Duration::from_millis(100)).await;
+    let node = relay_ctx.send_and_wait_reply::<Worker, Ping>(Ping, &worker_id, SendOptions::default()).await?;
+    assert_eq!(node, "node-b");
+
+    handle_b.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_lease_lost_ends_recv() -> Result<(), TestError> {
+    let backend = MemoryBackend::default();
+    let engine_a = engine(&backend, "node-a").await?;
+    let worker_id = ActorId::of::<Worker>("/worker");
+
+    let (ctx, _) = Actor::spawn(engine_a.clone(), worker_id.clone(), Worker, SpawnOptions::default()).await?;
+    let mut stream = ctx.recv().await?;
+
+    // Another engine takes the actor over once the lease can't be renewed
+    backend.delete_actor(&worker_id.record_id()).await?;
+    let next = timeout(Duration::from_secs(2), stream.next()).await.unwrap();
+    assert!(next.is_none());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_lease_timeout() -> Result<(), TestError> {
+    let backend = MemoryBackend::default();
+    let engine_a = engine(&backend, "node-a").await?;
+    let engine_b = engine(&backend, "node-b").await?;
+    let worker_id = ActorId::of::<Worker>("/worker");
+
+    let (_ctx_a, _) = Actor::spawn(engine_a.clone(), worker_id.clone(), Worker, SpawnOptions::default()).await?;
+
+    // A standby gives up instead of waiting for the lease forever
+    let options = SpawnOptions::builder()
+        .exists(SpawnExistsOptions::Restore)
+        .lease_timeout(Duration::from_millis(300))
+        .build();
+    let result = timeout(Duration::from_secs(2), Actor::spawn(engine_b, worker_id.clone(), Worker, options)).await;
+    match result.unwrap() {
+        Err(TestError::System(SystemActorError::LeaseTimeout(id, _))) => assert_eq!(id, worker_id),
+        Err(e) => panic!("expected a lease timeout, got {}", e),
+        Ok(_) => panic!("expected a lease timeout, the lease was acquired"),
+    }
+    Ok(())
+}
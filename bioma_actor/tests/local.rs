@@ -0,0 +1,112 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::{sleep, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+/// Replies with the numbers from 1 to `n`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Count(u64);
+
+#[derive(Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(Count)]
+struct Counter;
+
+impl Message<Count> for Counter {
+    type Response = u64;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &Count) -> Result<(), TestError> {
+        for i in 1..=message.0 {
+            ctx.reply(i).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Actor for Counter {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+async fn count_records(engine: &Engine, table: &str) -> Result<usize, SystemActorError> {
+    let mut res = engine.db()?.query(format!("SELECT id FROM {}", table)).await?;
+    let records: Vec<Record> = res.take(0)?;
+    Ok(records.len())
+}
+
+async fn spawn_counter(engine: &Engine) -> Result<(ActorId, tokio::task::JoinHandle<()>), TestError> {
+    let counter_id = ActorId::of::<Counter>("/counter");
+    let (mut counter_ctx, mut counter) =
+        Actor::spawn(engine.clone(), counter_id.clone(), Counter, SpawnOptions::default()).await?;
+    let handle = tokio::spawn(async move {
+        let _ = counter_ctx.run(&mut counter).await;
+    });
+    // Give the counter time to start receiving
+    sleep(Duration::from_millis(100)).await;
+    Ok((counter_id, handle))
+}
+
+#[test(tokio::test)]
+async fn test_local_write_behind() -> Result<(), TestError> {
+    let options = EngineOptions::builder().local_delivery(LocalDelivery::WriteBehind).build();
+    let engine = Engine::test_with(options).await?;
+    let (counter_id, counter_handle) = spawn_counter(&engine).await?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    // Streamed replies arrive in order, as with database delivery
+    let replies = relay_ctx.send_and_collect::<Counter, Count>(Count(3), &counter_id, SendOptions::default()).await?;
+    assert_eq!(replies, vec![1, 2, 3]);
+    let replies = relay_ctx.send_and_collect::<Counter, Count>(Count(0), &counter_id, SendOptions::default()).await?;
+    assert!(replies.is_empty());
+
+    // Messages and replies are stored behind delivery
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(count_records(&engine, "message").await?, 2);
+    assert_eq!(count_records(&engine, "reply").await?, 5);
+    let mut res = engine
+        .db()?
+        .query("SELECT id FROM message WHERE ->message_replies[0].out = NONE")
+        .await
+        .map_err(SystemActorError::from)?;
+    let unreplied: Vec<Record> = res.take(0).map_err(SystemActorError::from)?;
+    assert!(unreplied.is_empty());
+
+    counter_handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_local_memory_only() -> Result<(), TestError> {
+    let options = EngineOptions::builder().local_delivery(LocalDelivery::MemoryOnly).build();
+    let engine = Engine::test_with(options).await?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    // Not receiving yet, the message goes through the database and is picked up later
+    let counter_id = ActorId::of::<Counter>("/counter");
+    relay_ctx.do_send::<Counter, Count>(Count(1), &counter_id).await?;
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(count_records(&engine, "message").await?, 1);
+
+    let (counter_id, counter_handle) = spawn_counter(&engine).await?;
+    let replies = relay_ctx.send_and_collect::<Counter, Count>(Count(2), &counter_id, SendOptions::default()).await?;
+    assert_eq!(replies, vec![1, 2]);
+
+    // Only the first message was stored
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(count_records(&engine, "message").await?, 1);
+
+    counter_handle.abort();
+    Ok(())
+}
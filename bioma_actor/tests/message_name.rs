@@ -0,0 +1,92 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use test_log::test;
+use tokio::time::{timeout, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+// Versions 0 and 1 had a single `text` field, version 2 splits it into `greeting` and `name`
+#[derive(MessageName, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[message(name = "test.Greet", alias = "old_module::Greet", version = 2, upgrade = upgrade_greet)]
+struct Greet {
+    greeting: String,
+    name: String,
+}
+
+fn upgrade_greet(version: u32, msg: Value) -> Result<Value, SystemActorError> {
+    match version {
+        0 | 1 => {
+            let text = msg["text"].as_str().unwrap_or_default();
+            let (greeting, name) = text.split_once(' ').unwrap_or((text, ""));
+            Ok(json!({ "greeting": greeting, "name": name }))
+        }
+        _ => Ok(msg),
+    }
+}
+
+async fn next_frame(stream: &mut MessageStream) -> Result<FrameMessage, TestError> {
+    let frame = timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("message not received")
+        .expect("message stream ended")?;
+    Ok(frame)
+}
+
+#[test(tokio::test)]
+async fn test_message_name() -> Result<(), TestError> {
+    // The derive registers the name, no `register_message` needed
+    let engine = Engine::test().await?;
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    let receiver_id = ActorId::of::<Relay>("/receiver");
+    let (receiver_ctx, _) = Actor::spawn(engine.clone(), receiver_id.clone(), Relay, SpawnOptions::default()).await?;
+    let mut stream = receiver_ctx.recv().await?;
+
+    // Frames are sent with the stable name and current version
+    let greet = Greet { greeting: "Hello".to_string(), name: "world".to_string() };
+    relay_ctx.do_send_as(greet.clone(), &receiver_id).await?;
+    let frame = next_frame(&mut stream).await?;
+    assert_eq!(frame.name, "test.Greet");
+    assert_eq!(frame.version, Some(2));
+    assert_eq!(frame.is::<Greet>(), Some(greet.clone()));
+
+    // Frames written under an old name and schema, e.g. by a non-Rust client, are upgraded
+    engine
+        .db()?
+        .query("CREATE message CONTENT { name: 'old_module::Greet', version: 1, tx: $tx, rx: $rx, msg: $msg }")
+        .bind(("tx", relay_ctx.id().record_id()))
+        .bind(("rx", receiver_id.record_id()))
+        .bind(("msg", json!({ "text": "Hello world" })))
+        .await
+        .map_err(SystemActorError::from)?
+        .check()
+        .map_err(SystemActorError::from)?;
+    let frame = next_frame(&mut stream).await?;
+    assert_eq!(frame.name, "old_module::Greet");
+    assert_eq!(frame.is::<Greet>(), Some(greet.clone()));
+
+    // Frames without a version were written before versioning, and are upgraded from version 0
+    engine
+        .db()?
+        .query("CREATE message CONTENT { name: 'test.Greet', tx: $tx, rx: $rx, msg: $msg }")
+        .bind(("tx", relay_ctx.id().record_id()))
+        .bind(("rx", receiver_id.record_id()))
+        .bind(("msg", json!({ "text": "Hello world" })))
+        .await
+        .map_err(SystemActorError::from)?
+        .check()
+        .map_err(SystemActorError::from)?;
+    let frame = next_frame(&mut stream).await?;
+    assert_eq!(frame.version, None);
+    assert_eq!(frame.is::<Greet>(), Some(greet));
+
+    Ok(())
+}
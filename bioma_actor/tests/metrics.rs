@@ -0,0 +1,124 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::{sleep, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+    #[error("Failed on purpose")]
+    Failed,
+}
+
+impl ActorError for TestError {}
+
+/// Replies with a chunk per number
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Count(usize);
+
+/// Always fails
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Fail;
+
+#[derive(Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(Count, Fail)]
+struct Counter;
+
+impl Message<Count> for Counter {
+    type Response = usize;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &Count) -> Result<(), TestError> {
+        for i in 0..message.0 {
+            ctx.reply(i).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Message<Fail> for Counter {
+    type Response = ();
+
+    async fn handle(&mut self, _ctx: &mut ActorContext<Self>, _message: &Fail) -> Result<(), TestError> {
+        Err(TestError::Failed)
+    }
+}
+
+impl Actor for Counter {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+#[test(tokio::test)]
+async fn test_actor_metrics() -> Result<(), TestError> {
+    let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+
+    let counter = ActorId::of::<Counter>("/counter");
+    let (mut ctx, mut actor) = Actor::spawn(engine.clone(), counter.clone(), Counter, SpawnOptions::default()).await?;
+    let handle = tokio::spawn(async move {
+        let _ = actor.start(&mut ctx).await;
+    });
+    let (relay, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    sleep(Duration::from_millis(100)).await;
+
+    let options = || SendOptions::builder().timeout(Duration::from_secs(2)).build();
+    for _ in 0..2 {
+        let chunks = relay.send_and_collect::<Counter, Count>(Count(3), &counter, options()).await?;
+        assert_eq!(chunks, vec![0, 1, 2]);
+    }
+    let result = relay.send_and_wait_reply::<Counter, Fail>(Fail, &counter, options()).await;
+    assert!(result.is_err());
+
+    let metrics = engine.metrics().actor(&counter).await.unwrap();
+    assert_eq!(metrics.backlog, 0);
+    assert_eq!(metrics.stream_restarts, 0);
+    let count = metrics.messages.iter().find(|message| message.name.ends_with("Count")).unwrap();
+    assert_eq!(count.received, 2);
+    assert_eq!(count.reply_chunks, 6);
+    assert_eq!(count.errors, 0);
+    assert_eq!(count.latency.count, 2);
+    let fail = metrics.messages.iter().find(|message| message.name.ends_with("Fail")).unwrap();
+    assert_eq!(fail.received, 1);
+    assert_eq!(fail.errors, 1);
+
+    let text = engine.metrics().prometheus().await;
+    let line = |metric: &str, message: &str| {
+        text.lines()
+            .find(|line| line.starts_with(metric) && line.contains(&format!("message=\"{}\"", message)))
+            .map(|line| line.rsplit(' ').next().unwrap().to_string())
+    };
+    assert_eq!(line("bioma_actor_messages_received_total{", &count.name).as_deref(), Some("2"));
+    assert_eq!(line("bioma_actor_reply_chunks_total{", &count.name).as_deref(), Some("6"));
+    assert_eq!(line("bioma_actor_handler_errors_total{", &fail.name).as_deref(), Some("1"));
+    assert_eq!(line("bioma_actor_handler_duration_seconds_count{", &count.name).as_deref(), Some("2"));
+    assert!(text.contains("# TYPE bioma_actor_handler_duration_seconds histogram"));
+    assert!(text.contains("bioma_actor_mailbox_backlog{actor=\"/counter\""));
+
+    handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_metrics_disabled() -> Result<(), TestError> {
+    let options = EngineOptions::builder().metrics(MetricsOptions::builder().enabled(false).build()).build();
+    let engine = Engine::with_backend(MemoryBackend::default(), options)?;
+
+    let counter = ActorId::of::<Counter>("/counter");
+    let (mut ctx, mut actor) = Actor::spawn(engine.clone(), counter.clone(), Counter, SpawnOptions::default()).await?;
+    let handle = tokio::spawn(async move {
+        let _ = actor.start(&mut ctx).await;
+    });
+    let (relay, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    sleep(Duration::from_millis(100)).await;
+
+    relay.send_and_collect::<Counter, Count>(Count(1), &counter, SendOptions::default()).await?;
+    assert!(engine.metrics().actors().await.is_empty());
+
+    handle.abort();
+    Ok(())
+}
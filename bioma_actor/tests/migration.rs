@@ -0,0 +1,116 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use test_log::test;
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+// Version 0 stored the running total as `count`
+#[derive(Debug, Serialize, Deserialize)]
+struct Tally {
+    total: i64,
+}
+
+impl Actor for Tally {
+    type Error = TestError;
+
+    const STATE_VERSION: u32 = 1;
+
+    fn migrate(from_version: u32, mut state: Value) -> Result<Value, TestError> {
+        if from_version < 1 {
+            state = json!({ "total": state["count"] });
+        }
+        Ok(state)
+    }
+
+    async fn start(&mut self, _ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        Ok(())
+    }
+}
+
+// Unversioned, with fields that look like a state envelope
+#[derive(Debug, Serialize, Deserialize)]
+struct Draft {
+    version: u32,
+    state: String,
+}
+
+impl Actor for Draft {
+    type Error = TestError;
+
+    async fn start(&mut self, _ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        Ok(())
+    }
+}
+
+#[test(tokio::test)]
+async fn test_restore_migrates_state() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    let tally_id = ActorId::of::<Tally>("/tally");
+
+    // A record saved before the state was versioned
+    engine
+        .db()?
+        .query("CREATE $id CONTENT { tag: $tag, state: { count: 5 } }")
+        .bind(("id", tally_id.record_id()))
+        .bind(("tag", tally_id.tag().to_string()))
+        .await
+        .map_err(SystemActorError::from)?
+        .check()
+        .map_err(SystemActorError::from)?;
+
+    let options = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).build();
+    let (ctx, mut tally) = Actor::spawn(engine.clone(), tally_id.clone(), Tally { total: 0 }, options).await?;
+    assert_eq!(tally.total, 5);
+
+    // The migrated state is stored with the current version
+    let stored: Option<Value> = engine
+        .db()?
+        .query("SELECT VALUE state FROM ONLY $id")
+        .bind(("id", tally_id.record_id()))
+        .await
+        .map_err(SystemActorError::from)?
+        .take(0)
+        .map_err(SystemActorError::from)?;
+    assert_eq!(stored, Some(json!({ "$state_version": 1, "state": { "total": 5 } })));
+
+    // Saved state keeps the envelope, and restoring the current version leaves it unchanged
+    tally.total = 7;
+    tally.save(&ctx).await?;
+    let options = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).build();
+    let (_, tally) = Actor::spawn(engine.clone(), tally_id.clone(), Tally { total: 0 }, options).await?;
+    assert_eq!(tally.total, 7);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_restore_state_named_like_envelope() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    let draft_id = ActorId::of::<Draft>("/draft");
+
+    // Saved without an envelope, the fields are the actor's own
+    engine
+        .db()?
+        .query("CREATE $id CONTENT { tag: $tag, state: { version: 3, state: 'review' } }")
+        .bind(("id", draft_id.record_id()))
+        .bind(("tag", draft_id.tag().to_string()))
+        .await
+        .map_err(SystemActorError::from)?
+        .check()
+        .map_err(SystemActorError::from)?;
+
+    let options = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).build();
+    let (_, draft) =
+        Actor::spawn(engine.clone(), draft_id.clone(), Draft { version: 0, state: String::new() }, options).await?;
+    assert_eq!(draft.version, 3);
+    assert_eq!(draft.state, "review");
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::{sleep, timeout, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Job {
+    n: usize,
+}
+
+async fn send_jobs(relay_ctx: &ActorContext<Relay>, to: &ActorId) -> Result<(), TestError> {
+    relay_ctx.do_send_as(Job { n: 1 }, to).await?;
+    relay_ctx.do_send_as(Job { n: 2 }, to).await?;
+    let urgent = SendOptions::builder().timeout(Duration::from_secs(1)).priority(10).build();
+    let _replies = relay_ctx.send_as::<Job, ()>(Job { n: 3 }, to.clone(), urgent).await?;
+    // Let all messages reach the mailbox before reading
+    sleep(Duration::from_millis(300)).await;
+    Ok(())
+}
+
+async fn received_jobs(stream: &mut MessageStream, count: usize) -> Result<Vec<usize>, TestError> {
+    let mut jobs = Vec::new();
+    for _ in 0..count {
+        let frame = timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("message not received")
+            .expect("message stream ended")?;
+        jobs.push(frame.is::<Job>().expect("not a Job").n);
+    }
+    Ok(jobs)
+}
+
+#[test(tokio::test)]
+async fn test_priority_backlog() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    let worker_id = ActorId::of::<Relay>("/worker");
+    let (worker_ctx, _) = Actor::spawn(engine.clone(), worker_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // Messages are queued before the worker starts receiving
+    send_jobs(&relay_ctx, &worker_id).await?;
+    let mut stream = worker_ctx.recv().await?;
+
+    assert_eq!(received_jobs(&mut stream, 3).await?, vec![3, 1, 2]);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_priority_live() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    let worker_id = ActorId::of::<Relay>("/worker");
+    let (worker_ctx, _) = Actor::spawn(engine.clone(), worker_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // Messages arrive while the worker is busy and not reading its stream
+    let mut stream = worker_ctx.recv().await?;
+    send_jobs(&relay_ctx, &worker_id).await?;
+
+    assert_eq!(received_jobs(&mut stream, 3).await?, vec![3, 1, 2]);
+    Ok(())
+}
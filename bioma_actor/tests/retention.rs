@@ -0,0 +1,166 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::{sleep, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Ping;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PongActor;
+
+impl Message<Ping> for PongActor {
+    type Response = Ping;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _: &Ping) -> Result<(), TestError> {
+        ctx.reply(Ping).await?;
+        Ok(())
+    }
+}
+
+impl Actor for PongActor {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        let mut stream = ctx.recv().await?;
+        while let Some(Ok(frame)) = stream.next().await {
+            if let Some(ping) = frame.is::<Ping>() {
+                self.reply(ctx, &ping, &frame).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Stream;
+
+/// Replies with one chunk, then keeps streaming until aborted.
+#[derive(Debug, Serialize, Deserialize)]
+struct StreamActor;
+
+impl Message<Stream> for StreamActor {
+    type Response = Ping;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _: &Stream) -> Result<(), TestError> {
+        ctx.reply(Ping).await?;
+        sleep(Duration::from_secs(3600)).await;
+        Ok(())
+    }
+}
+
+impl Actor for StreamActor {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        let mut stream = ctx.recv().await?;
+        while let Some(Ok(frame)) = stream.next().await {
+            if let Some(message) = frame.is::<Stream>() {
+                self.reply(ctx, &message, &frame).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn message_count(engine: &Engine, rx: &ActorId) -> Result<usize, SystemActorError> {
+    let mut res = engine.db()?.query("SELECT id FROM message WHERE rx = $rx").bind(("rx", rx.record_id())).await?;
+    let records: Vec<Record> = res.take(0)?;
+    Ok(records.len())
+}
+
+async fn spawn_pong(engine: &Engine, id: &ActorId) -> Result<tokio::task::JoinHandle<()>, TestError> {
+    let (mut ctx, mut actor) = Actor::spawn(engine.clone(), id.clone(), PongActor, SpawnOptions::default()).await?;
+    Ok(tokio::spawn(async move {
+        let _ = ctx.run(&mut actor).await;
+    }))
+}
+
+#[test(tokio::test)]
+async fn test_retention_delete_on_final_reply() -> Result<(), TestError> {
+    let retention = RetentionOptions::builder().delete_on_final_reply(true).build();
+    let engine = Engine::test_with(EngineOptions::builder().retention(retention).build()).await?;
+
+    let pong_id = ActorId::of::<PongActor>("/pong");
+    let pong_handle = spawn_pong(&engine, &pong_id).await?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    relay_ctx.send_and_wait_reply::<PongActor, Ping>(Ping, &pong_id, SendOptions::default()).await?;
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(message_count(&engine, &pong_id).await?, 0);
+
+    // Unreplied messages are kept
+    let missing_id = ActorId::of::<PongActor>("/missing");
+    relay_ctx.do_send_as(Ping, &missing_id).await?;
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(message_count(&engine, &missing_id).await?, 1);
+
+    pong_handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_retention_keep_last() -> Result<(), TestError> {
+    let retention = RetentionOptions::builder().keep_last(1).compaction_interval(Duration::from_secs(3600)).build();
+    let engine = Engine::test_with(EngineOptions::builder().retention(retention).build()).await?;
+
+    let pong_id = ActorId::of::<PongActor>("/pong");
+    let pong_handle = spawn_pong(&engine, &pong_id).await?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    for _ in 0..3 {
+        relay_ctx.send_and_wait_reply::<PongActor, Ping>(Ping, &pong_id, SendOptions::default()).await?;
+    }
+    let missing_id = ActorId::of::<PongActor>("/missing");
+    relay_ctx.do_send_as(Ping, &missing_id).await?;
+    relay_ctx.do_send_as(Ping, &missing_id).await?;
+
+    assert_eq!(engine.compact().await?, 2);
+    assert_eq!(message_count(&engine, &pong_id).await?, 1);
+    assert_eq!(message_count(&engine, &missing_id).await?, 2);
+
+    pong_handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_retention_keeps_partial_stream() -> Result<(), TestError> {
+    let retention = RetentionOptions::builder()
+        .message_ttl(Duration::ZERO)
+        .reply_ttl(Duration::ZERO)
+        .compaction_interval(Duration::from_secs(3600))
+        .build();
+    let engine = Engine::test_with(EngineOptions::builder().retention(retention).build()).await?;
+
+    let stream_id = ActorId::of::<StreamActor>("/stream");
+    let (mut stream_ctx, mut stream_actor) =
+        Actor::spawn(engine.clone(), stream_id.clone(), StreamActor, SpawnOptions::default()).await?;
+    let stream_handle = tokio::spawn(async move {
+        let _ = stream_ctx.run(&mut stream_actor).await;
+    });
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    relay_ctx.do_send_as(Stream, &stream_id).await?;
+    sleep(Duration::from_millis(500)).await;
+
+    // The first chunk is stored, but the handler is still streaming
+    assert_eq!(engine.compact().await?, 0);
+    assert_eq!(message_count(&engine, &stream_id).await?, 1);
+    let mut res = engine.db()?.query("SELECT id FROM reply").await.map_err(SystemActorError::from)?;
+    let chunks: Vec<Record> = res.take(0).map_err(SystemActorError::from)?;
+    assert_eq!(chunks.len(), 1);
+
+    stream_handle.abort();
+    Ok(())
+}
@@ -0,0 +1,304 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use test_log::test;
+use tokio::time::{sleep, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+/// Replies with the worker name `replies` times, after `delay_ms`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Work {
+    key: String,
+    replies: u64,
+    delay_ms: u64,
+}
+
+impl Work {
+    fn new(key: &str) -> Self {
+        Self { key: key.to_string(), replies: 1, delay_ms: 0 }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, ActorHandlers)]
+#[handles(Work)]
+struct Worker;
+
+impl Message<Work> for Worker {
+    type Response = String;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &Work) -> Result<(), TestError> {
+        sleep(Duration::from_millis(message.delay_ms)).await;
+        for _ in 0..message.replies {
+            ctx.reply(ctx.id().name().to_string()).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Actor for Worker {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+struct Pool {
+    engine: Engine,
+    relay: ActorContext<Relay>,
+    router_id: ActorId,
+    handles: Vec<tokio::task::JoinHandle<()>>,
+}
+
+impl Pool {
+    async fn start(size: usize, router: Router) -> Result<Self, TestError> {
+        let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+        let mut handles = vec![];
+        for i in 0..size {
+            let id = ActorId::of::<Worker>(format!("/pool/worker/{}", i));
+            handles.push(spawn(&engine, id, Worker).await?);
+        }
+        let router_id = ActorId::of::<Router>("/pool");
+        handles.push(spawn(&engine, router_id.clone(), router).await?);
+        let (relay, _) =
+            Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+        // Give the actors time to start receiving
+        sleep(Duration::from_millis(100)).await;
+        Ok(Self { engine, relay, router_id, handles })
+    }
+
+    async fn send(&self, work: Work) -> Result<Vec<String>, TestError> {
+        Ok(self.relay.send_and_collect::<Worker, Work>(work, &self.router_id, SendOptions::default()).await?)
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        for handle in &self.handles {
+            handle.abort();
+        }
+    }
+}
+
+async fn spawn<T: Actor + 'static>(
+    engine: &Engine,
+    id: ActorId,
+    actor: T,
+) -> Result<tokio::task::JoinHandle<()>, TestError>
+where
+    TestError: From<T::Error>,
+{
+    let (mut ctx, mut actor) = Actor::spawn(engine.clone(), id, actor, SpawnOptions::default()).await?;
+    Ok(tokio::spawn(async move {
+        let _ = ctx.run(&mut actor).await;
+    }))
+}
+
+fn workers(range: std::ops::Range<usize>) -> Vec<ActorId> {
+    range.map(|i| ActorId::of::<Worker>(format!("/pool/worker/{}", i))).collect()
+}
+
+#[test(tokio::test)]
+async fn test_router_round_robin() -> Result<(), TestError> {
+    let pool = Pool::start(3, Router::builder().routees(workers(0..3)).build()).await?;
+
+    let mut replies = vec![];
+    for _ in 0..6 {
+        replies.extend(pool.send(Work::new("a")).await?);
+    }
+    assert_eq!(
+        replies,
+        vec![
+            "/pool/worker/0",
+            "/pool/worker/1",
+            "/pool/worker/2",
+            "/pool/worker/0",
+            "/pool/worker/1",
+            "/pool/worker/2"
+        ]
+    );
+
+    // Reply streams are forwarded as they are
+    let work = Work { replies: 3, ..Work::new("a") };
+    assert_eq!(pool.send(work).await?, vec!["/pool/worker/0"; 3]);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_router_broadcast() -> Result<(), TestError> {
+    let router = Router::builder().routees(workers(0..3)).strategy(RoutingStrategy::Broadcast).build();
+    let pool = Pool::start(3, router).await?;
+
+    let work = Work { replies: 2, ..Work::new("a") };
+    let mut replies = pool.send(work).await?;
+    replies.sort();
+    assert_eq!(
+        replies,
+        vec![
+            "/pool/worker/0",
+            "/pool/worker/0",
+            "/pool/worker/1",
+            "/pool/worker/1",
+            "/pool/worker/2",
+            "/pool/worker/2"
+        ]
+    );
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_router_consistent_hash() -> Result<(), TestError> {
+    let strategy = RoutingStrategy::ConsistentHash { key: Some("/key".to_string()) };
+    let pool = Pool::start(4, Router::builder().routees(workers(0..4)).strategy(strategy).build()).await?;
+
+    let keys: Vec<String> = (0..20).map(|i| format!("key-{}", i)).collect();
+    let mut routes = vec![];
+    for key in &keys {
+        let first = pool.send(Work::new(key)).await?;
+        // The same key goes to the same worker
+        assert_eq!(pool.send(Work::new(key)).await?, first);
+        routes.push(first[0].clone());
+    }
+    assert!(routes.iter().collect::<HashSet<_>>().len() > 1);
+
+    // Only the keys of a removed worker move
+    let removed = ActorId::of::<Worker>("/pool/worker/0");
+    pool.relay
+        .send_and_wait_reply::<Router, RemoveRoutee>(RemoveRoutee(removed), &pool.router_id, SendOptions::default())
+        .await?;
+    for (key, route) in keys.iter().zip(&routes) {
+        let now = pool.send(Work::new(key)).await?;
+        if route == "/pool/worker/0" {
+            assert_ne!(&now[0], route);
+        } else {
+            assert_eq!(&now[0], route);
+        }
+    }
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_router_least_loaded() -> Result<(), TestError> {
+    let router = Router::builder().routees(workers(0..2)).strategy(RoutingStrategy::LeastLoaded).build();
+    let pool = Pool::start(2, router).await?;
+
+    // Keep the first worker busy
+    let slow = Work { delay_ms: 1000, ..Work::new("slow") };
+    let relay = pool.relay.send::<Worker, Work>(slow, &pool.router_id, SendOptions::default()).await?;
+    sleep(Duration::from_millis(100)).await;
+
+    for _ in 0..3 {
+        assert_eq!(pool.send(Work::new("fast")).await?, vec!["/pool/worker/1"]);
+    }
+    let slow: Vec<String> = relay.map(|reply| reply.unwrap()).collect().await;
+    assert_eq!(slow, vec!["/pool/worker/0"]);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_router_resize() -> Result<(), TestError> {
+    let pool = Pool::start(3, Router::builder().routees(workers(0..1)).build()).await?;
+
+    let routees = pool
+        .relay
+        .send_and_wait_reply::<Router, AddRoutee>(
+            AddRoutee(workers(1..2)[0].clone()),
+            &pool.router_id,
+            SendOptions::default(),
+        )
+        .await?;
+    assert_eq!(routees, workers(0..2));
+    let mut replies = vec![];
+    for _ in 0..4 {
+        replies.extend(pool.send(Work::new("a")).await?);
+    }
+    assert_eq!(replies.iter().collect::<HashSet<_>>().len(), 2);
+
+    let routees = pool
+        .relay
+        .send_and_wait_reply::<Router, RemoveRoutee>(
+            RemoveRoutee(workers(0..1)[0].clone()),
+            &pool.router_id,
+            SendOptions::default(),
+        )
+        .await?;
+    assert_eq!(routees, workers(1..2));
+    assert_eq!(pool.send(Work::new("a")).await?, vec!["/pool/worker/1"]);
+
+    // An empty pool fails the message
+    pool.relay
+        .send_and_wait_reply::<Router, RemoveRoutee>(
+            RemoveRoutee(workers(1..2)[0].clone()),
+            &pool.router_id,
+            SendOptions::default(),
+        )
+        .await?;
+    assert!(pool.send(Work::new("a")).await.is_err());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_router_discover() -> Result<(), TestError> {
+    let query = ActorQuery::builder().prefix("/pool/worker/".into()).status(ActorStatus::Running).build();
+    let router = Router::builder().discover(query).refresh_interval(Duration::from_millis(100)).build();
+    let pool = Pool::start(2, router).await?;
+
+    let routees = pool
+        .relay
+        .send_and_wait_reply::<Router, ListRoutees>(ListRoutees, &pool.router_id, SendOptions::default())
+        .await?;
+    assert_eq!(routees, workers(0..2));
+
+    // A worker started later joins the pool
+    let handle = spawn(&pool.engine, workers(2..3)[0].clone(), Worker).await?;
+    sleep(Duration::from_millis(200)).await;
+    let mut replies = HashSet::new();
+    for _ in 0..6 {
+        replies.extend(pool.send(Work::new("a")).await?);
+    }
+    assert_eq!(replies.len(), 3);
+    handle.abort();
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_router_discover_excludes_itself() -> Result<(), TestError> {
+    let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+    let workers: Vec<ActorId> = (0..2).map(|i| ActorId::of::<Worker>(format!("/rerank/{}", i))).collect();
+    let mut handles = vec![];
+    for id in &workers {
+        handles.push(spawn(&engine, id.clone(), Worker).await?);
+    }
+    // The router is named like the prefix of its routees, so it matches its own query
+    let query = ActorQuery::builder().prefix("/rerank".into()).status(ActorStatus::Running).build();
+    let router = Router::builder().discover(query).strategy(RoutingStrategy::LeastLoaded).build();
+    let router_id = ActorId::of::<Router>("/rerank");
+    handles.push(spawn(&engine, router_id.clone(), router).await?);
+    let (relay, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    sleep(Duration::from_millis(100)).await;
+
+    let routees =
+        relay.send_and_wait_reply::<Router, ListRoutees>(ListRoutees, &router_id, SendOptions::default()).await?;
+    assert_eq!(routees, workers);
+
+    // Messages reach the workers, never the router again
+    for _ in 0..4 {
+        let replies =
+            relay.send_and_collect::<Worker, Work>(Work::new("a"), &router_id, SendOptions::default()).await?;
+        assert_eq!(replies.len(), 1);
+        assert!(replies[0].starts_with("/rerank/"));
+    }
+
+    for handle in handles {
+        handle.abort();
+    }
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use surrealdb::{engine::any::Any, Surreal};
+use test_log::test;
+use tokio::time::{timeout, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Tick;
+
+// Receiver whose context is polled directly by the tests
+#[derive(Debug, Serialize, Deserialize)]
+struct Ticked;
+
+impl Message<Tick> for Ticked {
+    type Response = ();
+
+    async fn handle(&mut self, _ctx: &mut ActorContext<Self>, _: &Tick) -> Result<(), TestError> {
+        Ok(())
+    }
+}
+
+impl Actor for Ticked {
+    type Error = TestError;
+
+    async fn start(&mut self, _ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        Ok(())
+    }
+}
+
+async fn test_engine() -> Result<Engine, SystemActorError> {
+    let schedule = ScheduleOptions::builder().poll_interval(Duration::from_millis(100)).build();
+    Engine::test_with(EngineOptions::builder().schedule(schedule).build()).await
+}
+
+#[test(tokio::test)]
+async fn test_send_after() -> Result<(), TestError> {
+    let engine = test_engine().await?;
+
+    let ticked_id = ActorId::of::<Ticked>("/ticked");
+    let (ticked_ctx, _) = Actor::spawn(engine.clone(), ticked_id.clone(), Ticked, SpawnOptions::default()).await?;
+    let mut stream = ticked_ctx.recv().await?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    let start = Instant::now();
+    let schedule_id = relay_ctx.send_after::<Ticked, Tick>(Tick, &ticked_id, Duration::from_millis(500)).await?;
+
+    let frame = timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("scheduled message not received")
+        .expect("message stream ended")?;
+    assert!(frame.is::<Tick>().is_some());
+    assert!(start.elapsed() >= Duration::from_millis(500));
+
+    // A single delivery removes the schedule and never fires again
+    assert!(!engine.cancel_schedule(&schedule_id).await?);
+    assert!(timeout(Duration::from_millis(500), stream.next()).await.is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_schedule_every_and_cancel() -> Result<(), TestError> {
+    let engine = test_engine().await?;
+
+    let ticked_id = ActorId::of::<Ticked>("/ticked");
+    let (ticked_ctx, _) = Actor::spawn(engine.clone(), ticked_id.clone(), Ticked, SpawnOptions::default()).await?;
+    let mut stream = ticked_ctx.recv().await?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    let schedule_id =
+        relay_ctx.schedule::<Ticked, Tick>(Tick, &ticked_id, Schedule::Every(Duration::from_millis(300))).await?;
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let frame = timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("scheduled message not received")
+            .expect("message stream ended")?;
+        ids.push(frame.id().clone());
+    }
+    assert_ne!(ids[0], ids[1]);
+
+    assert!(engine.cancel_schedule(&schedule_id).await?);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_schedule_invalid_cron() -> Result<(), TestError> {
+    let engine = test_engine().await?;
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    let result = relay_ctx
+        .schedule::<Ticked, Tick>(Tick, &ActorId::of::<Ticked>("/ticked"), Schedule::Cron("not a cron".to_string()))
+        .await;
+    assert!(matches!(result, Err(SystemActorError::InvalidSchedule(_))));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_schedule_local_delivery() -> Result<(), TestError> {
+    let schedule = ScheduleOptions::builder().poll_interval(Duration::from_millis(100)).build();
+    let options = EngineOptions::builder().schedule(schedule).local_delivery(LocalDelivery::MemoryOnly).build();
+    let engine = Engine::test_with(options).await?;
+
+    let ticked_id = ActorId::of::<Ticked>("/ticked");
+    let (ticked_ctx, _) = Actor::spawn(engine.clone(), ticked_id.clone(), Ticked, SpawnOptions::default()).await?;
+    let mut stream = ticked_ctx.recv().await?;
+    let (relay_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    // Scheduled messages are sent like any other message, here in memory only
+    relay_ctx.send_after::<Ticked, Tick>(Tick, &ticked_id, Duration::from_millis(200)).await?;
+    let frame = timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("scheduled message not received")
+        .expect("message stream ended")?;
+    assert!(frame.is::<Tick>().is_some());
+    assert!(frame.local);
+
+    let mut res = engine.db()?.query("SELECT id FROM message").await.map_err(SystemActorError::from)?;
+    let messages: Vec<Record> = res.take(0).map_err(SystemActorError::from)?;
+    assert!(messages.is_empty());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_schedule_shared_database() -> Result<(), TestError> {
+    // Two engines sharing the same database, both polling the schedules
+    let db: Surreal<Any> = Surreal::init();
+    db.connect("memory").await.map_err(SystemActorError::from)?;
+    let backend = SurrealBackend::new(db, "test".into(), "test".into()).await?;
+    let engine = || {
+        let schedule = ScheduleOptions::builder().poll_interval(Duration::from_millis(50)).build();
+        Engine::with_backend(backend.clone(), EngineOptions::builder().schedule(schedule).build())
+    };
+    let (engine_a, _engine_b) = (engine()?, engine()?);
+
+    let (relay_ctx, _) =
+        Actor::spawn(engine_a.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    let ticked_id = ActorId::of::<Ticked>("/ticked");
+    let schedule_id = relay_ctx.send_after::<Ticked, Tick>(Tick, &ticked_id, Duration::from_millis(200)).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    // The receiver is not running, the message waits in the database exactly once
+    assert!(!engine_a.cancel_schedule(&schedule_id).await?);
+    let mut res = engine_a
+        .db()?
+        .query("SELECT id FROM message WHERE rx = $rx")
+        .bind(("rx", ticked_id.record_id()))
+        .await
+        .map_err(SystemActorError::from)?;
+    let messages: Vec<Record> = res.take(0).map_err(SystemActorError::from)?;
+    assert_eq!(messages.len(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use bioma_actor::prelude::*;
+use object_store::path::Path;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_memory_store() -> Result<(), SystemActorError> {
+    let options = EngineOptions::builder().object_store(ObjectStoreOptions::Memory).build();
+    let engine = Engine::with_backend(MemoryBackend::default(), options)?;
+
+    // Clones of the engine share the store
+    let path = Path::from("reports/summary.md");
+    engine.store().put(&path, "# Summary".into()).await?;
+    let content = engine.clone().store().get(&path).await?.bytes().await?;
+    assert_eq!(content.as_ref(), b"# Summary");
+    Ok(())
+}
+
+#[test]
+fn test_store_options() {
+    let options: EngineOptions = serde_json::from_str(
+        r#"{
+            "local_store_dir": "/tmp/bioma",
+            "output_dir": "/tmp/bioma",
+            "hf_cache_dir": "/tmp/bioma",
+            "endpoint": "memory",
+            "namespace": "dev",
+            "database": "bioma",
+            "username": "root",
+            "password": "root",
+            "object_store": { "type": "s3", "bucket": "bioma", "endpoint": "http://localhost:9000", "allow_http": true }
+        }"#,
+    )
+    .unwrap();
+    let expected = ObjectStoreOptions::S3 {
+        bucket: "bioma".into(),
+        endpoint: Some("http://localhost:9000".into()),
+        region: None,
+        allow_http: true,
+    };
+    assert_eq!(options.object_store, expected);
+    assert_eq!(serde_json::from_str::<ObjectStoreOptions>(r#"{"type":"local"}"#).unwrap(), ObjectStoreOptions::Local);
+}
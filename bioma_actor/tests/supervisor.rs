@@ -0,0 +1,222 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::{sleep, timeout, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+    #[error("Crashed")]
+    Crashed,
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Increment;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Crash;
+
+// Counter actor that persists its count and fails on demand
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CrashingCounter {
+    count: u32,
+}
+
+impl Message<Increment> for CrashingCounter {
+    type Response = u32;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &Increment) -> Result<(), TestError> {
+        self.count += 1;
+        self.save(ctx).await?;
+        ctx.reply(self.count).await?;
+        Ok(())
+    }
+}
+
+impl Message<Crash> for CrashingCounter {
+    type Response = ();
+
+    async fn handle(&mut self, _ctx: &mut ActorContext<Self>, _msg: &Crash) -> Result<(), TestError> {
+        Ok(())
+    }
+}
+
+impl Actor for CrashingCounter {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        let mut stream = ctx.recv().await?;
+        while let Some(Ok(frame)) = stream.next().await {
+            if let Some(msg) = frame.is::<Increment>() {
+                self.reply(ctx, &msg, &frame).await?;
+            } else if let Some(msg) = frame.is::<Crash>() {
+                // Reply first so the crash message is not redelivered after the restart
+                self.reply(ctx, &msg, &frame).await?;
+                return Err(TestError::Crashed);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn counter_spec(id: ActorId) -> ChildSpec {
+    ChildSpec::of(id, CrashingCounter { count: 0 })
+}
+
+// Actor that fails as soon as it starts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct FailingActor;
+
+impl Actor for FailingActor {
+    type Error = TestError;
+
+    async fn start(&mut self, _ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        Err(TestError::Crashed)
+    }
+}
+
+#[test(tokio::test)]
+async fn test_supervisor_restart_restores_state() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let counter_id = ActorId::of::<CrashingCounter>("/supervisor/counter");
+    let options = SupervisorOptions::builder().min_backoff(Duration::from_millis(10)).build();
+    let supervisor = Supervisor::new(engine.clone(), options).child(counter_spec(counter_id.clone())).start();
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay) = Actor::spawn(engine.clone(), relay_id, Relay, SpawnOptions::default()).await?;
+
+    let count = relay_ctx
+        .send_and_wait_reply::<CrashingCounter, Increment>(Increment, &counter_id, SendOptions::default())
+        .await?;
+    assert_eq!(count, 1);
+
+    // Crash the child and wait for the supervisor to restart it
+    relay_ctx.send_and_collect::<CrashingCounter, Crash>(Crash, &counter_id, SendOptions::default()).await?;
+    sleep(Duration::from_millis(200)).await;
+
+    // The restarted child restores its persisted count
+    let count = relay_ctx
+        .send_and_wait_reply::<CrashingCounter, Increment>(Increment, &counter_id, SendOptions::default())
+        .await?;
+    assert_eq!(count, 2);
+    assert!(!supervisor.is_finished());
+
+    supervisor.stop();
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_supervisor_restart_limit() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let failing_id = ActorId::of::<FailingActor>("/supervisor/failing");
+    let spec = ChildSpec::of(failing_id.clone(), FailingActor);
+
+    let options = SupervisorOptions::builder()
+        .max_restarts(2)
+        .within(Duration::from_secs(10))
+        .min_backoff(Duration::from_millis(10))
+        .build();
+    let supervisor = Supervisor::new(engine.clone(), options).child(spec).start();
+
+    let result = timeout(Duration::from_secs(5), supervisor.join()).await.expect("supervisor did not give up");
+    match result {
+        Err(SystemActorError::RestartLimitExceeded(id, restarts)) => {
+            assert_eq!(id, failing_id);
+            assert_eq!(restarts, 2);
+        }
+        other => panic!("Unexpected supervisor result: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_supervisor_temporary_child() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let failing_id = ActorId::of::<FailingActor>("/supervisor/temporary");
+    let spec = ChildSpec::new(failing_id, |engine, id, options| {
+        tokio::spawn(async move {
+            let (mut ctx, mut actor) = Actor::spawn(engine, id, FailingActor, options)
+                .await
+                .map_err(|e| SystemActorError::ActorFailed(e.to_string().into()))?;
+            ctx.run(&mut actor).await.map_err(|e| SystemActorError::ActorFailed(e.to_string().into()))
+        })
+    })
+    .restart(ChildRestart::Temporary);
+
+    let supervisor = Supervisor::new(engine.clone(), SupervisorOptions::default()).child(spec).start();
+
+    // A temporary child is never restarted, so the supervisor finishes once it exits
+    let result = timeout(Duration::from_secs(5), supervisor.join()).await.expect("supervisor did not finish");
+    assert!(result.is_ok());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_supervisor_one_for_all_temporary_sibling() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let counter_id = ActorId::of::<CrashingCounter>("/supervisor/permanent");
+    let temporary_id = ActorId::of::<CrashingCounter>("/supervisor/temporary-sibling");
+    let options = SupervisorOptions::builder()
+        .strategy(RestartStrategy::OneForAll)
+        .min_backoff(Duration::from_millis(10))
+        .build();
+    let supervisor = Supervisor::new(engine.clone(), options)
+        .child(counter_spec(counter_id.clone()))
+        .child(counter_spec(temporary_id.clone()).restart(ChildRestart::Temporary))
+        .spawn()
+        .await?;
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay) = Actor::spawn(engine.clone(), relay_id, Relay, SpawnOptions::default()).await?;
+    let count = relay_ctx
+        .send_and_wait_reply::<CrashingCounter, Increment>(Increment, &temporary_id, SendOptions::default())
+        .await?;
+    assert_eq!(count, 1);
+
+    // Crash the permanent child, its temporary sibling is stopped but not restarted
+    relay_ctx.send_and_collect::<CrashingCounter, Crash>(Crash, &counter_id, SendOptions::default()).await?;
+    sleep(Duration::from_millis(200)).await;
+
+    let count = relay_ctx
+        .send_and_wait_reply::<CrashingCounter, Increment>(Increment, &counter_id, SendOptions::default())
+        .await?;
+    assert_eq!(count, 1);
+    let sibling =
+        relay_ctx.send_and_wait_reply::<CrashingCounter, Increment>(Increment, &temporary_id, SendOptions::default());
+    assert!(timeout(Duration::from_millis(500), sibling).await.is_err());
+    assert!(!supervisor.is_finished());
+
+    supervisor.stop();
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_supervisor_spawn_failure() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    // A counter with the same id already exists, and the child refuses to replace it
+    let counter_id = ActorId::of::<CrashingCounter>("/supervisor/existing");
+    let (_ctx, _counter) =
+        Actor::spawn(engine.clone(), counter_id.clone(), CrashingCounter { count: 0 }, SpawnOptions::default()).await?;
+    let spec = ChildSpec::of(counter_id, CrashingCounter { count: 0 })
+        .options(SpawnOptions::builder().exists(SpawnExistsOptions::Error).build());
+
+    let result = Supervisor::new(engine.clone(), SupervisorOptions::default()).child(spec).spawn().await;
+    match result {
+        Err(SystemActorError::ActorFailed(e)) => assert!(e.contains("already exists"), "{}", e),
+        other => panic!("Unexpected supervisor result: {:?}", other.map(|supervisor| supervisor.children().to_vec())),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use bioma_actor::prelude::*;
+use bioma_actor::testkit::TestKit;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::Duration;
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Ping(u32);
+
+/// Replies with the value of a key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Get(String);
+
+/// Looks up keys in a store, and tells the auditor about every lookup
+#[derive(Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(Get)]
+struct Lookup {
+    store: ActorId,
+    auditor: ActorId,
+}
+
+impl Message<Get> for Lookup {
+    type Response = Option<u32>;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &Get) -> Result<(), TestError> {
+        let value = ctx.send_and_wait_reply::<Store, Get>(message.clone(), &self.store, SendOptions::default()).await?;
+        ctx.do_send_as(Ping(value.unwrap_or_default()), &self.auditor).await?;
+        ctx.reply(value).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Lookup {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+/// Only stubbed in these tests
+#[derive(Debug, Serialize, Deserialize)]
+struct Store;
+
+impl Message<Get> for Store {
+    type Response = Option<u32>;
+
+    async fn handle(&mut self, _ctx: &mut ActorContext<Self>, _message: &Get) -> Result<(), TestError> {
+        unreachable!("stubbed")
+    }
+}
+
+impl Actor for Store {
+    type Error = TestError;
+
+    async fn start(&mut self, _ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        unreachable!("stubbed")
+    }
+}
+
+/// Waits for an hour, then replies
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Sleepy;
+
+#[derive(Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(Sleepy)]
+struct Sleeper;
+
+impl Message<Sleepy> for Sleeper {
+    type Response = bool;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _message: &Sleepy) -> Result<(), TestError> {
+        tokio::time::sleep(Duration::from_secs(3600)).await;
+        ctx.reply(true).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Sleeper {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+#[test(tokio::test)]
+async fn test_probe_and_stub() -> Result<(), TestError> {
+    let mut kit = TestKit::new().await?;
+    let mut auditor = kit.probe("/auditor").await?;
+    let store = kit.stub::<Store, Get>("/store", vec![Some(42)]).await?;
+    let lookup = kit.spawn("/lookup", Lookup { store, auditor: auditor.id().clone() }).await?;
+
+    let options = SendOptions::builder().timeout(Duration::from_secs(5)).build();
+    let value = kit.relay().send_and_wait_reply::<Lookup, Get>(Get("answer".into()), &lookup, options).await?;
+    assert_eq!(value, Some(42));
+    assert_eq!(auditor.expect_msg::<Ping>(Duration::from_secs(1)).await, Ping(42));
+    auditor.expect_no_msg(Duration::from_millis(100)).await;
+    assert_eq!(auditor.received().len(), 1);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_probe_reply() -> Result<(), TestError> {
+    let kit = TestKit::new().await?;
+    let mut probe = kit.probe("/probe").await?;
+
+    let options = SendOptions::builder().timeout(Duration::from_secs(5)).build();
+    let asked = kit.relay().send_as_and_wait_reply::<Ping, Ping>(Ping(1), probe.id().clone(), options);
+    let answer = async {
+        assert_eq!(probe.expect_msg::<Ping>(Duration::from_secs(1)).await, Ping(1));
+        probe.reply(Ping(2)).await
+    };
+    let (asked, answered) = tokio::join!(asked, answer);
+    answered?;
+    assert_eq!(asked?, Ping(2));
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_paused_clock() -> Result<(), TestError> {
+    let mut kit = TestKit::paused().await?;
+    let sleeper = kit.spawn("/sleeper", Sleeper).await?;
+    let probe = kit.probe("/silent").await?;
+    let wall_clock = std::time::Instant::now();
+    let start = tokio::time::Instant::now();
+
+    // A timeout elapses without waiting for it
+    let options = SendOptions::builder().timeout(Duration::from_secs(60)).build();
+    let result = kit.relay().send_as_and_wait_reply::<Ping, Ping>(Ping(1), probe.id().clone(), options).await;
+    assert!(matches!(result, Err(SystemActorError::MessageTimeout(_, _))));
+    assert!(start.elapsed() >= Duration::from_secs(60));
+
+    // So does a delay in an actor
+    let options = SendOptions::builder().timeout(Duration::from_secs(7200)).build();
+    assert!(kit.relay().send_and_wait_reply::<Sleeper, Sleepy>(Sleepy, &sleeper, options).await?);
+    assert!(start.elapsed() >= Duration::from_secs(3660));
+    assert!(wall_clock.elapsed() < Duration::from_secs(30));
+    Ok(())
+}
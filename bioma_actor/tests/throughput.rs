@@ -0,0 +1,125 @@
+//! Message throughput with many concurrent actors.
+//!
+//! Run with:
+//!
+//! ```sh
+//! cargo test --release -p bioma_actor --test throughput -- --ignored --nocapture
+//! ```
+//!
+//! Every pair of actors exchanges request/reply messages through the database. The throughput
+//! with the shared concurrent client grows with the number of pairs until the database itself
+//! is saturated.
+//!
+//! The baseline is simulated: the same exchanges are serialized behind one mutex, so only one
+//! round trip is in flight at a time. It is not the previous locked client, which held its
+//! mutex for each query rather than for a whole round trip, so the reported speedup is an
+//! upper bound of the gain.
+
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use test_log::test;
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+use tracing::info;
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Ping(u64);
+
+#[derive(Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(Ping)]
+struct Pong;
+
+impl Message<Ping> for Pong {
+    type Response = u64;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &Ping) -> Result<(), TestError> {
+        ctx.reply(message.0).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Pong {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+/// Sends `messages` pings from each of `pairs` senders to its own receiver, and returns messages per second.
+///
+/// If `serialized`, each exchange holds a lock shared by all senders, so only one is in flight at a
+/// time, as a simulated baseline.
+async fn run(pairs: usize, messages: u64, serialized: bool) -> Result<f64, TestError> {
+    let engine = Engine::test().await?;
+
+    let mut receivers = Vec::new();
+    let mut senders = Vec::new();
+    for i in 0..pairs {
+        let pong_id = ActorId::of::<Pong>(format!("/pong/{}", i));
+        let (mut pong_ctx, mut pong) =
+            Actor::spawn(engine.clone(), pong_id.clone(), Pong, SpawnOptions::default()).await?;
+        receivers.push(tokio::spawn(async move { pong_ctx.run(&mut pong).await }));
+
+        let relay_id = ActorId::of::<Relay>(format!("/relay/{}", i));
+        let (relay_ctx, _) = Actor::spawn(engine.clone(), relay_id, Relay, SpawnOptions::default()).await?;
+        senders.push((relay_ctx, pong_id));
+    }
+    // Let the receivers set up their live queries
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let lock = Arc::new(Mutex::new(()));
+    let start = Instant::now();
+    let tasks: Vec<_> = senders
+        .into_iter()
+        .map(|(relay_ctx, pong_id)| {
+            let lock = lock.clone();
+            tokio::spawn(async move {
+                for i in 0..messages {
+                    let _guard = if serialized { Some(lock.lock().await) } else { None };
+                    let reply =
+                        relay_ctx.send_and_wait_reply::<Pong, Ping>(Ping(i), &pong_id, SendOptions::default()).await?;
+                    assert_eq!(reply, i);
+                }
+                Ok::<_, SystemActorError>(())
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.expect("sender panicked")?;
+    }
+    let elapsed = start.elapsed();
+
+    for receiver in receivers {
+        receiver.abort();
+    }
+    Ok((pairs as u64 * messages) as f64 / elapsed.as_secs_f64())
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 8))]
+#[ignore = "Benchmark, run explicitly in release mode"]
+async fn bench_concurrent_throughput() -> Result<(), TestError> {
+    let messages = 200;
+    for pairs in [1, 4, 16, 64] {
+        let baseline = run(pairs, messages, true).await?;
+        let concurrent = run(pairs, messages, false).await?;
+        info!(
+            "pairs={} simulated-baseline={:.0} msg/s concurrent={:.0} msg/s speedup<={:.2}x",
+            pairs,
+            baseline,
+            concurrent,
+            concurrent / baseline
+        );
+    }
+    Ok(())
+}
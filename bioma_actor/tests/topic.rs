@@ -0,0 +1,88 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::{timeout, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+}
+
+impl ActorError for TestError {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct SourceDeleted {
+    source: String,
+}
+
+#[test(tokio::test)]
+async fn test_publish_subscribe() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let (publisher_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/publisher"), Relay, SpawnOptions::default()).await?;
+    let (first_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/first"), Relay, SpawnOptions::default()).await?;
+    let (second_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/second"), Relay, SpawnOptions::default()).await?;
+
+    first_ctx.subscribe("sources").await?;
+    second_ctx.subscribe("sources").await?;
+    // Subscribing twice has no effect
+    second_ctx.subscribe("sources").await?;
+
+    let mut first_stream = first_ctx.recv().await?;
+    let mut second_stream = second_ctx.recv().await?;
+
+    let event = SourceDeleted { source: "/docs".to_string() };
+    assert_eq!(publisher_ctx.publish("sources", event.clone()).await?, 2);
+
+    for stream in [&mut first_stream, &mut second_stream] {
+        let frame = timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("published message not received")
+            .expect("message stream ended")?;
+        assert_eq!(frame.topic.as_deref(), Some("sources"));
+        assert_eq!(frame.is::<SourceDeleted>(), Some(event.clone()));
+    }
+
+    // Unsubscribed actors no longer receive messages
+    second_ctx.unsubscribe("sources").await?;
+    assert_eq!(publisher_ctx.publish("sources", event.clone()).await?, 1);
+    assert!(timeout(Duration::from_millis(500), second_stream.next()).await.is_err());
+
+    // Nobody listens on other topics
+    assert_eq!(publisher_ctx.publish("other", event).await?, 0);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_publish_claim_check() -> Result<(), TestError> {
+    let store_dir = std::env::temp_dir().join(format!("bioma-publish-{}", ulid::Ulid::new()));
+    let claim_check = ClaimCheckOptions::builder().threshold(1024).build();
+    let options = EngineOptions::builder().local_store_dir(store_dir.clone()).claim_check(claim_check).build();
+    let engine = Engine::test_with(options).await?;
+
+    let (publisher_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/publisher"), Relay, SpawnOptions::default()).await?;
+    let (subscriber_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/subscriber"), Relay, SpawnOptions::default()).await?;
+    subscriber_ctx.subscribe("sources").await?;
+    let mut stream = subscriber_ctx.recv().await?;
+
+    // Published messages are sent through the backend, so large content is offloaded
+    let event = SourceDeleted { source: "/docs".repeat(1_000) };
+    assert_eq!(publisher_ctx.publish("sources", event.clone()).await?, 1);
+    let frame = timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("published message not received")
+        .expect("message stream ended")?;
+    assert_eq!(frame.topic.as_deref(), Some("sources"));
+    assert_eq!(frame.is::<SourceDeleted>(), Some(event));
+    assert!(std::fs::read_dir(store_dir.join("claim-check")).is_ok());
+
+    let _ = std::fs::remove_dir_all(&store_dir);
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use test_log::test;
+use tokio::time::{sleep, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl ActorError for TestError {}
+
+/// Asks the back actor, replies with the trace contexts of both actors
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Ask;
+
+/// Replies with the trace context of the back actor
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Lookup;
+
+#[derive(Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(Ask)]
+struct Front {
+    back: ActorId,
+}
+
+impl Message<Ask> for Front {
+    type Response = Vec<TraceContext>;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _message: &Ask) -> Result<(), TestError> {
+        let back = ctx.send_and_wait_reply::<Back, Lookup>(Lookup, &self.back, SendOptions::default()).await?;
+        let front = ctx.trace_context().cloned().unwrap();
+        ctx.reply(vec![front, back]).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Front {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(Lookup)]
+struct Back;
+
+impl Message<Lookup> for Back {
+    type Response = TraceContext;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _message: &Lookup) -> Result<(), TestError> {
+        let trace = ctx.trace_context().cloned().unwrap();
+        ctx.reply(trace).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Back {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        ctx.serve(self).await
+    }
+}
+
+async fn spawn<T: Actor + 'static>(engine: &Engine, id: ActorId, actor: T) -> Result<(), TestError>
+where
+    TestError: From<T::Error>,
+{
+    let (mut ctx, mut actor) = Actor::spawn(engine.clone(), id, actor, SpawnOptions::default()).await?;
+    tokio::spawn(async move {
+        let _ = actor.start(&mut ctx).await;
+    });
+    Ok(())
+}
+
+/// The spans exported to a file
+fn exported_spans(path: &std::path::Path) -> Result<Vec<Value>, TestError> {
+    let mut spans = vec![];
+    for line in std::fs::read_to_string(path)?.lines() {
+        let request: Value = serde_json::from_str(line)?;
+        for resource in request["resourceSpans"].as_array().unwrap() {
+            assert_eq!(resource["resource"]["attributes"][0]["value"]["stringValue"], "test");
+            for scope in resource["scopeSpans"].as_array().unwrap() {
+                spans.extend(scope["spans"].as_array().unwrap().iter().cloned());
+            }
+        }
+    }
+    Ok(spans)
+}
+
+#[test(tokio::test)]
+async fn test_trace_propagation() -> Result<(), TestError> {
+    let path = std::env::temp_dir().join(format!("bioma-trace-{}.jsonl", ulid::Ulid::new()));
+    let tracing = TracingOptions::builder()
+        .exporter(TraceExporter::File { path: path.clone() })
+        .service_name("test".into())
+        .export_interval(Duration::from_millis(100))
+        .build();
+    let options = EngineOptions::builder().tracing(tracing).build();
+    let engine = Engine::with_backend(MemoryBackend::default(), options)?;
+
+    let back = ActorId::of::<Back>("/back");
+    let front = ActorId::of::<Front>("/front");
+    spawn(&engine, back.clone(), Back).await?;
+    spawn(&engine, front.clone(), Front { back }).await?;
+    let (relay, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+    sleep(Duration::from_millis(100)).await;
+
+    // The whole request shares the trace and baggage of the caller
+    let root = TraceContext::new().with_baggage("request", "42");
+    let options = SendOptions::builder().timeout(Duration::from_secs(5)).trace(root.clone()).build();
+    let traces = relay.send_and_wait_reply::<Front, Ask>(Ask, &front, options).await?;
+    let (front_trace, back_trace) = (&traces[0], &traces[1]);
+    assert_eq!(front_trace.trace_id, root.trace_id);
+    assert_eq!(back_trace.trace_id, root.trace_id);
+    assert_eq!(back_trace.baggage.get("request").map(String::as_str), Some("42"));
+    assert_ne!(front_trace.span_id, back_trace.span_id);
+
+    // Sending and processing are exported as a chain of spans
+    sleep(Duration::from_millis(300)).await;
+    let spans = exported_spans(&path)?;
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(spans.len(), 4);
+    assert!(spans.iter().all(|span| span["traceId"] == root.trace_id.as_str()));
+    let parent_of = |span_id: &str| -> String {
+        let span = spans.iter().find(|span| span["spanId"] == span_id).unwrap();
+        span["parentSpanId"].as_str().unwrap().to_string()
+    };
+    // Back processing <- send Lookup <- Front processing <- send Ask <- caller
+    let send_lookup = parent_of(&back_trace.span_id);
+    assert_eq!(parent_of(&send_lookup), front_trace.span_id);
+    let send_ask = parent_of(&front_trace.span_id);
+    assert_eq!(parent_of(&send_ask), root.span_id);
+
+    // Without a caller trace, each request starts a new one
+    let options = SendOptions::builder().timeout(Duration::from_secs(5)).build();
+    let traces = relay.send_and_wait_reply::<Front, Ask>(Ask, &front, options).await?;
+    assert_ne!(traces[0].trace_id, root.trace_id);
+    assert_eq!(traces[0].trace_id, traces[1].trace_id);
+    Ok(())
+}
+
+#[test]
+fn test_traceparent() {
+    let trace = TraceContext::new();
+    assert_eq!(TraceContext::from_traceparent(&trace.traceparent()), Some(trace));
+
+    let trace = TraceContext::from_traceparent("00-4BF92F3577B34DA6A3CE929D0E0E4736-00F067AA0BA902B7-01").unwrap();
+    assert_eq!(trace.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(trace.span_id, "00f067aa0ba902b7");
+
+    assert!(TraceContext::from_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+    assert!(TraceContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-01").is_none());
+}
@@ -0,0 +1,120 @@
+use bioma_actor::prelude::*;
+use serde::{Deserialize, Serialize};
+use test_log::test;
+use tokio::time::{sleep, timeout, Duration};
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+    #[error("System error: {0}")]
+    System(#[from] SystemActorError),
+    #[error("Fake error")]
+    FakeError,
+}
+
+impl ActorError for TestError {}
+
+// Actor that fails as soon as it starts
+#[derive(Debug, Serialize, Deserialize)]
+struct FailingActor;
+
+impl Actor for FailingActor {
+    type Error = TestError;
+
+    async fn start(&mut self, _ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        Err(TestError::FakeError)
+    }
+}
+
+async fn next_terminated(ctx: &ActorContext<Relay>) -> Result<Terminated, SystemActorError> {
+    let mut stream = ctx.recv().await?;
+    let frame = timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("no Terminated message received")
+        .expect("message stream ended")?;
+    Ok(frame.is::<Terminated>().expect("not a Terminated message"))
+}
+
+#[test(tokio::test)]
+async fn test_watch_killed() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let (mut watcher_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/watcher"), Relay, SpawnOptions::default()).await?;
+    let watched_id = ActorId::of::<Relay>("/watched");
+    let (watched_ctx, _) = Actor::spawn(engine.clone(), watched_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    watcher_ctx.watch(&watched_id).await?;
+    watched_ctx.kill().await?;
+
+    let terminated = next_terminated(&watcher_ctx).await?;
+    assert_eq!(terminated.id, watched_id);
+    assert_eq!(terminated.reason, ExitReason::Killed);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_watch_error_exit() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let (mut watcher_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/watcher"), Relay, SpawnOptions::default()).await?;
+    let failing_id = ActorId::of::<FailingActor>("/failing");
+    let (mut failing_ctx, mut failing_actor) =
+        Actor::spawn(engine.clone(), failing_id.clone(), FailingActor, SpawnOptions::default()).await?;
+
+    watcher_ctx.watch(&failing_id).await?;
+
+    let failing_handle = tokio::spawn(async move {
+        let result = failing_ctx.run(&mut failing_actor).await;
+        assert!(result.is_err());
+    });
+    failing_handle.await.unwrap();
+
+    let terminated = next_terminated(&watcher_ctx).await?;
+    assert_eq!(terminated.id, failing_id);
+    assert_eq!(terminated.reason, ExitReason::Error("Fake error".to_string()));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_watch_not_found() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let (mut watcher_ctx, _) =
+        Actor::spawn(engine.clone(), ActorId::of::<Relay>("/watcher"), Relay, SpawnOptions::default()).await?;
+    let missing_id = ActorId::of::<Relay>("/missing");
+
+    watcher_ctx.watch(&missing_id).await?;
+
+    let terminated = next_terminated(&watcher_ctx).await?;
+    assert_eq!(terminated.id, missing_id);
+    assert_eq!(terminated.reason, ExitReason::NotFound);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_exit_recorded_only_for_run_actors() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    // A context that only sends leaves no exit behind
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _) = Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+    drop(relay_ctx);
+
+    let failing_id = ActorId::of::<FailingActor>("/failing");
+    let (mut failing_ctx, mut failing_actor) =
+        Actor::spawn(engine.clone(), failing_id.clone(), FailingActor, SpawnOptions::default()).await?;
+    let _ = failing_ctx.run(&mut failing_actor).await;
+    drop(failing_ctx);
+    sleep(Duration::from_millis(100)).await;
+
+    let relay = engine.backend().get_actor(&relay_id.record_id()).await?.expect("relay record");
+    assert_eq!(relay.exit(), None);
+    let failing = engine.backend().get_actor(&failing_id.record_id()).await?.expect("failing record");
+    assert_eq!(failing.exit(), Some(&ExitReason::Error("Fake error".to_string())));
+
+    Ok(())
+}
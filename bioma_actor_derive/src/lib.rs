@@ -0,0 +1,160 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, punctuated::Punctuated, DeriveInput, LitInt, LitStr, Path, Token, Type};
+
+/// Derives `ActorHandlers`, routing frames to the `Message` implementations listed in `#[handles(...)]`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(ActorHandlers, Debug, Serialize, Deserialize)]
+/// #[handles(StoreEmbeddings, GenerateEmbeddings, TopK)]
+/// pub struct Embeddings {
+///     // ...
+/// }
+/// ```
+#[proc_macro_derive(ActorHandlers, attributes(handles))]
+pub fn derive_actor_handlers(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut messages: Vec<Type> = Vec::new();
+    let mut found = false;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("handles")) {
+        let types = attr.parse_args_with(Punctuated::<Type, Token![,]>::parse_terminated)?;
+        messages.extend(types);
+        found = true;
+    }
+    // An empty `#[handles()]` is allowed for actors that only drain their mailbox
+    if !found {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "ActorHandlers requires a #[handles(MessageType, ...)] attribute",
+        ));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let arms = messages.iter().map(|message| {
+        quote! {
+            match frame.decode::<#message>() {
+                Some(Ok(message)) => {
+                    <Self as ::bioma_actor::Message<#message>>::reply(self, ctx, &message, frame).await?;
+                    return Ok(true);
+                }
+                Some(Err(e)) => {
+                    ctx.reject(frame, &e).await;
+                    return Err(e.into());
+                }
+                None => {}
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::bioma_actor::ActorHandlers for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            async fn dispatch(
+                &mut self,
+                ctx: &mut ::bioma_actor::ActorContext<Self>,
+                frame: &::bioma_actor::FrameMessage,
+            ) -> Result<bool, <Self as ::bioma_actor::Actor>::Error> {
+                #(#arms)*
+                Ok(false)
+            }
+        }
+    })
+}
+
+/// Derives `MessageName` from a `#[message(...)]` attribute, and registers the type for the process.
+///
+/// - `name = "..."`: stable name used in frames (required)
+/// - `alias = "..."`: previous name still accepted, can be repeated
+/// - `version = N`: schema version of the message content
+/// - `upgrade = path`: `fn(u32, serde_json::Value) -> Result<serde_json::Value, SystemActorError>`
+///   converting content from an older version
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(MessageName, Clone, Debug, Serialize, Deserialize)]
+/// #[message(name = "bioma.llm.IndexGlobs", alias = "bioma_llm::indexer::IndexGlobs", version = 2, upgrade = upgrade_globs)]
+/// pub struct IndexGlobs {
+///     pub globs: Vec<String>,
+/// }
+/// ```
+#[proc_macro_derive(MessageName, attributes(message))]
+pub fn derive_message_name(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_message_name(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_message_name(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut name: Option<LitStr> = None;
+    let mut aliases: Vec<LitStr> = Vec::new();
+    let mut version: Option<LitInt> = None;
+    let mut upgrade: Option<Path> = None;
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("message")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("alias") {
+                aliases.push(meta.value()?.parse()?);
+            } else if meta.path.is_ident("version") {
+                version = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("upgrade") {
+                upgrade = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected `name`, `alias`, `version` or `upgrade`"));
+            }
+            Ok(())
+        })?;
+    }
+    let Some(name) = name else {
+        return Err(syn::Error::new_spanned(&input.ident, "MessageName requires #[message(name = \"...\")]"));
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let version = version.map(|version| quote! { const VERSION: u32 = #version; });
+    let upgrade = upgrade.map(|upgrade| {
+        quote! {
+            fn upgrade(
+                version: u32,
+                msg: ::bioma_actor::__private::Value,
+            ) -> Result<::bioma_actor::__private::Value, ::bioma_actor::SystemActorError> {
+                #upgrade(version, msg)
+            }
+        }
+    });
+
+    // Generic types have no single type to register, they are registered with `register_message`
+    let registration = input.generics.params.is_empty().then(|| {
+        quote! {
+            ::bioma_actor::__private::inventory::submit! {
+                ::bioma_actor::__private::MessageNameRegistration::of::<#ident>()
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::bioma_actor::MessageName for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+            const ALIASES: &'static [&'static str] = &[#(#aliases),*];
+            #version
+            #upgrade
+        }
+
+        #registration
+    })
+}
@@ -7,7 +7,8 @@ use tracing::{debug, error, info, warn};
 /// Logs a message at the specified level.
 ///
 /// The `Log` action logs a message when ticked and always returns success.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+#[derive(Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(BehaviorTick)]
 pub struct Log {
     pub level: LogLevel,
     pub text: String,
@@ -45,7 +46,7 @@ impl ActorFactory for LogFactory {
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("LogFactory::spawn: start {}", ctx.id());
-            actor.start(&mut ctx).await?;
+            ctx.run(&mut actor).await?;
             debug!("LogFactory::spawn: end {}", ctx.id());
             Ok(())
         }))
@@ -70,13 +71,6 @@ impl Actor for Log {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
-                self.reply(ctx, &BehaviorTick, &frame).await?;
-                break;
-            }
-        }
-        Ok(())
+        ctx.serve_once(self).await
     }
 }
@@ -8,8 +8,9 @@ use tracing::debug;
 /// Waits for a specified duration, then succeeds.
 ///
 /// The `Wait` action pauses for the given duration when ticked and always returns success after the
-/// delay period has elapsed.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+/// delay period has elapsed. A cancelled tick stops waiting.
+#[derive(Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(BehaviorTick)]
 pub struct Wait {
     #[serde(with = "humantime_serde")]
     pub duration: Duration,
@@ -40,7 +41,7 @@ impl ActorFactory for WaitFactory {
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("WaitFactory::spawn: start {}", ctx.id());
-            actor.start(&mut ctx).await?;
+            ctx.run(&mut actor).await?;
             debug!("WaitFactory::spawn: end {}", ctx.id());
             Ok(())
         }))
@@ -51,7 +52,11 @@ impl Message<BehaviorTick> for Wait {
     type Response = BehaviorStatus;
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorTick) -> Result<(), Self::Error> {
-        tokio::time::sleep(self.duration).await;
+        // Nobody waits for the status of a cancelled tick
+        let cancel = ctx.cancellation_token();
+        if cancel.run_until_cancelled(tokio::time::sleep(self.duration)).await.is_none() {
+            return Ok(());
+        }
         ctx.reply(BehaviorStatus::Success).await?;
         Ok(())
     }
@@ -61,13 +66,6 @@ impl Actor for Wait {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
-                self.reply(ctx, &BehaviorTick, &frame).await?;
-                break;
-            }
-        }
-        Ok(())
+        ctx.serve_once(self).await
     }
 }
@@ -33,12 +33,17 @@ pub trait Behavior: Sized + Message<BehaviorTick> {
 ///
 /// This struct is typically sent to behavior nodes to initiate their processing
 /// during a behavior tree traversal.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+///
+/// Actions and sequences stop after their first tick. Decorators and the other composites
+/// keep serving ticks: if handling one fails, the sender receives the error, which parents
+/// treat as a failure, and the node stays up for the next tick.
+///
+/// A tick is cancelled when its sender drops the reply stream or times out, which cascades to
+/// the ticks sent to children, see `ActorContext::cancellation_token`.
+#[derive(Clone, Debug, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.behavior.BehaviorTick")]
 pub struct BehaviorTick;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct BehaviorCancel;
-
 /// Represents the final status of a behavior after execution.
 ///
 /// Due to the asynchronous nature of behavior execution, behaviors that haven't
@@ -174,15 +179,17 @@ impl Decorator {
 
     /// Stops the child of this decorator node.
     ///
-    /// This method removes the child's handle and ID from the decorator node's internal fields.
-    /// By releasing the handle, we expect the child and all its descendants to be stopped by the tokio runtime.
+    /// This method aborts the child's task and removes its handle and ID from the decorator node's
+    /// internal fields. Actors watching the child receive a `Terminated` message.
     ///
     /// # Note
     ///
     /// This method clears both the `child_handle` and `child` fields, effectively
     /// disconnecting the decorator from its child node.
     pub fn child_stop(&mut self) {
-        self.child_handle = None;
+        if let Some(handle) = self.child_handle.take() {
+            handle.abort();
+        }
         self.child = None;
     }
 }
@@ -265,8 +272,8 @@ impl Composite {
 
     /// Stops a child of this composite node by its index.
     ///
-    /// This method removes the child's handle and ID from the composite node's internal lists.
-    /// By releasing the handle, we expect the child and all its children to be stopped by the tokio runtime.
+    /// This method aborts the child's task and removes its handle and ID from the composite node's
+    /// internal lists. Actors watching the child receive a `Terminated` message.
     ///
     /// # Arguments
     ///
@@ -278,7 +285,7 @@ impl Composite {
     /// the method will silently do nothing for that list.
     pub fn child_stop(&mut self, idx: usize) {
         if idx < self.children_handles.len() {
-            self.children_handles.remove(idx);
+            self.children_handles.remove(idx).abort();
         }
         if idx < self.children.len() {
             self.children.remove(idx);
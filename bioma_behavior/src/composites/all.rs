@@ -1,7 +1,6 @@
 use crate::prelude::*;
 use bioma_actor::prelude::*;
 use bon::Builder;
-use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
@@ -10,7 +9,8 @@ use tracing::debug;
 /// The `All` composite node runs each of its child nodes concurrently. If any child node fails, the `All` node
 /// immediately fails and all other child nodes are interrupted; otherwise, it succeeds once all
 /// child nodes have successfully completed.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+#[derive(Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(BehaviorTick)]
 pub struct All {
     #[serde(skip)]
     #[builder(skip)]
@@ -40,7 +40,7 @@ impl ActorFactory for AllFactory {
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("AllFactory::spawn: start {}", ctx.id());
-            actor.start(&mut ctx).await?;
+            ctx.run(&mut actor).await?;
             debug!("AllFactory::spawn: end {}", ctx.id());
             Ok(())
         }))
@@ -83,7 +83,7 @@ impl Message<BehaviorTick> for All {
 
         if overall_status == BehaviorStatus::Failure {
             // Stop all children
-            for child_idx in 0..children.len() {
+            for child_idx in (0..children.len()).rev() {
                 self.node.child_stop(child_idx);
             }
         }
@@ -97,12 +97,6 @@ impl Actor for All {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
-                self.reply(ctx, &BehaviorTick, &frame).await?;
-            }
-        }
-        Ok(())
+        ctx.serve(self).await
     }
 }
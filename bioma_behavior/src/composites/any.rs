@@ -9,7 +9,8 @@ use tracing::debug;
 /// The `Any` composite node runs each of its child nodes concurrently. If any child node succeeds, the `Any` node
 /// immediately succeeds and interrupts all other running child nodes; if all child nodes fail,
 /// then the `Any` node fails.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+#[derive(Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(BehaviorTick)]
 pub struct Any {
     #[serde(skip)]
     #[builder(skip)]
@@ -39,7 +40,7 @@ impl ActorFactory for AnyFactory {
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("AnyFactory::spawn: start {}", ctx.id());
-            actor.start(&mut ctx).await?;
+            ctx.run(&mut actor).await?;
             debug!("AnyFactory::spawn: end {}", ctx.id());
             Ok(())
         }))
@@ -79,7 +80,7 @@ impl Message<BehaviorTick> for Any {
 
         if overall_status == BehaviorStatus::Success {
             // Stop all children
-            for child_idx in 0..children.len() {
+            for child_idx in (0..children.len()).rev() {
                 self.node.child_stop(child_idx);
             }
         }
@@ -93,12 +94,6 @@ impl Actor for Any {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
-                self.reply(ctx, &BehaviorTick, &frame).await?;
-            }
-        }
-        Ok(())
+        ctx.serve(self).await
     }
 }
@@ -9,7 +9,8 @@ use tracing::debug;
 /// The `Fallback` composite node processes its children one by one in order. It returns success as soon as one
 /// child node succeeds. If a child fails, it proceeds to the next one. If all children fail,
 /// then the `Fallback` node fails.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+#[derive(Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(BehaviorTick)]
 pub struct Fallback {
     #[serde(skip)]
     #[builder(skip)]
@@ -39,7 +40,7 @@ impl ActorFactory for FallbackFactory {
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("FallbackFactory::spawn: start {}", ctx.id());
-            actor.start(&mut ctx).await?;
+            ctx.run(&mut actor).await?;
             debug!("FallbackFactory::spawn: end {}", ctx.id());
             Ok(())
         }))
@@ -71,12 +72,6 @@ impl Actor for Fallback {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
-                self.reply(ctx, &BehaviorTick, &frame).await?;
-            }
-        }
-        Ok(())
+        ctx.serve(self).await
     }
 }
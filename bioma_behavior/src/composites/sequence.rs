@@ -9,7 +9,8 @@ use tracing::debug;
 /// The `Sequence` composite node processes its children one by one in order. It returns success only if
 /// all child nodes succeed. If a child fails, the `Sequence` node immediately fails. If a child
 /// returns running, the `Sequence` node also returns running.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+#[derive(Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(BehaviorTick)]
 pub struct Sequence {
     #[serde(skip)]
     #[builder(skip)]
@@ -39,7 +40,7 @@ impl ActorFactory for SequenceFactory {
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("SequenceFactory::spawn: start {}", ctx.id());
-            actor.start(&mut ctx).await?;
+            ctx.run(&mut actor).await?;
             debug!("SequenceFactory::spawn: end {}", ctx.id());
             Ok(())
         }))
@@ -74,13 +75,6 @@ impl Actor for Sequence {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
-                self.reply(ctx, &BehaviorTick, &frame).await?;
-                break;
-            }
-        }
-        Ok(())
+        ctx.serve_once(self).await
     }
 }
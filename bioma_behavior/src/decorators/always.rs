@@ -8,7 +8,8 @@ use tracing::debug;
 ///
 /// The `Always` decorator node executes its child node but always returns the
 /// configured status (Success or Failure), ignoring the child's result.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+#[derive(Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(BehaviorTick)]
 pub struct Always {
     pub success: bool,
     #[serde(skip)]
@@ -38,7 +39,7 @@ impl ActorFactory for AlwaysFactory {
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("AlwaysFactory::spawn: start {}", ctx.id());
-            actor.start(&mut ctx).await?;
+            ctx.run(&mut actor).await?;
             debug!("AlwaysFactory::spawn: end {}", ctx.id());
             Ok(())
         }))
@@ -66,11 +67,7 @@ impl Actor for Always {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            self.reply(ctx, &BehaviorTick, &frame).await?;
-        }
-        Ok(())
+        ctx.serve(self).await
     }
 }
 
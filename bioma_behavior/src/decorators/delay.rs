@@ -8,8 +8,9 @@ use tracing::debug;
 /// Delays execution before proceeding with its child node.
 ///
 /// The `Delay` decorator node pauses for a specified duration before executing its child node. It returns the result
-/// of the child node's execution.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+/// of the child node's execution. A cancelled tick stops the delay, and is passed on to the child.
+#[derive(Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(BehaviorTick)]
 pub struct Delay {
     #[serde(with = "humantime_serde")]
     pub duration: Duration,
@@ -40,7 +41,7 @@ impl ActorFactory for DelayFactory {
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("DelayFactory::spawn: start {}", ctx.id());
-            actor.start(&mut ctx).await?;
+            ctx.run(&mut actor).await?;
             debug!("DelayFactory::spawn: end {}", ctx.id());
             Ok(())
         }))
@@ -51,7 +52,11 @@ impl Message<BehaviorTick> for Delay {
     type Response = BehaviorStatus;
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorTick) -> Result<(), Self::Error> {
-        tokio::time::sleep(self.duration).await;
+        // Nobody waits for the status of a cancelled tick
+        let cancel = ctx.cancellation_token();
+        if cancel.run_until_cancelled(tokio::time::sleep(self.duration)).await.is_none() {
+            return Ok(());
+        }
 
         let Some(child) = self.node.child(ctx, SpawnOptions::default()).await? else {
             ctx.reply(BehaviorStatus::Success).await?;
@@ -74,10 +79,6 @@ impl Actor for Delay {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            self.reply(ctx, &BehaviorTick, &frame).await?;
-        }
-        Ok(())
+        ctx.serve(self).await
     }
 }
@@ -8,7 +8,8 @@ use tracing::debug;
 ///
 /// The `Invert` decorator node executes its child node and then inverts the result:
 /// Success becomes Failure, Failure becomes Success, and Running remains unchanged.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+#[derive(Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(BehaviorTick)]
 pub struct Invert {
     #[serde(skip)]
     #[builder(skip)]
@@ -37,7 +38,7 @@ impl ActorFactory for InvertFactory {
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("InvertFactory::spawn: start {}", ctx.id());
-            actor.start(&mut ctx).await?;
+            ctx.run(&mut actor).await?;
             debug!("InvertFactory::spawn: end {}", ctx.id());
             Ok(())
         }))
@@ -72,10 +73,6 @@ impl Actor for Invert {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            self.reply(ctx, &BehaviorTick, &frame).await?;
-        }
-        Ok(())
+        ctx.serve(self).await
     }
 }
@@ -11,7 +11,8 @@ use tracing::debug;
 /// The `Timeout` decorator node attempts to execute its child node within a specified duration.
 /// If the child node completes before the timeout, it returns the child's result.
 /// If the timeout occurs first, it returns a failure status.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+#[derive(Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(BehaviorTick)]
 pub struct Timeout {
     #[serde(with = "humantime_serde")]
     pub duration: Duration,
@@ -42,7 +43,7 @@ impl ActorFactory for TimeoutFactory {
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("TimeoutFactory::spawn: start {}", ctx.id());
-            actor.start(&mut ctx).await?;
+            ctx.run(&mut actor).await?;
             debug!("TimeoutFactory::spawn: end {}", ctx.id());
             Ok(())
         }))
@@ -82,10 +83,6 @@ impl Actor for Timeout {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            self.reply(ctx, &BehaviorTick, &frame).await?;
-        }
-        Ok(())
+        ctx.serve(self).await
     }
 }
@@ -8,7 +8,7 @@ pub mod decorators;
 
 pub mod prelude {
     pub use crate::actions;
-    pub use crate::behavior::{self, Behavior, BehaviorCancel, BehaviorStatus, BehaviorTick};
+    pub use crate::behavior::{self, Behavior, BehaviorStatus, BehaviorTick};
     pub use crate::composites;
     pub use crate::decorators;
     pub use crate::error::BehaviorError;
@@ -169,7 +169,8 @@ impl Node {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles()]
 pub struct BehaviorTree {
     pub root: Node,
     pub logs: Vec<String>,
@@ -208,22 +209,11 @@ impl Actor for BehaviorTree {
         // Send a tick to the root
         let _ = ctx.do_send_as(BehaviorTick, &root_id).await;
 
-        let mut stream = ctx.recv().await?;
-        let result = Ok(());
-
-        loop {
-            tokio::select! {
-                Some(Ok(_frame)) = stream.next() => {
-                    // Handle the frame - continue loop after processing
-                },
-                root_result = &mut rx => {
-                    match root_result {
-                        Ok(_) => break,
-                        Err(_) => break,
-                    }
-                }
-            }
-        }
+        // The tree handles no messages, it serves until the root finishes
+        let result = tokio::select! {
+            result = ctx.serve(self) => result,
+            _ = &mut rx => Ok(()),
+        };
 
         debug!("BehaviorTree::start: end {}", ctx.id());
 
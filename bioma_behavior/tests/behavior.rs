@@ -1,5 +1,8 @@
+use actions::log::LogLevel::Info;
 use bioma_actor::prelude::*;
+use bioma_actor::testkit::TestKit;
 use bioma_behavior::prelude::*;
+use bioma_behavior::tree::Node;
 use std::io::Write;
 use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, Layer};
 
@@ -10,6 +13,47 @@ async fn test_behavior_basic() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_composite_serves_after_error() -> Result<(), Box<dyn std::error::Error>> {
+    // No behaviors are registered, so spawning the children fails on every tick
+    let mut kit = TestKit::new().await?;
+    let log_0 = actions::Log::builder().level(Info).text("Log 0".to_string()).build();
+    let log_0 = Node::from("log_0", log_0, vec![])?;
+    let Node::Composite(node) = Node::from("all_0", composites::All::builder().build(), vec![log_0])? else {
+        unreachable!("All is a composite");
+    };
+    let mut all = composites::All::builder().build();
+    all.node.copy_children(&node);
+    let all_id = kit.spawn("/all_0", all).await?;
+
+    for _ in 0..2 {
+        let status = kit
+            .relay()
+            .send_and_wait_reply::<composites::All, BehaviorTick>(BehaviorTick, &all_id, SendOptions::default())
+            .await;
+        assert!(matches!(status, Err(SystemActorError::MessageReply(_))), "unexpected status {:?}", status);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wait_cancelled() -> Result<(), Box<dyn std::error::Error>> {
+    let engine = Engine::with_backend(MemoryBackend::default(), EngineOptions::default())?;
+    let wait = actions::Wait::builder().duration(std::time::Duration::from_secs(3600)).build();
+    let wait_id = ActorId::of::<actions::Wait>("/wait_0");
+    let (mut wait_ctx, mut wait) = Actor::spawn(engine.clone(), wait_id.clone(), wait, SpawnOptions::default()).await?;
+    let wait_handle = tokio::spawn(async move { wait_ctx.run(&mut wait).await });
+    let (relay, _) = Actor::spawn(engine, ActorId::of::<Relay>("/relay"), Relay, SpawnOptions::default()).await?;
+
+    // The tick times out, the cancelled wait stops and the action ends
+    let options = SendOptions::builder().timeout(std::time::Duration::from_millis(200)).build();
+    let status = relay.send_and_wait_reply::<actions::Wait, BehaviorTick>(BehaviorTick, &wait_id, options).await;
+    assert!(matches!(status, Err(SystemActorError::MessageTimeout(_, _))), "unexpected status {:?}", status);
+    let ended = tokio::time::timeout(std::time::Duration::from_secs(2), wait_handle).await??;
+    assert!(ended.is_ok());
+    Ok(())
+}
+
 async fn run_behavior_tree_from_json(tree_json: &str) -> Result<Engine, Box<dyn std::error::Error>> {
     let engine = Engine::test().await?;
     bioma_behavior::register_behaviors(&engine.registry()).await?;
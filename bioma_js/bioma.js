@@ -41,7 +41,7 @@ class BiomaInterface {
         return actor;
     }
 
-    async sendMessage(tx, rx, name, message) {
+    async sendMessage(tx, rx, name, message, version = undefined) {
         const messageId = ulid();
         const recordId = new RecordId('message', messageId);
         const frame = {
@@ -50,6 +50,10 @@ class BiomaInterface {
             rx: rx.id,
             msg: message
         };
+        // Schema version of a message with a stable name, older versions are upgraded by the receiver
+        if (version !== undefined) {
+            frame.version = version;
+        }
 
         try {
             await this.db.create(recordId, frame);
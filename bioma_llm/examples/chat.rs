@@ -99,7 +99,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt().with_env_filter(filter).init();
 
     // Initialize the actor system
-    let engine = Engine::test().await?;
+    let engine = Engine::test_with(EngineOptions::load(&ConfigSources::default())?).await?;
 
     // Create actor IDs
     let main_id = ActorId::of::<MainActor>("/main");
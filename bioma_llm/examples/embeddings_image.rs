@@ -10,7 +10,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt().with_env_filter(filter).init();
 
     // Initialize the actor system
-    let engine = Engine::test().await?;
+    let engine = Engine::test_with(EngineOptions::load(&ConfigSources::default())?).await?;
 
     // Create actor ID
     let embeddings_id = ActorId::of::<Embeddings>("/embeddings");
@@ -11,7 +11,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt().with_env_filter(filter).init();
 
     // Initialize the actor system
-    let engine = Engine::test().await?;
+    let engine = Engine::test_with(EngineOptions::load(&ConfigSources::default())?).await?;
 
     // Create multiple rerank actors
     let num_rerank_actors = 3;
@@ -33,6 +33,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         rerank_handles.push(rerank_handle);
     }
 
+    // Spawn a router spreading the queries between the rerank actors
+    let router_id = ActorId::of::<Router>("/rerank");
+    let router = Router::builder().routees(rerank_actors).strategy(RoutingStrategy::LeastLoaded).build();
+    let (mut router_ctx, mut router) =
+        Actor::spawn(engine.clone(), router_id.clone(), router, SpawnOptions::default()).await?;
+    tokio::spawn(async move {
+        if let Err(e) = router.start(&mut router_ctx).await {
+            error!("Router error: {}", e);
+        }
+    });
+
     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
 
     // Spawn a relay actor to connect to rerank actors
@@ -82,14 +93,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         "The Statue of Liberty was a gift from France to the United States.",
     ];
 
-    // Distribute queries among rerank actors
+    // Send all queries to the router, which distributes them among rerank actors
     let mut rerank_futures = Vec::new();
 
-    for (i, query) in queries.iter().enumerate() {
-        let rerank_id = &rerank_actors[i % num_rerank_actors];
+    for query in queries.iter() {
         let future = relay_ctx.send_and_wait_reply::<Rerank, RankTexts>(
             RankTexts::builder().query(query.to_string()).texts(texts.iter().map(|s| s.to_string()).collect()).build(),
-            rerank_id,
+            &router_id,
             SendOptions::builder().timeout(std::time::Duration::from_secs(100)).build(),
         );
         rerank_futures.push(future);
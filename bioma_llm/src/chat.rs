@@ -53,7 +53,8 @@ impl From<OllamaError> for ChatError {
 
 impl ActorError for ChatError {}
 
-#[derive(bon::Builder, Debug, Clone, Serialize, Deserialize)]
+#[derive(bon::Builder, Debug, Clone, Serialize, Deserialize, ActorHandlers)]
+#[handles(ChatMessages)]
 pub struct Chat {
     #[builder(default = DEFAULT_MODEL_NAME.into())]
     pub model: Cow<'static, str>,
@@ -103,7 +104,8 @@ impl Schema {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.ChatMessages")]
 pub struct ChatMessages {
     pub messages: Vec<ChatMessage>,
     pub restart: bool,
@@ -157,8 +159,9 @@ impl Message<ChatMessages> for Chat {
             let mut stream = self.ollama.send_chat_messages_stream(chat_message_request).await?;
             let mut accumulated_content = String::new();
 
-            // Stream responses back to caller
-            while let Some(response) = stream.next().await {
+            // Stream responses back to caller, until the caller stops waiting for them
+            let cancel = ctx.cancellation_token();
+            while let Some(Some(response)) = cancel.run_until_cancelled(stream.next()).await {
                 match response {
                     Ok(chunk) => {
                         // Send chunk through actor's reply mechanism
@@ -192,8 +195,14 @@ impl Message<ChatMessages> for Chat {
                 }
             }
         } else {
-            // Send the messages to the ollama client
-            let result = self.ollama.send_chat_messages(chat_message_request).await?;
+            // Send the messages to the ollama client, unless the caller stops waiting for the reply
+            let cancel = ctx.cancellation_token();
+            let Some(result) = cancel.run_until_cancelled(self.ollama.send_chat_messages(chat_message_request)).await
+            else {
+                info!("Chat cancelled");
+                return Ok(());
+            };
+            let result = result?;
 
             // Add the response message to the history only if its an assistant message
             if result.message.role == ollama_rs::generation::chat::MessageRole::Assistant {
@@ -226,15 +235,7 @@ impl Actor for Chat {
 
         self.init(ctx).await?;
 
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(chat_messages) = frame.is::<ChatMessages>() {
-                let response = self.reply(ctx, &chat_messages, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
-            }
-        }
+        ctx.serve(self).await?;
         info!("{} Finished", ctx.id());
         Ok(())
     }
@@ -159,7 +159,8 @@ pub struct EmbeddingRequest {
 }
 
 /// Store embeddings for texts or images
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.StoreEmbeddings")]
 pub struct StoreEmbeddings {
     /// The content to embed (either texts or images)
     pub content: EmbeddingContent,
@@ -168,7 +169,8 @@ pub struct StoreEmbeddings {
 }
 
 /// Generate embeddings for texts or images
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.GenerateEmbeddings")]
 pub struct GenerateEmbeddings {
     /// The content to embed (either texts or images)
     pub content: EmbeddingContent,
@@ -194,7 +196,8 @@ pub enum Query {
 }
 
 /// Get the top k similar embeddings to a query
-#[derive(Builder, Debug, Clone, Serialize, Deserialize)]
+#[derive(Builder, Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.TopK")]
 pub struct TopK {
     /// The query to search for
     pub query: Query,
@@ -215,7 +218,8 @@ pub struct Similarity {
     pub metadata: Option<Value>,
 }
 
-#[derive(bon::Builder, Debug, Serialize, Deserialize)]
+#[derive(bon::Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(StoreEmbeddings, GenerateEmbeddings, TopK)]
 pub struct Embeddings {
     pub table_name_prefix: Option<String>,
     #[builder(default = Model::NomicEmbedTextV15)]
@@ -286,12 +290,10 @@ impl Message<TopK> for Embeddings {
             }
         };
 
-        let db = ctx.engine().db();
+        let db = ctx.engine().db()?;
         let query_sql = include_str!("../sql/similarities.surql").replace("{top_k}", &message.k.to_string());
 
         let mut results = db
-            .lock()
-            .await
             .query(query_sql)
             .bind(("query", query_embedding))
             .bind(("threshold", message.threshold))
@@ -318,7 +320,7 @@ impl Message<StoreEmbeddings> for Embeddings {
 
         let embeddings = rx.await??;
 
-        let db = ctx.engine().db();
+        let db = ctx.engine().db()?;
         let emb_query = include_str!("../sql/embeddings.surql");
 
         let mut embeddings_ids: Vec<RecordId> = Vec::new();
@@ -338,8 +340,6 @@ impl Message<StoreEmbeddings> for Embeddings {
             };
 
             let mut results = db
-                .lock()
-                .await
                 .query(emb_query)
                 .bind(("embedding", embedding))
                 .bind(("metadata", metadata))
@@ -436,26 +436,7 @@ impl Actor for Embeddings {
 
         self.init(ctx).await?;
 
-        // Start the message stream
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(input) = frame.is::<StoreEmbeddings>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
-            } else if let Some(input) = frame.is::<GenerateEmbeddings>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
-            } else if let Some(input) = frame.is::<TopK>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
-            }
-        }
+        ctx.serve(self).await?;
         info!("{} Finished", ctx.id());
         Ok(())
     }
@@ -527,13 +508,11 @@ impl Embeddings {
                     .replace("{dim}", &text_model_info.dim.to_string());
 
                 // Execute the schema definition
-                let db = ctx.engine().db();
-                db.lock().await.query(&schema_def).await.map_err(SystemActorError::from)?;
+                let db = ctx.engine().db()?;
+                db.query(&schema_def).await.map_err(SystemActorError::from)?;
 
                 // Store text model info in database if not already present
                 let model: Result<Option<Record>, _> = db
-                    .lock()
-                    .await
                     .create(("model", self.model.to_string()))
                     .content(text_model_info)
                     .await
@@ -544,8 +523,6 @@ impl Embeddings {
 
                 // Store image model info in database if not already present
                 let model: Result<Option<Record>, _> = db
-                    .lock()
-                    .await
                     .create(("model", self.image_model.to_string()))
                     .content(image_model_info)
                     .await
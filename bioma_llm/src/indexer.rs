@@ -53,7 +53,14 @@ pub enum IndexerError {
 
 impl ActorError for IndexerError {}
 
-#[derive(bon::Builder, Debug, Clone, Serialize, Deserialize)]
+/// Topic on which the indexer publishes `Indexed` after each `IndexGlobs` request
+pub const INDEXED_TOPIC: &str = "indexer/indexed";
+
+/// Topic on which the indexer publishes `DeletedSource` after each `DeleteSource` request
+pub const SOURCE_DELETED_TOPIC: &str = "indexer/source_deleted";
+
+#[derive(bon::Builder, Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.IndexGlobs")]
 pub struct IndexGlobs {
     pub globs: Vec<String>,
     #[builder(default = default_chunk_capacity())]
@@ -79,7 +86,8 @@ fn default_chunk_batch_size() -> usize {
     DEFAULT_CHUNK_BATCH_SIZE
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.Indexed")]
 pub struct Indexed {
     pub indexed: usize,
     pub cached: usize,
@@ -137,12 +145,14 @@ pub struct ContentSource {
     pub uri: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.DeleteSource")]
 pub struct DeleteSource {
     pub source: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.DeletedSource")]
 pub struct DeletedSource {
     pub deleted_embeddings: usize,
     pub deleted_sources: Vec<ContentSource>,
@@ -318,7 +328,7 @@ impl Message<IndexGlobs> for Indexer {
         let mut indexed = 0;
         let mut cached = 0;
 
-        for glob in message.globs.iter() {
+        'globs: for glob in message.globs.iter() {
             let local_store_dir = ctx.engine().local_store_dir();
             let full_glob = if std::path::Path::new(glob).is_absolute() {
                 glob.clone()
@@ -354,6 +364,12 @@ impl Message<IndexGlobs> for Indexer {
             };
 
             for pathbuf in paths {
+                // Stop early if the sender stopped waiting for the result
+                if ctx.is_cancelled() {
+                    info!("Indexing cancelled");
+                    break 'globs;
+                }
+
                 let uri = pathbuf.to_string_lossy().to_string();
                 let source = ContentSource { source: full_glob_clone.clone(), uri: uri.clone() };
 
@@ -362,9 +378,7 @@ impl Message<IndexGlobs> for Indexer {
                     format!("SELECT id.source AS source, id.uri AS uri FROM source:{{source: $source, uri: $uri}}");
                 let sources = ctx
                     .engine()
-                    .db()
-                    .lock()
-                    .await
+                    .db()?
                     .query(&query)
                     .bind(("source", source.source.clone()))
                     .bind(("uri", source.uri.clone()))
@@ -466,9 +480,7 @@ impl Message<IndexGlobs> for Indexer {
                 if !embeddings_ids.is_empty() {
                     let source_query = include_str!("../sql/source.surql");
                     ctx.engine()
-                        .db()
-                        .lock()
-                        .await
+                        .db()?
                         .query(*&source_query)
                         .bind(("source", source.source.clone()))
                         .bind(("uri", source.uri.clone()))
@@ -482,7 +494,9 @@ impl Message<IndexGlobs> for Indexer {
         }
 
         info!("Indexed {} paths, cached {} paths, in {:?}", indexed, cached, total_index_globs_time.elapsed());
-        ctx.reply(Indexed { indexed, cached }).await?;
+        let result = Indexed { indexed, cached };
+        ctx.reply(result.clone()).await?;
+        ctx.publish(INDEXED_TOPIC, result).await?;
         Ok(())
     }
 }
@@ -492,15 +506,10 @@ impl Message<DeleteSource> for Indexer {
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &DeleteSource) -> Result<(), IndexerError> {
         let query = include_str!("../sql/del_source.surql").replace("{prefix}", &self.embeddings.table_prefix());
-        let db = ctx.engine().db();
+        let db = ctx.engine().db()?;
 
-        let mut results = db
-            .lock()
-            .await
-            .query(&query)
-            .bind(("source", message.source.clone()))
-            .await
-            .map_err(SystemActorError::from)?;
+        let mut results =
+            db.query(&query).bind(("source", message.source.clone())).await.map_err(SystemActorError::from)?;
 
         let delete_result: DeletedSource = results
             .take::<Vec<DeletedSource>>(0)
@@ -520,12 +529,14 @@ impl Message<DeleteSource> for Indexer {
             }
         }
 
-        ctx.reply(delete_result).await?;
+        ctx.reply(delete_result.clone()).await?;
+        ctx.publish(SOURCE_DELETED_TOPIC, delete_result).await?;
         Ok(())
     }
 }
 
-#[derive(bon::Builder, Debug, Serialize, Deserialize)]
+#[derive(bon::Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(IndexGlobs, DeleteSource)]
 pub struct Indexer {
     pub embeddings: Embeddings,
     pub pdf_analyzer: PdfAnalyzer,
@@ -534,11 +545,7 @@ pub struct Indexer {
     pdf_analyzer_id: Option<ActorId>,
     markitdown_id: Option<ActorId>,
     #[serde(skip)]
-    pdf_analyzer_handle: Option<tokio::task::JoinHandle<()>>,
-    #[serde(skip)]
-    embeddings_handle: Option<tokio::task::JoinHandle<()>>,
-    #[serde(skip)]
-    markitdown_handle: Option<tokio::task::JoinHandle<()>>,
+    supervisor: Option<SupervisorHandle>,
 }
 
 impl Default for Indexer {
@@ -550,9 +557,7 @@ impl Default for Indexer {
             embeddings_id: None,
             pdf_analyzer_id: None,
             markitdown_id: None,
-            pdf_analyzer_handle: None,
-            embeddings_handle: None,
-            markitdown_handle: None,
+            supervisor: None,
         }
     }
 }
@@ -563,23 +568,7 @@ impl Actor for Indexer {
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), IndexerError> {
         self.init(ctx).await?;
 
-        // Start the message stream
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(input) = frame.is::<IndexGlobs>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
-            } else if let Some(input) = frame.is::<DeleteSource>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
-            }
-        }
-
-        Ok(())
+        ctx.serve(self).await
     }
 }
 
@@ -593,62 +582,33 @@ impl Indexer {
         self.pdf_analyzer_id = Some(pdf_analyzer_id.clone());
 
         // Spawn the pdf-analyzer actor
-        let (mut pdf_analyzer_ctx, mut pdf_analyzer_actor) = Actor::spawn(
-            ctx.engine().clone(),
-            pdf_analyzer_id.clone(),
-            self.pdf_analyzer.clone(),
-            SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
-        )
-        .await?;
-
-        // Start the pdf-analyzer actor
-        let pdf_analyzer_handle = tokio::spawn(async move {
-            if let Err(e) = pdf_analyzer_actor.start(&mut pdf_analyzer_ctx).await {
-                error!("PdfAnalyzer actor error: {}", e);
-            }
-        });
+        let pdf_analyzer_spec = ChildSpec::of(pdf_analyzer_id.clone(), self.pdf_analyzer.clone())
+            .options(SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build());
 
         // Generate child id for embeddings
         let embeddings_id = ActorId::of::<Embeddings>(format!("{}/embeddings", self_id.name()));
         self.embeddings_id = Some(embeddings_id.clone());
 
         // Spawn the embeddings actor
-        let (mut embeddings_ctx, mut embeddings_actor) = Actor::spawn(
-            ctx.engine().clone(),
-            embeddings_id.clone(),
-            self.embeddings.clone(),
-            SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
-        )
-        .await?;
-
-        // Start the embeddings actor
-        let embeddings_handle = tokio::spawn(async move {
-            if let Err(e) = embeddings_actor.start(&mut embeddings_ctx).await {
-                error!("Embeddings actor error: {}", e);
-            }
-        });
+        let embeddings_spec = ChildSpec::of(embeddings_id.clone(), self.embeddings.clone())
+            .options(SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build());
 
         // Generate child id for markitdown
         let markitdown_id = ActorId::of::<MarkitDown>(format!("{}/markitdown", self_id.name()));
         self.markitdown_id = Some(markitdown_id.clone());
 
-        let (mut markitdown_ctx, mut markitdown_actor) = Actor::spawn(
-            ctx.engine().clone(),
-            markitdown_id.clone(),
-            self.markitdown.clone(),
-            SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
-        )
-        .await?;
-
-        let markitdown_handle = tokio::spawn(async move {
-            if let Err(e) = markitdown_actor.start(&mut markitdown_ctx).await {
-                error!("MarkitDown actor error: {}", e);
-            }
-        });
-
-        self.pdf_analyzer_handle = Some(pdf_analyzer_handle);
-        self.embeddings_handle = Some(embeddings_handle);
-        self.markitdown_handle = Some(markitdown_handle);
+        // Spawn the markitdown actor
+        let markitdown_spec = ChildSpec::of(markitdown_id.clone(), self.markitdown.clone())
+            .options(SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build());
+
+        // Supervise the children, restarting them if they fail
+        let supervisor = Supervisor::new(ctx.engine().clone(), SupervisorOptions::default())
+            .child(pdf_analyzer_spec)
+            .child(embeddings_spec)
+            .child(markitdown_spec)
+            .spawn()
+            .await?;
+        self.supervisor = Some(supervisor);
 
         info!("Indexer ready");
 
@@ -1,7 +1,7 @@
 use bioma_actor::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use tracing::{error, info};
+use tracing::info;
 use url::Url;
 
 #[derive(Deserialize)]
@@ -9,7 +9,8 @@ struct MarkitDownServerResponse {
     text_content: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.AnalyzeMCFile")]
 pub struct AnalyzeMCFile {
     pub file_path: PathBuf,
 }
@@ -28,7 +29,8 @@ pub enum MarkitDownError {
 
 impl ActorError for MarkitDownError {}
 
-#[derive(bon::Builder, Debug, Serialize, Deserialize, Clone)]
+#[derive(bon::Builder, Debug, Serialize, Deserialize, Clone, ActorHandlers)]
+#[handles(AnalyzeMCFile)]
 pub struct MarkitDown {
     #[builder(default = Url::parse("http://localhost:5001").unwrap())]
     pub markitdown_url: Url,
@@ -81,16 +83,7 @@ impl Actor for MarkitDown {
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
         info!("{} Started", ctx.id());
 
-        let mut stream = ctx.recv().await?;
-
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(input) = frame.is::<AnalyzeMCFile>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
-            }
-        }
+        ctx.serve(self).await?;
 
         info!("{} Finished", ctx.id());
         Ok(())
@@ -1,7 +1,7 @@
 use bioma_actor::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use tracing::{error, info};
+use tracing::info;
 use url::Url;
 
 #[derive(Deserialize)]
@@ -28,7 +28,8 @@ fn convert_pdf_json_to_markdown(json_data: &Vec<JsonDataFromPdf>) -> Result<Stri
     Ok(markdown)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.AnalyzePdf")]
 pub struct AnalyzePdf {
     pub file_path: PathBuf,
 }
@@ -47,7 +48,8 @@ pub enum PdfAnalyzerError {
 
 impl ActorError for PdfAnalyzerError {}
 
-#[derive(bon::Builder, Debug, Serialize, Deserialize, Clone)]
+#[derive(bon::Builder, Debug, Serialize, Deserialize, Clone, ActorHandlers)]
+#[handles(AnalyzePdf)]
 pub struct PdfAnalyzer {
     #[builder(default = Url::parse("http://localhost:5060").unwrap())]
     pub pdf_analyzer_url: Url,
@@ -100,16 +102,7 @@ impl Actor for PdfAnalyzer {
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
         info!("{} Started", ctx.id());
 
-        let mut stream = ctx.recv().await?;
-
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(input) = frame.is::<AnalyzePdf>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
-            }
-        }
+        ctx.serve(self).await?;
 
         info!("{} Finished", ctx.id());
         Ok(())
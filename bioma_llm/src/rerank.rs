@@ -27,7 +27,8 @@ pub enum RerankError {
 
 impl ActorError for RerankError {}
 
-#[derive(Builder, Debug, Clone, Serialize, Deserialize)]
+#[derive(Builder, Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.RankTexts")]
 pub struct RankTexts {
     /// The query text to compare against the corpus of texts
     ///
@@ -148,7 +149,8 @@ impl std::fmt::Debug for StrongSharedRerank {
     }
 }
 
-#[derive(bon::Builder, Debug, Serialize, Deserialize)]
+#[derive(bon::Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(RankTexts)]
 pub struct Rerank {
     #[builder(default = Model::BGERerankerV2M3)]
     pub model: Model,
@@ -199,16 +201,7 @@ impl Actor for Rerank {
 
         self.init(ctx).await?;
 
-        // Start the message stream
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(rank_texts) = frame.is::<RankTexts>() {
-                let response = self.reply(ctx, &rank_texts, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
-            }
-        }
+        ctx.serve(self).await?;
         info!("{} Finished", ctx.id());
         Ok(())
     }
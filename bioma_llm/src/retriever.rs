@@ -34,7 +34,8 @@ pub enum RetrieverError {
 
 impl ActorError for RetrieverError {}
 
-#[derive(bon::Builder, Debug, Clone, Serialize, Deserialize)]
+#[derive(bon::Builder, Debug, Clone, Serialize, Deserialize, MessageName)]
+#[message(name = "bioma.llm.RetrieveContext")]
 pub struct RetrieveContext {
     /// The query to search for
     #[serde(flatten)]
@@ -231,7 +232,8 @@ impl Message<RetrieveContext> for Retriever {
     }
 }
 
-#[derive(bon::Builder, Debug, Serialize, Deserialize)]
+#[derive(bon::Builder, Debug, Serialize, Deserialize, ActorHandlers)]
+#[handles(RetrieveContext)]
 pub struct Retriever {
     #[builder(default)]
     pub embeddings: Embeddings,
@@ -240,9 +242,7 @@ pub struct Retriever {
     embeddings_id: Option<ActorId>,
     rerank_id: Option<ActorId>,
     #[serde(skip)]
-    embeddings_handle: Option<tokio::task::JoinHandle<()>>,
-    #[serde(skip)]
-    rerank_handle: Option<tokio::task::JoinHandle<()>>,
+    supervisor: Option<SupervisorHandle>,
 }
 
 impl Default for Retriever {
@@ -252,8 +252,7 @@ impl Default for Retriever {
             rerank: Rerank::default(),
             embeddings_id: None,
             rerank_id: None,
-            embeddings_handle: None,
-            rerank_handle: None,
+            supervisor: None,
         }
     }
 }
@@ -264,17 +263,7 @@ impl Actor for Retriever {
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), RetrieverError> {
         self.init(ctx).await?;
 
-        // Start the message stream
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(input) = frame.is::<RetrieveContext>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
-            }
-        }
-        Ok(())
+        ctx.serve(self).await
     }
 }
 
@@ -288,39 +277,20 @@ impl Retriever {
         self.rerank_id = Some(rerank_id.clone());
 
         // Spawn the embeddings actor
-        let (mut embeddings_ctx, mut embeddings_actor) = Actor::spawn(
-            ctx.engine().clone(),
-            embeddings_id.clone(),
-            self.embeddings.clone(),
-            SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
-        )
-        .await?;
+        let embeddings_spec = ChildSpec::of(embeddings_id.clone(), self.embeddings.clone())
+            .options(SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build());
 
         // Spawn the rerank actor
-        let (mut rerank_ctx, mut rerank_actor) = Actor::spawn(
-            ctx.engine().clone(),
-            rerank_id.clone(),
-            self.rerank.clone(),
-            SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
-        )
-        .await?;
-
-        // Start the embeddings actor
-        let embeddings_handle = tokio::spawn(async move {
-            if let Err(e) = embeddings_actor.start(&mut embeddings_ctx).await {
-                error!("Embeddings actor error: {}", e);
-            }
-        });
-
-        // Start the rerank actor
-        let rerank_handle = tokio::spawn(async move {
-            if let Err(e) = rerank_actor.start(&mut rerank_ctx).await {
-                error!("Rerank actor error: {}", e);
-            }
-        });
-
-        self.embeddings_handle = Some(embeddings_handle);
-        self.rerank_handle = Some(rerank_handle);
+        let rerank_spec = ChildSpec::of(rerank_id.clone(), self.rerank.clone())
+            .options(SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build());
+
+        // Supervise the children, restarting them if they fail
+        let supervisor = Supervisor::new(ctx.engine().clone(), SupervisorOptions::default())
+            .child(embeddings_spec)
+            .child(rerank_spec)
+            .spawn()
+            .await?;
+        self.supervisor = Some(supervisor);
 
         info!("Retriever ready");
 
@@ -268,9 +268,7 @@ async fn test_embeddings_top_k_similarities() -> Result<(), TestError> {
     let uri = "test_uri.test";
 
     engine
-        .db()
-        .lock()
-        .await
+        .db()?
         .query(source_query)
         .bind(("source", source))
         .bind(("uri", uri))
@@ -345,9 +343,7 @@ async fn test_embeddings_persistence() -> Result<(), TestError> {
     let uri = "test_uri.test";
 
     engine
-        .db()
-        .lock()
-        .await
+        .db()?
         .query(source_query)
         .bind(("source", source))
         .bind(("uri", uri))
@@ -440,9 +436,7 @@ async fn test_embeddings_with_metadata() -> Result<(), TestError> {
     let uri = "test_uri.test";
 
     engine
-        .db()
-        .lock()
-        .await
+        .db()?
         .query(source_query)
         .bind(("source", source))
         .bind(("uri", uri))
@@ -563,9 +557,7 @@ async fn test_embeddings_pool() -> Result<(), TestError> {
             let prefix = table_prefixes[i].clone();
 
             engine
-                .db()
-                .lock()
-                .await
+                .db()?
                 .query(source_query)
                 .bind(("source", source))
                 .bind(("uri", uri))
@@ -701,9 +693,7 @@ async fn test_image_embeddings_store_and_search() -> Result<(), TestError> {
 
     let source_query = include_str!("../sql/source.surql");
     engine
-        .db()
-        .lock()
-        .await
+        .db()?
         .query(source_query)
         .bind(("source", "test_source.test"))
         .bind(("uri", "test_uri.test"))
@@ -779,9 +769,7 @@ async fn test_embeddings_cross_modal_search() -> Result<(), TestError> {
     let source_query = include_str!("../sql/source.surql");
 
     engine
-        .db()
-        .lock()
-        .await
+        .db()?
         .query(source_query)
         .bind(("source", "test_source.test"))
         .bind(("uri", "test_uri.test"))
@@ -894,9 +882,7 @@ async fn test_embeddings_mixed_modal_storage() -> Result<(), TestError> {
 
     let source_query = include_str!("../sql/source.surql");
     engine
-        .db()
-        .lock()
-        .await
+        .db()?
         .query(source_query)
         .bind(("source", "test_source_image.test"))
         .bind(("uri", "test_uri_image.test"))
@@ -918,9 +904,7 @@ async fn test_embeddings_mixed_modal_storage() -> Result<(), TestError> {
         .await?;
 
     engine
-        .db()
-        .lock()
-        .await
+        .db()?
         .query(source_query)
         .bind(("source", "test_source_text.test"))
         .bind(("uri", "test_uri_text.test"))
@@ -997,9 +981,7 @@ async fn test_embeddings_source_filtering() -> Result<(), TestError> {
         // Create source record for each source
         let source_query = include_str!("../sql/source.surql");
         engine
-            .db()
-            .lock()
-            .await
+            .db()?
             .query(source_query)
             .bind(("source", source))
             .bind(("uri", source))
@@ -1139,9 +1121,7 @@ async fn test_base64_image_embeddings() -> Result<(), TestError> {
         // Create source record
         let source_query = include_str!("../sql/source.surql");
         engine
-            .db()
-            .lock()
-            .await
+            .db()?
             .query(source_query)
             .bind(("source", format!("base64_test_{}.test", i)))
             .bind(("uri", format!("base64_uri_{}.test", i)))
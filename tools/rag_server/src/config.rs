@@ -1,4 +1,5 @@
 use anyhow::Result;
+use bioma_actor::{ConfigSources, EngineOptions};
 use bioma_tool::client::ServerConfig;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
@@ -8,8 +9,8 @@ use tracing::info;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    #[serde(default = "default_engine_endpoint")]
-    pub engine_endpoint: Cow<'static, str>,
+    #[serde(flatten)]
+    pub engine: EngineOptions,
     #[serde(default = "default_rag_endpoint")]
     pub rag_endpoint: Cow<'static, str>,
     #[serde(default = "default_chat_model")]
@@ -43,7 +44,7 @@ answer the user's query:
 impl Default for Config {
     fn default() -> Self {
         Self {
-            engine_endpoint: default_engine_endpoint(),
+            engine: EngineOptions::builder().endpoint(default_engine_endpoint()).build(),
             rag_endpoint: default_rag_endpoint(),
             chat_model: default_chat_model(),
             chat_prompt: default_chat_prompt(),
@@ -54,26 +55,31 @@ impl Default for Config {
 
 #[derive(Parser)]
 pub struct Args {
+    /// Config file, TOML or JSON
     #[arg(short, long)]
     pub config: Option<PathBuf>,
+    /// Overrides a config value, e.g. `--set chat_model=llama3.2`
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub overrides: Vec<String>,
 }
 
 impl Args {
+    /// Loads the config file, then the `BIOMA_*` environment variables, then the overrides.
     pub fn load_config(&self) -> Result<Config> {
-        let config: Config = match &self.config {
-            Some(path) => {
-                let config = std::fs::read_to_string(path)?;
-                info!("Loaded config: {}", path.display());
-                serde_json::from_str::<Config>(&config)?
-            }
-            None => {
-                info!("Default config:");
-                Config::default()
-            }
-        };
-        info!("├─ Engine Endpoint: {}", config.engine_endpoint);
+        let sources = ConfigSources::builder()
+            .maybe_file(self.config.clone())
+            .overrides(self.overrides.clone())
+            // Renamed when the engine options were flattened into the config
+            .aliases(vec![("engine_endpoint".into(), "endpoint".into())])
+            .build();
+        let config = sources.load(&Config::default())?;
+        config.engine.validate()?;
+        info!("Config:");
+        info!("├─ Engine Endpoint: {}", config.engine.endpoint);
         info!("├─ Chat Model: {}", config.chat_model);
         info!("├─ Chat Prompt: {}...", config.chat_prompt.chars().take(50).collect::<String>());
+        info!("├─ Tracing: {:?}", config.engine.tracing.exporter);
+        info!("├─ Object Store: {:?}", config.engine.object_store);
         info!("├─ Tool Servers: {} configured", config.tool_servers.len());
         for (i, server) in config.tool_servers.iter().enumerate() {
             let prefix = if i == config.tool_servers.len() - 1 { "└──" } else { "├──" };
@@ -1,6 +1,6 @@
 use actix_cors::Cors;
 use actix_multipart::form::{json::Json as MpJson, tempfile::TempFile, MultipartForm};
-use actix_web::{middleware::Logger, web, App, HttpResponse, HttpServer, Responder};
+use actix_web::{middleware::Logger, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
 use base64::Engine as Base64Engine;
 use bioma_actor::prelude::*;
 use bioma_llm::prelude::*;
@@ -13,6 +13,7 @@ use ollama_rs::generation::tools::ToolCall;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::error::Error as StdError;
+use std::io::Read;
 use tool::Tools;
 use tracing::{debug, error, info};
 
@@ -72,6 +73,11 @@ async fn hello() -> impl Responder {
     HttpResponse::Ok().json("Hello world!")
 }
 
+async fn metrics(data: web::Data<AppState>) -> HttpResponse {
+    let body = data.engine.metrics().prometheus().await;
+    HttpResponse::Ok().content_type("text/plain; version=0.0.4").body(body)
+}
+
 async fn reset(data: web::Data<AppState>) -> HttpResponse {
     info!("Resetting the engine");
     let engine = data.engine.clone();
@@ -108,130 +114,101 @@ struct Uploaded {
     size: usize,
 }
 
+/// Object store path of a relative file path
+fn store_path(path: &std::path::Path) -> object_store::path::Path {
+    object_store::path::Path::from_iter(path.components().filter_map(|component| match component {
+        std::path::Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+        _ => None,
+    }))
+}
+
 async fn upload(MultipartForm(form): MultipartForm<Upload>, data: web::Data<AppState>) -> impl Responder {
-    let output_dir = data.engine.local_store_dir().clone();
+    let store = data.engine.store();
     let target_dir = form.metadata.path.clone();
 
-    // Determine the target path for the file
-    let temp_file_path = if form.file.file_name.as_ref().map_or(false, |name| name.ends_with(".zip")) {
-        if target_dir.extension().map_or(false, |ext| ext == "zip") {
-            output_dir.join(&target_dir)
-        } else {
-            let original_name =
-                form.file.file_name.as_ref().map(|name| name.to_string()).unwrap_or_else(|| "uploaded.zip".to_string());
-            output_dir.join(&target_dir).join(original_name)
-        }
-    } else {
-        output_dir.join(&target_dir)
-    };
-
-    // Create parent directory
-    if let Some(parent) = temp_file_path.parent() {
-        if let Err(e) = tokio::fs::create_dir_all(parent).await {
-            error!("Failed to create target directory: {}", e);
+    let content = match tokio::fs::read(form.file.file.path()).await {
+        Ok(content) => content,
+        Err(e) => {
+            error!("Failed to read uploaded file: {}", e);
             return HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to create target directory",
+                "error": "Failed to read uploaded file",
                 "details": e.to_string()
             }));
         }
-    }
-
-    // Create a temporary file for the upload
-    let temp_path = std::env::temp_dir().join(format!("upload_{}", uuid::Uuid::new_v4()));
-
-    // Copy the uploaded content to the temporary file using tokio
-    if let Err(e) = tokio::fs::copy(&form.file.file.path(), &temp_path).await {
-        error!("Failed to copy uploaded file: {}", e);
-        return HttpResponse::InternalServerError().json(json!({
-            "error": "Failed to copy uploaded file",
-            "details": e.to_string()
-        }));
-    }
+    };
 
-    let temp_path_clone = temp_path.clone();
-    let temp_file_path_clone = temp_file_path.clone();
+    let target_is_zip = target_dir.extension().map_or(false, |ext| ext == "zip");
+    let is_zip = target_is_zip || form.file.file_name.as_ref().map_or(false, |name| name.ends_with(".zip"));
 
-    if temp_file_path.extension().map_or(false, |ext| ext == "zip") {
-        // Handle zip file
+    // Files to store, the entries of a zip archive or the uploaded file
+    let files = if is_zip {
+        // Extract next to the archive, or into the target directory
+        let extract_to = if target_is_zip {
+            target_dir.parent().map(|parent| parent.to_path_buf()).unwrap_or_default()
+        } else {
+            target_dir.clone()
+        };
         let extract_result = tokio::task::spawn_blocking(
-            move || -> Result<Vec<std::path::PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
-                let file = std::fs::File::open(&temp_path_clone)?;
-                let mut archive = zip::ZipArchive::new(file)?;
-
-                // Extract to the parent directory of the zip file
-                let extract_to = temp_file_path_clone.parent().unwrap_or(&temp_file_path_clone);
-
-                // Get list of files that will be extracted
-                let mut extracted_files = Vec::new();
+            move || -> Result<Vec<(std::path::PathBuf, Vec<u8>)>, Box<dyn std::error::Error + Send + Sync>> {
+                let mut archive = zip::ZipArchive::new(std::io::Cursor::new(content))?;
+                let mut files = Vec::new();
                 for i in 0..archive.len() {
-                    let file = archive.by_index(i)?;
-                    if !file.name().ends_with('/') {
-                        // Skip directories
-                        let outpath = extract_to.join(file.name());
-                        extracted_files.push(outpath);
+                    let mut file = archive.by_index(i)?;
+                    // Skip directories, and entries outside of the archive
+                    let Some(name) = file.enclosed_name() else {
+                        continue;
+                    };
+                    if file.is_dir() {
+                        continue;
                     }
+                    let mut content = Vec::new();
+                    file.read_to_end(&mut content)?;
+                    files.push((extract_to.join(name), content));
                 }
-
-                // Now perform the extraction
-                archive.extract(extract_to)?;
-
-                Ok(extracted_files)
+                Ok(files)
             },
         )
         .await;
 
-        // Clean up temporary file
-        let _ = tokio::fs::remove_file(&temp_path).await;
-
         match extract_result {
-            Ok(Ok(files)) => HttpResponse::Ok().json(Uploaded {
-                message: format!("Zip file extracted {} files successfully", files.len()),
-                paths: files,
-                size: form.file.size,
-            }),
+            Ok(Ok(files)) => files,
             Ok(Err(e)) => {
                 error!("Error extracting zip file: {:?}", e);
-                HttpResponse::InternalServerError().json(json!({
+                return HttpResponse::InternalServerError().json(json!({
                     "error": "Failed to extract zip archive",
                     "details": e.to_string()
-                }))
+                }));
             }
             Err(e) => {
                 error!("Task error: {:?}", e);
-                HttpResponse::InternalServerError().json(json!({
+                return HttpResponse::InternalServerError().json(json!({
                     "error": "Internal server error",
                     "details": e.to_string()
-                }))
+                }));
             }
         }
     } else {
-        // Handle regular file
-        match tokio::fs::rename(&temp_path, &temp_file_path).await {
-            Ok(_) => HttpResponse::Ok().json(Uploaded {
-                message: "File uploaded successfully".to_string(),
-                paths: vec![temp_file_path],
-                size: form.file.size,
-            }),
-            Err(e) => {
-                error!("Error moving file: {:?}", e);
-                if let Err(copy_err) = tokio::fs::copy(&temp_path, &temp_file_path).await {
-                    error!("Error copying file: {:?}", copy_err);
-                    return HttpResponse::InternalServerError().json(json!({
-                        "error": "Failed to save uploaded file",
-                        "details": copy_err.to_string()
-                    }));
-                }
-                // Clean up source file after successful copy
-                let _ = tokio::fs::remove_file(&temp_path).await;
-
-                HttpResponse::Ok().json(Uploaded {
-                    message: "File uploaded successfully".to_string(),
-                    paths: vec![temp_file_path],
-                    size: form.file.size,
-                })
-            }
+        vec![(target_dir, content)]
+    };
+
+    let mut paths = Vec::new();
+    for (path, content) in files {
+        if let Err(e) = store.put(&store_path(&path), content.into()).await {
+            error!("Error storing file: {:?}", e);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to save uploaded file",
+                "details": e.to_string()
+            }));
         }
+        paths.push(path);
     }
+
+    let message = if is_zip {
+        format!("Zip file extracted {} files successfully", paths.len())
+    } else {
+        "File uploaded successfully".to_string()
+    };
+    HttpResponse::Ok().json(Uploaded { message, paths, size: form.file.size })
 }
 
 async fn index(body: web::Json<IndexGlobs>, data: web::Data<AppState>) -> HttpResponse {
@@ -568,12 +545,21 @@ struct AskResponse {
     context: Vec<ChatMessage>,
 }
 
-async fn ask(body: web::Json<AskQuery>, data: web::Data<AppState>) -> HttpResponse {
+async fn ask(req: HttpRequest, body: web::Json<AskQuery>, data: web::Data<AppState>) -> HttpResponse {
     let user_actor = match data.user_actor().await {
         Ok(actor) => actor,
         Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
     };
 
+    // Continue the trace of the caller, so the retriever and chat messages are correlated
+    let trace = req
+        .headers()
+        .get("traceparent")
+        .and_then(|value| value.to_str().ok())
+        .and_then(TraceContext::from_traceparent)
+        .unwrap_or_default();
+    info!("Ask trace: {}", trace.trace_id);
+
     let query = body
         .messages
         .iter()
@@ -595,7 +581,7 @@ async fn ask(body: web::Json<AskQuery>, data: web::Data<AppState>) -> HttpRespon
         .send_and_wait_reply::<Retriever, RetrieveContext>(
             retrieve_context,
             &data.retriever,
-            SendOptions::builder().timeout(std::time::Duration::from_secs(30)).build(),
+            SendOptions::builder().timeout(std::time::Duration::from_secs(30)).trace(trace.clone()).build(),
         )
         .await;
 
@@ -657,7 +643,7 @@ async fn ask(body: web::Json<AskQuery>, data: web::Data<AppState>) -> HttpRespon
                         tools: None,
                     },
                     &data.chat,
-                    SendOptions::builder().timeout(std::time::Duration::from_secs(60)).build(),
+                    SendOptions::builder().timeout(std::time::Duration::from_secs(60)).trace(trace).build(),
                 )
                 .await;
 
@@ -850,7 +836,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config = args.load_config()?;
 
     // Initialize engine
-    let engine = Engine::connect(EngineOptions::builder().endpoint(config.engine_endpoint.clone()).build()).await?;
+    let engine = Engine::connect(config.engine.clone()).await?;
 
     // Spawn main actors and their relays
     let mut actor_handles = Vec::new();
@@ -970,6 +956,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             )
             .route("/health", web::get().to(health))
             .route("/hello", web::post().to(hello))
+            .route("/metrics", web::get().to(metrics))
             .route("/reset", web::post().to(reset))
             .route("/index", web::post().to(index))
             .route("/retrieve", web::post().to(retrieve))